@@ -370,6 +370,11 @@ class Summarizer:
         "hdratio50": {"deg": 0.2, "opp": 0.0, "relationships": 0.0},
         "hdratioboot": {"deg": 0.2, "opp": 0.0, "relationships": 0.0},
     }
+    DEFAULT_DEG_BASELINE = {
+        "minrtt50": 0.1,
+        "hdratio50": 0.9,
+        "hdratioboot": 0.9,
+    }
     RELATIONSHIP_PAIR_NAMES = {
         (7, 8): "Peering vs Transit",
         (8, 8): "Transit vs Transit",
@@ -386,6 +391,7 @@ class Summarizer:
         pri_relationship,
         alt_relationship,
         hdratio_diff_ci,
+        baseline=None,
     ):
         self.metric = str(metric)
         self.summary = str(summary)
@@ -394,6 +400,7 @@ class Summarizer:
         self.diff_ci = float(diff_ci)
         # Only for DegradationSummarizer:
         self.base_ci = float(base_ci)
+        self.baseline = None if baseline is None else float(baseline)
         # Only for RelationshipSummarizer:
         self.pri_relationship = int(pri_relationship)
         self.alt_relationship = int(alt_relationship)
@@ -412,6 +419,7 @@ class Summarizer:
                 self.pri_relationship,
                 self.alt_relationship,
                 self.hdratio_diff_ci,
+                self.baseline,
             )
         )
 
@@ -421,7 +429,9 @@ class Summarizer:
     def __str__(self):
         lb = "true" if self.lb else "false"
         if self.summary == "deg":
-            return f"{self.metric}--{self.summary}--bound-{lb}--diff-thresh-{self.diff_thresh:0.2f}--diff-ci-{self.diff_ci:0.2f}--base-ci-{self.base_ci:0.2f}"
+            return f"{self.metric}--{self.summary}--bound-{lb}--diff-thresh-{self.diff_thresh:0.2f}--diff-ci-{self.diff_ci:0.2f}--base-ci-{self.base_ci:0.2f}" + (
+                "" if self.baseline is None else f"--baseline-{self.baseline:0.2f}"
+            )
         if self.summary == "opp":
             return f"{self.metric}--{self.summary}--bound-{lb}--diff-thresh-{self.diff_thresh:0.2f}--diff-ci-{self.diff_ci:0.2f}"
         if self.summary == "relationships":
@@ -429,7 +439,7 @@ class Summarizer:
 
     @staticmethod
     def parse(string):
-        deg_string = r"^(.+)--deg--bound-(.+)--diff-thresh-([0-9.]+)--diff-ci-([0-9.]+)--base-ci-([0-9.]+)$"
+        deg_string = r"^(.+)--deg--bound-(.+)--diff-thresh-([0-9.]+)--diff-ci-([0-9.]+)--base-ci-([0-9.]+)(?:--baseline-([0-9.]+))?$"
         opp_string = r"^(.+)--opp--bound-(.+)--diff-thresh-([0-9.]+)--diff-ci-([0-9.]+)(?-([0-9.]+))?$"
         rel_string = r"^(.+)--relationships-(.+)-(.+)--bound-(.+)--diff-thresh-([0-9.]+)--diff-ci-([0-9.]+)$"
         summary = "deg"
@@ -440,6 +450,7 @@ class Summarizer:
             diff_thresh = float(m.group(3))
             diff_ci = float(m.group(4))
             base_ci = float(m.group(5))
+            baseline = m.group(6)
             pri = 0
             alt = 0
             hdratio_diff_ci = 0.0
@@ -453,6 +464,7 @@ class Summarizer:
                 pri,
                 alt,
                 hdratio_diff_ci,
+                baseline,
             )
         summary = "opp"
         m = re.match(opp_string, string)
@@ -552,6 +564,7 @@ class Summarizer:
             pri_relationship,
             alt_relationship,
             hdratio_diff_ci,
+            Summarizer.DEFAULT_DEG_BASELINE[metric] if summary == "deg" else None,
         )
 
 
@@ -588,9 +601,27 @@ class TemporalPattern:
         "Continuous",
         "Diurnal",
         "Episodic",
+        "WeekdayDiurnal",
+        "WeekendDiurnal",
+        "Weekly",
+    ]
+    VALID = [
+        "Uneventful",
+        "Continuous",
+        "Diurnal",
+        "Episodic",
+        "WeekdayDiurnal",
+        "WeekendDiurnal",
+        "Weekly",
+    ]
+    WITH_SHIFTS = [
+        "Continuous",
+        "Diurnal",
+        "Episodic",
+        "WeekdayDiurnal",
+        "WeekendDiurnal",
+        "Weekly",
     ]
-    VALID = ["Uneventful", "Continuous", "Diurnal", "Episodic"]
-    WITH_SHIFTS = ["Continuous", "Diurnal", "Episodic"]
 
 
 CLIENT_CONTINENTS = ["AF", "AS", "EU", "NA", "OC", "SA"]
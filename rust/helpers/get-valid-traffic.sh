@@ -3,6 +3,10 @@ set -eu
 
 path=$1
 
-cat $1/path-summaries.txt \
-    | grep -Ee "(Uneventful|Continuous|Diurnal|Episodic)" \
-    | awk '{cnt += $9;}END{print cnt;}'
\ No newline at end of file
+awk -F, '
+    NR == 1 { for (i = 1; i <= NF; i++) col[$i] = i; next; }
+    $col["temporal_behavior"] ~ /(Uneventful|Continuous|Diurnal|Episodic|Weekly)/ {
+        cnt += $col["valid_bytes"];
+    }
+    END { print cnt; }
+' $path/path-summaries.csv
\ No newline at end of file
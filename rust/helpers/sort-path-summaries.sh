@@ -1,44 +1,38 @@
 #!/bin/bash
 set -eu
 
-# output file columns:
-# 0 sorting_key (see definitions below)
-# 1 vip_metro
-# 2 bgp_prefix
-# 3 cont
-# 4 cc
-# 5 distinct_shifts
-# 6 bad_bytes
-# 7 noroute_bytes
-# 8 shifted_bytes
-# 9 valid_bytes
-# 10 wideci_bytes
-# 11 bad_bins
-# 12 noroute_bins
-# 13 shifted_bins
-# 14 valid_bins
-# 15 wideci_bins
-# 16 temporal_behavior
-
-FIELDS="sorting_key vip_metro bgp_prefix cont cc distinct_shifts bad_bytes noroute_bytes shifted_bytes valid_bytes wideci_bytes bad_bins noroute_bins shifted_bins valid_bins wideci_bins temporal_behavior"
+# Output files have a sorting_key column followed by the columns of
+# path-summaries.csv (see PathSummary::COLUMNS); columns are looked up
+# by name in the header, so adding columns does not break sorting.
+# Classes are matched whole, so Diurnal excludes WeekdayDiurnal and
+# WeekendDiurnal.  perfstats writes ranked-paths-*.csv natively (see
+# ranking.rs); this script re-sorts existing outputs.
 
 # sorting key for continuous: shifted_bins/valid_bins
 # sorting key for diurnal: bad_bytes/shifted_bytes
 # sorting key for episodic: shifted_bins/distinct_shifts
 
-function sort_continuous {
+function sort_class {
     local fn=$1
-    grep Continuous $fn | awk '{print $13/$14,$0;}' | sort -k 1 -g
+    local class=$2
+    local num=$3
+    local den=$4
+    awk -F, -v class=$class -v num=$num -v den=$den '
+        NR == 1 { for (i = 1; i <= NF; i++) col[$i] = i; next; }
+        $col["temporal_behavior"] ~ ("^" class "$") { print $col[num]/$col[den] "," $0; }
+    ' $fn | sort -t, -k 1 -g
+}
+
+function sort_continuous {
+    sort_class $1 Continuous shifted_bins valid_bins
 }
 
 function sort_diurnal {
-    local fn=$1
-    grep Diurnal $fn | awk '{print $6/$8,$0;}' | sort -k 1 -g
+    sort_class $1 Diurnal bad_bytes shifted_bytes
 }
 
 function sort_episodic {
-    local fn=$1
-    grep Episodic $fn | awk '{print $13/$5,$0;}' | sort -k 1 -g
+    sort_class $1 Episodic shifted_bins distinct_shifts
 }
 
 OUTDIR=sorted-path-summaries
@@ -47,15 +41,14 @@ TEMPDIR=../output-5410-talk/tempconfig--bin-900--days-2--fracExisting-0.80--frac
 mkdir -p $OUTDIR
 basename $TEMPDIR > $OUTDIR/temp-config.txt
 
-CONFIGS="hdratio50--deg--bound-true--diff-thresh-0.10--diff-ci-0.10--base-ci-0.20:hdratio50-deg-0.10 hdratio50--opp--bound-true--diff-thresh-0.10--diff-ci-0.10:hdratio50-opp-0.10 minrtt50--deg--bound-true--diff-thresh-10.00--diff-ci-10.00--base-ci-20.00:minrtt50-deg-10.0 minrtt50--deg--bound-true--diff-thresh-5.00--diff-ci-10.00--base-ci-20.00:minrtt50-deg-5.0 minrtt50--opp--bound-true--diff-thresh-10.00--diff-ci-10.00--hdratio-diff-ci-0.10:minrtt50-opp-10.0"
+CONFIGS="hdratio50--deg--bound-true--diff-thresh-0.10--diff-ci-0.10--base-ci-0.20--baseline-0.90:hdratio50-deg-0.10 hdratio50--opp--bound-true--diff-thresh-0.10--diff-ci-0.10:hdratio50-opp-0.10 minrtt50--deg--bound-true--diff-thresh-10.00--diff-ci-10.00--base-ci-20.00--baseline-0.10:minrtt50-deg-10.0 minrtt50--deg--bound-true--diff-thresh-5.00--diff-ci-10.00--base-ci-20.00--baseline-0.10:minrtt50-deg-5.0 minrtt50--opp--bound-true--diff-thresh-10.00--diff-ci-10.00--hdratio-diff-ci-0.10:minrtt50-opp-10.0"
 
 for cfgspec in $CONFIGS ; do
     dir=${cfgspec%%:*}
     shortname=${cfgspec##*:}
     for behavior in continuous diurnal episodic ; do
         outfile=$OUTDIR/$shortname-$behavior.csv
-        echo $FIELDS > $outfile
-        sort_$behavior $TEMPDIR/$dir/path-summaries.txt >> $outfile
-        sed -i 's/ /,/g' $outfile
+        echo "sorting_key,$(head -n 1 $TEMPDIR/$dir/path-summaries.csv)" > $outfile
+        sort_$behavior $TEMPDIR/$dir/path-summaries.csv >> $outfile
     done
 done
@@ -282,7 +282,7 @@ mod tests {
             "10.0.2.0/23",
             "10.0.0.0/22",
         ]);
-        let output: HashSet<IpNet> = mkset(&["10.0.0.0/24", "10.0.2.0/24", "10.0.3.0/24"]);;
+        let output: HashSet<IpNet> = mkset(&["10.0.0.0/24", "10.0.2.0/24", "10.0.3.0/24"]);
         assert_eq!(noncovered_prefixes(input.iter()), output);
     }
 
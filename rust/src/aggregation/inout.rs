@@ -65,7 +65,7 @@ pub fn load_input(infn: &PathBuf) -> HashMap<u32, HashMap<IpNet, PrefixData>> {
     let mut rdr = csv::Reader::from_path(infn).unwrap();
     for result in rdr.deserialize() {
         let rtinfo: RouteInfo = result.unwrap();
-        let prefix2data = asn2prefix2data.entry(rtinfo.origin_asn).or_insert_with(HashMap::new);
+        let prefix2data = asn2prefix2data.entry(rtinfo.origin_asn).or_default();
         let pfxdata: &mut PrefixData =
             prefix2data.entry(rtinfo.prefix).or_insert_with(|| PrefixData::new(&rtinfo));
         pfxdata.timeseries.insert(rtinfo).unwrap();
@@ -29,7 +29,7 @@ impl<T: Timed> TimeSeries<T> {
         self.time2item.get(&time)
     }
 
-    pub fn iter(&self) -> btree_map::Iter<i64, T> {
+    pub fn iter(&self) -> btree_map::Iter<'_, i64, T> {
         self.time2item.iter()
     }
 }
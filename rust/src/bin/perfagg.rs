@@ -62,7 +62,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         asn2aggregated.insert(
             *asn,
             aggregate_prefixes(&noncovered, &|net1: &IpNet, net2: &IpNet| {
-                compare_prefixes(net1, net2, &prefix2data, &opts)
+                compare_prefixes(net1, net2, prefix2data, &opts)
             }),
         );
     }
@@ -1,20 +1,29 @@
 use std::borrow::Borrow;
-use std::collections::HashSet;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs;
-use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use crossbeam::sync::WaitGroup;
 use log::{error, info};
-use rayon;
 use structopt::StructOpt;
 
+use fbperf::performance::comparison;
 use fbperf::performance::db;
+use fbperf::performance::explain;
+use fbperf::performance::manifest;
 use fbperf::performance::perfstats;
 use fbperf::performance::perfstats::TimeBinSummarizer;
+use fbperf::performance::ranking;
+use fbperf::performance::sensitivity;
+use fbperf::performance::sink;
 use fbperf::performance::summarizers;
+use fbperf::performance::summarizers::registry::{self, SummarizerSpec};
+use fbperf::performance::sweep;
+use fbperf::performance::timeseries;
+use fbperf::performance::windows;
 
 #[derive(Clone, Debug, StructOpt)]
 #[structopt(
@@ -27,63 +36,166 @@ struct Opt {
     /// The input CSV file
     input_files: Vec<PathBuf>,
     #[structopt(long, parse(from_os_str))]
-    /// The output directory where to store files
-    outdir: PathBuf,
-    #[structopt(long, parse(from_os_str), default_value = "")]
-    pathid_dump_list_file: PathBuf,
+    /// The output directory where to store files (not needed by explain)
+    outdir: Option<PathBuf>,
+    #[structopt(long, parse(from_os_str))]
+    /// Dump timeseries of the paths listed in this file, one PathId per
+    /// line, e.g., "gru 1.0.0.0/24 SA BR"
+    timeseries_pathids: Option<PathBuf>,
+    #[structopt(long = "timeseries-metro", number_of_values = 1)]
+    /// Dump timeseries of paths whose VIP metro matches this glob, e.g., "gr*" (repeatable)
+    timeseries_metros: Vec<String>,
+    #[structopt(long = "timeseries-prefix", number_of_values = 1)]
+    /// Dump timeseries of paths covered by this prefix (repeatable)
+    timeseries_prefixes: Vec<ipnet::IpNet>,
+    #[structopt(long = "timeseries-country", number_of_values = 1)]
+    /// Dump timeseries of paths to clients in this country, e.g., BR (repeatable)
+    timeseries_countries: Vec<String>,
+    #[structopt(long = "timeseries-class", number_of_values = 1)]
+    /// Dump timeseries of paths in this temporal class, e.g., Diurnal (repeatable)
+    timeseries_classes: Vec<perfstats::TemporalBehavior>,
+    #[structopt(long)]
+    /// Dump timeseries of the selected paths with the most shifted bytes only
+    timeseries_top: Option<usize>,
+    #[structopt(long, parse(from_os_str))]
+    /// TOML file declaring the summarizers and temporal configs to run
+    /// (see helpers/sweep-example.toml); overrides the built-in sweep
+    config: Option<PathBuf>,
+    #[structopt(
+        long = "summarizer",
+        number_of_values = 1,
+        parse(try_from_str = SummarizerSpec::parse)
+    )]
+    /// Summarizer to run, e.g., minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true
+    /// (repeatable); overrides the summarizers in the built-in sweep or --config
+    summarizers: Vec<SummarizerSpec>,
+    #[structopt(long, default_value = "text,pickle,csv", use_delimiter = true)]
+    /// Formats for path summaries and temporal behavior tables: text,
+    /// pickle, csv, jsonl
+    formats: Vec<sink::OutputFormat>,
+    #[structopt(long)]
+    /// Save each DBSummary to summaries/<summarizer>.json.gz for the
+    /// reclassify tool
+    save_summaries: bool,
+    #[structopt(long)]
+    /// List the registered summarizers and their parameters, then exit
+    list_summarizers: bool,
+    #[structopt(long = "rank", number_of_values = 1)]
+    /// Ranking metric for a class in ranked-paths reports, e.g.,
+    /// Continuous=shifted_bins/valid_bins (repeatable); other classes
+    /// keep their defaults
+    ranks: Vec<String>,
+    #[structopt(long, default_value = "100")]
+    /// Paths with the most traffic ranked in each class
+    rank_top: usize,
     #[structopt(long, default_value = "900")]
     bin_duration_secs: u32,
     #[structopt(long, default_value = "4")]
     threads: usize,
+    #[structopt(long)]
+    /// Compare classes under a grid of TemporalConfigs against the first config
+    sensitivity: bool,
+    #[structopt(
+        long = "sensitivity-range",
+        number_of_values = 1,
+        parse(try_from_str = sensitivity::SensitivityRange::parse)
+    )]
+    /// Values of a TemporalConfig field for the --sensitivity grid,
+    /// e.g., diurnal_min_bad_bins=1,4,8 (repeatable); overrides the
+    /// [sensitivity] table in --config and the built-in ranges
+    sensitivity_ranges: Vec<sensitivity::SensitivityRange>,
+    #[structopt(long, default_value = "0")]
+    /// Classify paths over windows of this many days (0 disables)
+    window_days: u64,
+    #[structopt(long, default_value = "1")]
+    /// Days between the start of consecutive windows
+    window_step_days: u64,
+    #[structopt(long, default_value = "0")]
+    /// Permutations for the diurnal significance test (0 disables)
+    diurnal_permutations: u32,
+    #[structopt(long, default_value = "0.05")]
+    /// Maximum p-value for a path to be classified as diurnal
+    diurnal_max_pvalue: f32,
+    #[structopt(long, default_value = "1")]
+    /// Consecutive shifted bins required to enter the shifted state
+    shift_enter_min_bins: u16,
+    #[structopt(long, default_value = "1")]
+    /// Consecutive non-shifted bins required to leave the shifted state
+    shift_exit_min_bins: u16,
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
 }
 
-fn build_summarizers(db: &db::DB) -> Vec<Arc<dyn TimeBinSummarizer>> {
-    let max_minrtt50_ci_halfwidth: f32 = 20.0;
-    let max_hdratio50_ci_halfwidth: f32 = 0.20;
-    let mut summarizers: Vec<Arc<dyn TimeBinSummarizer>> = Vec::new();
-
-    for &max_minrtt50_diff_ci_halfwidth in [10.0f32].iter() {
-        for &min_minrtt50_diff in [5.0, 10.0, 20.0].iter() {
-            let ml = Arc::new(summarizers::opportunity::MinRtt50ImprovementSummarizer {
-                minrtt50_min_improv: min_minrtt50_diff,
-                max_minrtt50_diff_ci_halfwidth,
-                max_hdratio50_diff_ci_halfwidth: 0.1,
-                compare_lower_bound: true,
-            });
-            summarizers.push(ml);
-        }
-        for &min_minrtt50_diff in [5.0, 10.0, 20.0, 50.0].iter() {
-            let ml =
-                Arc::new(summarizers::degradation::MinRtt50LowerBoundDegradationSummarizer::new(
-                    0.1,
-                    min_minrtt50_diff,
-                    max_minrtt50_diff_ci_halfwidth,
-                    max_minrtt50_ci_halfwidth,
-                    db,
-                ));
-            summarizers.push(ml);
-        }
+#[derive(Clone, Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+enum Command {
+    /// Combine DBSummaries saved with --save-summaries by runs over
+    /// shards of the dataset and dump them under each temporal config
+    Merge {
+        #[structopt(parse(from_os_str))]
+        /// The saved DBSummary files
+        summary_files: Vec<PathBuf>,
+    },
+    /// Print why each bin of a path was summarized as it was and how
+    /// the path is classified under each temporal config
+    Explain {
+        #[structopt(long)]
+        /// The path to explain, e.g., "gru 1.0.0.0/24 SA BR"
+        pathid: String,
+        #[structopt(long, parse(try_from_str = SummarizerSpec::parse))]
+        /// The summarizer to explain, as for --summarizer
+        summarizer: SummarizerSpec,
+    },
+    /// Compare the bins and paths two summarizers find shifted, e.g.,
+    /// MinRTT P50 against P25, under each temporal config
+    Compare {
+        #[structopt(long, parse(try_from_str = SummarizerSpec::parse))]
+        /// The reference summarizer, as for --summarizer
+        reference: SummarizerSpec,
+        #[structopt(long, parse(try_from_str = SummarizerSpec::parse))]
+        /// The summarizer compared against the reference
+        other: SummarizerSpec,
+    },
+}
+
+/// The summarizers run when neither --config nor --summarizer is
+/// given; helpers/sweep-example.toml declares the same sweep.
+fn build_summarizer_specs() -> Vec<SummarizerSpec> {
+    let spec = |text: String| SummarizerSpec::parse(&text).unwrap();
+    let mut specs: Vec<SummarizerSpec> = Vec::new();
+
+    for thresh in [5.0, 10.0, 20.0].iter() {
+        specs.push(spec(format!(
+            "minrtt50-opp:thresh={},diff_ci=10,hdratio_diff_ci=0.1,bound=true",
+            thresh
+        )));
     }
-    for &max_hdratio50_diff_ci_halfwidth in [0.1f32].iter() {
-        for &min_hdratio_diff in [0.05, 0.1, 0.2].iter() {
-            let hl = Arc::new(summarizers::opportunity::HdRatio50ImprovementSummarizer {
-                hdratio50_min_improv: min_hdratio_diff,
-                max_hdratio50_diff_ci_halfwidth,
-                compare_lower_bound: true,
-            });
-            summarizers.push(hl);
-        }
-        for &min_hdratio_diff in [0.05, 0.1, 0.2, 0.5, 0.75].iter() {
-            let hl =
-                Arc::new(summarizers::degradation::HdRatio50LowerBoundDegradationSummarizer::new(
-                    0.9,
-                    min_hdratio_diff,
-                    max_hdratio50_diff_ci_halfwidth,
-                    max_hdratio50_ci_halfwidth,
-                    db,
-                ));
-            summarizers.push(hl);
-        }
+    for thresh in [5.0, 10.0, 20.0, 50.0].iter() {
+        specs.push(spec(format!(
+            "minrtt50-deg:baseline=0.1,thresh={},diff_ci=10,base_ci=20",
+            thresh
+        )));
+    }
+    for thresh in [5.0, 10.0, 20.0].iter() {
+        specs.push(spec(format!(
+            "minrtt50-distinct-deg:baseline=0.1,thresh={},diff_ci=10,base_ci=20,min_frac=0.8",
+            thresh
+        )));
+    }
+    for thresh in [0.05, 0.1, 0.2].iter() {
+        specs.push(spec(format!("hdratio50-opp:thresh={},diff_ci=0.1,bound=true", thresh)));
+    }
+    for thresh in [0.05, 0.1, 0.2, 0.5, 0.75].iter() {
+        specs.push(spec(format!(
+            "hdratio50-deg:baseline=0.9,thresh={},diff_ci=0.1,base_ci=0.2",
+            thresh
+        )));
+    }
+    for thresh in [0.05, 0.1, 0.2].iter() {
+        specs.push(spec(format!(
+            "hdratio50-distinct-deg:baseline=0.9,thresh={},diff_ci=0.1,base_ci=0.2,min_frac=0.8",
+            thresh
+        )));
     }
 
     let relationship_pairs = [
@@ -103,63 +215,87 @@ fn build_summarizers(db: &db::DB) -> Vec<Arc<dyn TimeBinSummarizer>> {
         ),
         (1u32 << db::PeerType::Transit as u8, 1u32 << db::PeerType::Transit as u8),
     ];
-    for &(primary_bitmask, alternate_bitmask) in &relationship_pairs {
-        let ml = Arc::new(summarizers::relationships::MinRtt50RelationshipSummarizer {
-            primary_bitmask,
-            alternate_bitmask,
-            minrtt50_min_improv: 5.0,
-            max_minrtt50_diff_ci_halfwidth: 10.0,
-            compare_lower_bound: true,
-        });
-        summarizers.push(ml);
-        let ml = Arc::new(summarizers::relationships::HdRatio50RelationshipSummarizer {
-            primary_bitmask,
-            alternate_bitmask,
-            hdratio50_min_improv: 0.05,
-            max_hdratio50_diff_ci_halfwidth: 0.2,
-            compare_lower_bound: true,
-        });
-        summarizers.push(ml);
+    for &(primary, alternate) in &relationship_pairs {
+        specs.push(spec(format!(
+            "minrtt50-rel:primary={},alternate={},thresh=5,diff_ci=10,bound=true",
+            primary, alternate
+        )));
+        specs.push(spec(format!(
+            "hdratio50-rel:primary={},alternate={},thresh=0.05,diff_ci=0.2,bound=true",
+            primary, alternate
+        )));
     }
-    summarizers
+    specs
 }
 
-fn build_temporal_configs() -> Vec<perfstats::TemporalConfig> {
-    let mut configs: Vec<perfstats::TemporalConfig> = Vec::new();
-    configs.push(perfstats::TemporalConfig {
-        bin_duration_secs: 900,
-        min_days: 2,
-        min_frac_existing_bins: 0.6,
-        min_frac_bins_with_alternate: 0.6,
-        min_frac_valid_bins: 0.6,
-        continuous_min_frac_shifted_bins: 0.75,
-        diurnal_min_bad_bins: 4,
-        diurnal_bad_bin_min_prob_shift: 0.5,
-        uneventful_max_frac_shifted_bins: 0.0,
-    });
-    configs.push(perfstats::TemporalConfig {
-        bin_duration_secs: 900,
-        min_days: 2,
-        min_frac_existing_bins: 0.6,
-        min_frac_bins_with_alternate: 0.6,
-        min_frac_valid_bins: 0.6,
-        continuous_min_frac_shifted_bins: 0.75,
-        diurnal_min_bad_bins: 1,
-        diurnal_bad_bin_min_prob_shift: 0.5,
-        uneventful_max_frac_shifted_bins: 0.0,
-    });
-    configs.push(perfstats::TemporalConfig {
-        bin_duration_secs: 900,
-        min_days: 2,
-        min_frac_existing_bins: 0.8,
-        min_frac_bins_with_alternate: 0.8,
-        min_frac_valid_bins: 0.8,
-        continuous_min_frac_shifted_bins: 0.90,
-        diurnal_min_bad_bins: 8,
-        diurnal_bad_bin_min_prob_shift: 0.8,
-        uneventful_max_frac_shifted_bins: 0.05,
-    });
-    configs
+fn build_temporal_configs(opts: &Opt) -> Vec<perfstats::TemporalConfig> {
+    let mut tempconfigs = vec![
+        perfstats::TemporalConfig {
+            bin_duration_secs: 900,
+            min_days: 2,
+            min_frac_existing_bins: 0.6,
+            min_frac_bins_with_alternate: 0.6,
+            min_frac_valid_bins: 0.6,
+            continuous_min_frac_shifted_bins: 0.75,
+            diurnal_min_bad_bins: 4,
+            diurnal_bad_bin_min_prob_shift: 0.5,
+            diurnal_permutations: opts.diurnal_permutations,
+            diurnal_max_pvalue: opts.diurnal_max_pvalue,
+            uneventful_max_frac_shifted_bins: 0.0,
+            weekly_min_weeks: 2,
+            weekly_min_autocorr: 0.5,
+            shift_enter_min_bins: 1,
+            shift_exit_min_bins: 1,
+        },
+        perfstats::TemporalConfig {
+            bin_duration_secs: 900,
+            min_days: 2,
+            min_frac_existing_bins: 0.6,
+            min_frac_bins_with_alternate: 0.6,
+            min_frac_valid_bins: 0.6,
+            continuous_min_frac_shifted_bins: 0.75,
+            diurnal_min_bad_bins: 1,
+            diurnal_bad_bin_min_prob_shift: 0.5,
+            diurnal_permutations: opts.diurnal_permutations,
+            diurnal_max_pvalue: opts.diurnal_max_pvalue,
+            uneventful_max_frac_shifted_bins: 0.0,
+            weekly_min_weeks: 2,
+            weekly_min_autocorr: 0.5,
+            shift_enter_min_bins: 1,
+            shift_exit_min_bins: 1,
+        },
+        perfstats::TemporalConfig {
+            bin_duration_secs: 900,
+            min_days: 2,
+            min_frac_existing_bins: 0.8,
+            min_frac_bins_with_alternate: 0.8,
+            min_frac_valid_bins: 0.8,
+            continuous_min_frac_shifted_bins: 0.90,
+            diurnal_min_bad_bins: 8,
+            diurnal_bad_bin_min_prob_shift: 0.8,
+            diurnal_permutations: opts.diurnal_permutations,
+            diurnal_max_pvalue: opts.diurnal_max_pvalue,
+            uneventful_max_frac_shifted_bins: 0.05,
+            weekly_min_weeks: 2,
+            weekly_min_autocorr: 0.5,
+            shift_enter_min_bins: 1,
+            shift_exit_min_bins: 1,
+        },
+    ];
+    // Also classify with hysteresis so raw and smoothed shift
+    // decisions can be compared side by side.
+    if opts.shift_enter_min_bins > 1 || opts.shift_exit_min_bins > 1 {
+        let smoothed: Vec<perfstats::TemporalConfig> = tempconfigs
+            .iter()
+            .map(|cfg| perfstats::TemporalConfig {
+                shift_enter_min_bins: opts.shift_enter_min_bins,
+                shift_exit_min_bins: opts.shift_exit_min_bins,
+                ..*cfg
+            })
+            .collect();
+        tempconfigs.extend(smoothed);
+    }
+    tempconfigs
 }
 
 fn load_all_databases(opts: &Opt) -> db::DB {
@@ -198,60 +334,158 @@ fn load_all_databases(opts: &Opt) -> db::DB {
     }
 }
 
-fn load_pathid_timeseries(input: &PathBuf) -> Result<HashSet<db::PathId>, Box<dyn Error>> {
-    let mut pathids: HashSet<db::PathId> = HashSet::new();
-    if input.to_str().unwrap() == "" {
-        return Ok(pathids);
+fn build_rank_config(opts: &Opt) -> Result<ranking::RankConfig, Box<dyn Error>> {
+    let mut config = ranking::RankConfig {
+        top: opts.rank_top,
+        ..Default::default()
+    };
+    for spec in opts.ranks.iter() {
+        config.set(spec)?;
     }
-    let f = fs::File::open(input)?;
-    let filerdr = BufReader::new(f);
-    for line in filerdr.lines() {
-        let line = line?;
-        if let Some(pathid) = db::PathId::from_text(&line) {
-            pathids.insert(pathid);
-        } else {
-            error!("Could not parse PathId from line [{}]", &line);
-        }
+    Ok(config)
+}
+
+fn build_window_config(opts: &Opt) -> Result<Option<windows::WindowConfig>, Box<dyn Error>> {
+    if opts.window_days == 0 {
+        return Ok(None);
+    }
+    if opts.window_step_days == 0 {
+        return Err("--window-step-days must be at least 1".into());
+    }
+    if opts.window_step_days > opts.window_days {
+        return Err("--window-step-days must not exceed --window-days".into());
+    }
+    Ok(Some(windows::WindowConfig {
+        window_secs: opts.window_days * 86400,
+        step_secs: opts.window_step_days * 86400,
+    }))
+}
+
+fn build_path_selector(opts: &Opt) -> Result<timeseries::PathSelector, Box<dyn Error>> {
+    let mut selector = timeseries::PathSelector {
+        metros: opts.timeseries_metros.clone(),
+        prefixes: opts.timeseries_prefixes.clone(),
+        countries: opts.timeseries_countries.clone(),
+        classes: opts.timeseries_classes.clone(),
+        top_shifted_bytes: opts.timeseries_top,
+        ..Default::default()
+    };
+    if let Some(path) = &opts.timeseries_pathids {
+        selector.load_pathids(path)?;
+        info!("will dump timeseries of up to {} listed PathIds", selector.pathids.len());
     }
-    info!("will dump {} PathIds for timeseries plotting", pathids.len());
-    Ok(pathids)
+    Ok(selector)
 }
 
-fn dump_pathid_timeseries(
-    db: &db::DB,
-    dbsum: &perfstats::DBSummary,
-    path: &PathBuf,
-    pathids: &HashSet<db::PathId>,
+fn merge_summaries(
+    opts: &Opt,
+    outdir: &Path,
+    summary_files: &[PathBuf],
+    tempconfigs: &[perfstats::TemporalConfig],
+    rankcfg: &ranking::RankConfig,
 ) -> Result<(), Box<dyn Error>> {
-    let mut filepath = path.clone();
-    filepath.push("pathid-timeseries-dump.txt");
-    let file =
-        fs::OpenOptions::new().read(true).write(true).truncate(true).create(true).open(filepath)?;
-    let mut bw = BufWriter::new(file);
-    for pid in pathids {
-        let pinfo = &db.pathid2info[pid];
-        let psum = match dbsum.pathid2summary.get(pid) {
-            Some(psum) => psum,
-            None => continue,
-        };
-        for (time, bin) in pinfo.time2bin.iter() {
-            let (is_shifted, diff_ci) = match psum.time2binstats.get(time) {
-                Some(binstats) => (binstats.is_shifted as u8, binstats.diff_ci),
-                None => (0, 0.0),
-            };
-            write!(bw, "{} {} {} {}", pid.text(), bin.bytes_acked_sum, is_shifted, diff_ci,)?;
-            for rtopt in bin.num2route.iter() {
-                match rtopt {
-                    Some(rtinfo) => write!(
-                        bw,
-                        "{} {} {}",
-                        rtinfo.minrtt_ms_p50, rtinfo.hdratio_p50, rtinfo.px_nexthops
-                    )?,
-                    None => write!(bw, " NULL NULL NULL")?,
-                };
+    let mut prefix2dbsum: BTreeMap<String, perfstats::DBSummary> = BTreeMap::new();
+    for file in summary_files.iter() {
+        let dbsum = perfstats::DBSummary::load(file)?;
+        info!(
+            "loaded {} with {} paths from {:?}",
+            dbsum.summarizer,
+            dbsum.pathid2summary.len(),
+            file
+        );
+        match prefix2dbsum.get_mut(&dbsum.summarizer) {
+            Some(merged) => merged.merge(dbsum).map_err(|e| format!("{:?}: {}", file, e))?,
+            None => {
+                prefix2dbsum.insert(dbsum.summarizer.clone(), dbsum);
             }
         }
     }
+    let run = manifest::RunInfo::build(summary_files, None);
+    let mut entries: Vec<manifest::OutputEntry> = Vec::new();
+    for (prefix, dbsum) in prefix2dbsum.iter_mut() {
+        let spec: Option<SummarizerSpec> =
+            dbsum.spec.as_ref().and_then(|text| SummarizerSpec::parse(text).ok());
+        let info = manifest::SummarizerInfo::new(prefix.clone(), spec.as_ref());
+        if opts.save_summaries {
+            let mut file: PathBuf = outdir.join("summaries");
+            fs::create_dir_all(&file)?;
+            file.push(format!("{}.json.gz", prefix));
+            dbsum.save(&file)?;
+        }
+        for tempcfg in tempconfigs.iter() {
+            let mut reldir: PathBuf = PathBuf::from(tempcfg.prefix());
+            reldir.push(prefix);
+            let dir: PathBuf = outdir.join(&reldir);
+            info!("processing {}", dir.to_str().unwrap());
+            dbsum.reclassify(tempcfg);
+            dbsum.dump(&dir, &opts.formats)?;
+            ranking::RankedPaths::build(dbsum, rankcfg).dump(&dir, &opts.formats)?;
+            tempcfg.dump(&dir)?;
+            summarizers::opportunity::dump_opportunity_vs_relationship(dbsum, &dir)?;
+            let entry = manifest::OutputEntry::new(reldir, "summary", info.clone(), tempcfg, None);
+            run.dump_manifest(outdir, &entry)?;
+            entries.push(entry);
+        }
+    }
+    entries.sort_by(|e1, e2| e1.dir.cmp(&e2.dir));
+    run.dump_index(outdir, &entries)?;
+    Ok(())
+}
+
+fn explain_path(opts: &Opt, pathid: &str, spec: &SummarizerSpec) -> Result<(), Box<dyn Error>> {
+    let pathid: db::PathId =
+        db::PathId::from_text(pathid).ok_or_else(|| format!("cannot parse path {:?}", pathid))?;
+    let tempconfigs: Vec<perfstats::TemporalConfig> = match &opts.config {
+        Some(path) => sweep::SweepConfig::from_file(path)?.tempconfigs,
+        None => build_temporal_configs(opts),
+    };
+    let db = load_all_databases(opts);
+    let summarizer: Arc<dyn TimeBinSummarizer> = spec.build(&db)?;
+    let lines: Vec<String> = explain::explain_path(&db, &pathid, summarizer.as_ref(), &tempconfigs)
+        .ok_or_else(|| format!("path {:?} not in input files", pathid.text()))?;
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn compare_summarizers(
+    opts: &Opt,
+    outdir: &Path,
+    reference: &SummarizerSpec,
+    other: &SummarizerSpec,
+    tempconfigs: &[perfstats::TemporalConfig],
+) -> Result<(), Box<dyn Error>> {
+    let db = load_all_databases(opts);
+    let refsum: Arc<dyn TimeBinSummarizer> = reference.build(&db)?;
+    let othsum: Arc<dyn TimeBinSummarizer> = other.build(&db)?;
+    let mut refdbsum = perfstats::DBSummary::build(&db, refsum.borrow(), &tempconfigs[0]);
+    let mut othdbsum = perfstats::DBSummary::build(&db, othsum.borrow(), &tempconfigs[0]);
+    let run = manifest::RunInfo::build(&opts.input_files, Some(&db));
+    let refinfo = manifest::SummarizerInfo::new(refsum.prefix(), Some(reference));
+    let othinfo = manifest::SummarizerInfo::new(othsum.prefix(), Some(other));
+    let mut entries: Vec<manifest::OutputEntry> = Vec::new();
+    for (i, tempcfg) in tempconfigs.iter().enumerate() {
+        if i > 0 {
+            refdbsum.reclassify(tempcfg);
+            othdbsum.reclassify(tempcfg);
+        }
+        let mut reldir: PathBuf = PathBuf::from(tempcfg.prefix());
+        reldir.push("comparisons");
+        reldir.push(format!("{}--vs--{}", othsum.prefix(), refsum.prefix()));
+        let dir: PathBuf = outdir.join(&reldir);
+        info!("processing {}", dir.to_str().unwrap());
+        comparison::SummarizerComparison::build(&refdbsum, &othdbsum).dump(&dir, &opts.formats)?;
+        tempcfg.dump(&dir)?;
+        let mut entry =
+            manifest::OutputEntry::new(reldir, "comparison", othinfo.clone(), tempcfg, None);
+        entry.reference = Some(refinfo.clone());
+        run.dump_manifest(outdir, &entry)?;
+        entries.push(entry);
+    }
+    run.dump_index(outdir, &entries)?;
     Ok(())
 }
 
@@ -259,40 +493,145 @@ fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
     let opts = Opt::from_args();
 
-    let pathids: HashSet<db::PathId> = load_pathid_timeseries(&opts.pathid_dump_list_file).unwrap();
+    if opts.list_summarizers {
+        for stype in registry::SUMMARIZER_TYPES.iter() {
+            println!("{}", stype.usage());
+            println!("    {}", stype.description);
+            println!("    prefix {}", stype.prefix);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Explain {
+        pathid,
+        summarizer,
+    }) = &opts.cmd
+    {
+        return explain_path(&opts, pathid, summarizer);
+    }
+    let outdir: PathBuf = opts.outdir.clone().ok_or("--outdir is required")?;
+
+    let selector: timeseries::PathSelector = build_path_selector(&opts)?;
+    let rankcfg: ranking::RankConfig = build_rank_config(&opts)?;
+    let wincfg: Option<windows::WindowConfig> = build_window_config(&opts)?;
+
+    let sweep: Option<sweep::SweepConfig> = match &opts.config {
+        Some(path) => {
+            let sweep = sweep::SweepConfig::from_file(path)?;
+            sweep.dump(&outdir, path)?;
+            Some(sweep)
+        }
+        None => None,
+    };
+
+    if let Some(Command::Merge {
+        summary_files,
+    }) = &opts.cmd
+    {
+        let tempconfigs: Vec<perfstats::TemporalConfig> = match &sweep {
+            Some(sweep) => sweep.tempconfigs.clone(),
+            None => build_temporal_configs(&opts),
+        };
+        return merge_summaries(&opts, &outdir, summary_files, &tempconfigs, &rankcfg);
+    }
+    if let Some(Command::Compare {
+        reference,
+        other,
+    }) = &opts.cmd
+    {
+        let tempconfigs: Vec<perfstats::TemporalConfig> = match &sweep {
+            Some(sweep) => sweep.tempconfigs.clone(),
+            None => build_temporal_configs(&opts),
+        };
+        return compare_summarizers(&opts, &outdir, reference, other, &tempconfigs);
+    }
 
     let db_arc = Arc::new(load_all_databases(&opts));
     info!("loaded global DB");
     info!("{}", db_arc.stats());
+    let run_arc = Arc::new(manifest::RunInfo::build(&opts.input_files, Some(&db_arc)));
+    let entries_arc_mtx: Arc<Mutex<Vec<manifest::OutputEntry>>> = Arc::new(Mutex::new(Vec::new()));
 
-    let tempconfigs: Vec<perfstats::TemporalConfig> = build_temporal_configs();
-    let summarizers: Vec<Arc<dyn perfstats::TimeBinSummarizer>> = build_summarizers(&db_arc);
+    let (tempconfigs, mut specs) = match &sweep {
+        Some(sweep) => (sweep.tempconfigs.clone(), sweep.summarizers.clone()),
+        None => (build_temporal_configs(&opts), build_summarizer_specs()),
+    };
+    if !opts.summarizers.is_empty() {
+        specs = opts.summarizers.clone();
+    }
+    let summarizers: Vec<(SummarizerSpec, Arc<dyn TimeBinSummarizer>)> = specs
+        .into_iter()
+        .map(|spec| spec.build(&db_arc).map(|summarizer| (spec, summarizer)))
+        .collect::<Result<_, _>>()?;
+
+    let sensitivity_grid: Vec<perfstats::TemporalConfig> = if opts.sensitivity {
+        let mut ranges: Vec<sensitivity::SensitivityRange> = opts.sensitivity_ranges.clone();
+        if ranges.is_empty() {
+            ranges = match &sweep {
+                Some(sweep) if !sweep.sensitivity.is_empty() => sweep.sensitivity.clone(),
+                _ => sensitivity::SensitivityRange::defaults(),
+            };
+        }
+        sensitivity::build_one_at_a_time_grid(&tempconfigs[0], &ranges)?
+    } else {
+        Vec::new()
+    };
 
     let pool = rayon::ThreadPoolBuilder::new().num_threads(opts.threads).build().unwrap();
     let wg = WaitGroup::new();
 
-    for summarizer_arc in summarizers.iter() {
+    for (spec, summarizer_arc) in summarizers.iter() {
         let db = Arc::clone(&db_arc);
-        let summarizer = Arc::clone(&summarizer_arc);
+        let summarizer = Arc::clone(summarizer_arc);
+        let info = manifest::SummarizerInfo::new(summarizer.prefix(), Some(spec));
+        let spec: String = spec.to_string();
+        let run = Arc::clone(&run_arc);
+        let entries_arc_mtx = Arc::clone(&entries_arc_mtx);
         let wg = wg.clone();
         let opts = opts.clone();
+        let outdir = outdir.clone();
         let tempconfigs = tempconfigs.clone();
-        let pathids = pathids.clone();
+        let selector = selector.clone();
+        let rankcfg = rankcfg.clone();
+        let sensitivity_grid = sensitivity_grid.clone();
         pool.spawn(move || {
+            let record = |entry: manifest::OutputEntry| {
+                run.dump_manifest(&outdir, &entry).unwrap_or_else(|e| {
+                    error!("{}: could not dump manifest", summarizer.prefix());
+                    error!("{:?}", e);
+                });
+                entries_arc_mtx.lock().unwrap().push(entry);
+            };
             let mut dbsum: perfstats::DBSummary =
                 perfstats::DBSummary::build(&db, summarizer.borrow(), &tempconfigs[0]);
+            dbsum.spec = Some(spec);
+            if opts.save_summaries {
+                let mut file: PathBuf = outdir.join("summaries");
+                file.push(format!("{}.json.gz", summarizer.prefix()));
+                fs::create_dir_all(file.parent().unwrap())
+                    .and_then(|_| dbsum.save(&file))
+                    .unwrap_or_else(|e| {
+                        error!("{}: could not save DBSummary", summarizer.prefix());
+                        error!("{:?}", e);
+                    });
+            }
             for (i, tempcfg) in tempconfigs.iter().enumerate() {
-                let mut dir: PathBuf = opts.outdir.clone();
-                dir.push(tempcfg.prefix());
-                dir.push(summarizer.prefix());
+                let mut reldir: PathBuf = PathBuf::from(tempcfg.prefix());
+                reldir.push(summarizer.prefix());
+                let mut dir: PathBuf = outdir.join(&reldir);
                 info!("processing {}", dir.to_str().unwrap());
                 if i > 0 {
-                    dbsum.reclassify(&db, tempcfg);
+                    dbsum.reclassify(tempcfg);
                 }
-                dbsum.dump(&dir, &db, &*summarizer).unwrap_or_else(|e| {
+                dbsum.dump(&dir, &opts.formats).unwrap_or_else(|e| {
                     error!("{}: could not dump DBSummary", summarizer.prefix());
                     error!("{:?}", e);
                 });
+                ranking::RankedPaths::build(&dbsum, &rankcfg)
+                    .dump(&dir, &opts.formats)
+                    .unwrap_or_else(|e| {
+                        error!("{}: could not dump RankedPaths", summarizer.prefix());
+                        error!("{:?}", e);
+                    });
                 tempcfg.dump(&dir).unwrap_or_else(|e| {
                     error!("{}: could not dump TemporalConfig", summarizer.prefix());
                     error!("{:?}", e);
@@ -305,17 +644,96 @@ fn main() -> Result<(), Box<dyn Error>> {
                         );
                         error!("{:?}", e);
                     });
-                dump_pathid_timeseries(&db, &dbsum, &dir, &pathids).unwrap_or_else(|e| {
-                    error!("{}: could not dump prefix timeseries", summarizer.prefix());
+                selector.dump(&db, &dbsum, &dir).unwrap_or_else(|e| {
+                    error!("{}: could not dump path timeseries", summarizer.prefix());
                     error!("{:?}", e);
-                })
+                });
+                record(manifest::OutputEntry::new(
+                    reldir.clone(),
+                    "summary",
+                    info.clone(),
+                    tempcfg,
+                    None,
+                ));
+                if let Some(wincfg) = wincfg {
+                    let winsum =
+                        windows::WindowedSummary::build(&db, &*summarizer, tempcfg, &wincfg);
+                    dir.push(wincfg.prefix());
+                    winsum.dump(&dir).unwrap_or_else(|e| {
+                        error!("{}: could not dump WindowedSummary", summarizer.prefix());
+                        error!("{:?}", e);
+                    });
+                    reldir.push(wincfg.prefix());
+                    record(manifest::OutputEntry::new(
+                        reldir,
+                        "windows",
+                        info.clone(),
+                        tempcfg,
+                        Some(wincfg),
+                    ));
+                }
+            }
+            if opts.sensitivity {
+                let reference: perfstats::TemporalConfig = tempconfigs[0];
+                let report = sensitivity::SensitivityReport::build(
+                    &mut dbsum,
+                    &reference,
+                    &sensitivity_grid,
+                );
+                let mut reldir: PathBuf = PathBuf::from("sensitivity");
+                reldir.push(summarizer.prefix());
+                let dir: PathBuf = outdir.join(&reldir);
+                info!("processing {}", dir.to_str().unwrap());
+                report.dump(&dir).unwrap_or_else(|e| {
+                    error!("{}: could not dump SensitivityReport", summarizer.prefix());
+                    error!("{:?}", e);
+                });
+                record(manifest::OutputEntry::new(
+                    reldir,
+                    "sensitivity",
+                    info.clone(),
+                    &reference,
+                    None,
+                ));
             }
             drop(db);
+            drop(run);
+            drop(entries_arc_mtx);
             drop(summarizer);
             drop(wg);
         });
     }
 
     wg.wait();
+    let mut entries: Vec<manifest::OutputEntry> = entries_arc_mtx.lock().unwrap().clone();
+    entries.sort_by(|e1, e2| e1.dir.cmp(&e2.dir));
+    run_arc.dump_index(&outdir, &entries)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../helpers/sweep-example.toml");
+
+    #[test]
+    fn test_sweep_example_matches_builtin_sweep() {
+        let database = db::DB::default();
+        let sweep = sweep::SweepConfig::parse(EXAMPLE).unwrap();
+        let prefixes = |summarizers: Vec<Arc<dyn TimeBinSummarizer>>| {
+            let mut prefixes: Vec<String> = summarizers.iter().map(|s| s.prefix()).collect();
+            prefixes.sort();
+            prefixes
+        };
+        let builtin: Vec<Arc<dyn TimeBinSummarizer>> =
+            build_summarizer_specs().iter().map(|spec| spec.build(&database).unwrap()).collect();
+        assert!(prefixes(sweep.build_summarizers(&database).unwrap()) == prefixes(builtin));
+
+        let opts = Opt::from_iter(&["perfstats"]);
+        let tempconfigs: Vec<String> =
+            build_temporal_configs(&opts).iter().map(|cfg| cfg.prefix()).collect();
+        let example: Vec<String> = sweep.tempconfigs.iter().map(|cfg| cfg.prefix()).collect();
+        assert!(example == tempconfigs);
+    }
+}
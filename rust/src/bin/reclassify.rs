@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use log::{error, info};
+use structopt::StructOpt;
+
+use fbperf::performance::perfstats;
+use fbperf::performance::ranking;
+use fbperf::performance::sink;
+use fbperf::performance::summarizers;
+use fbperf::performance::sweep;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "reclassify",
+    about = "Classify DBSummaries saved by perfstats --save-summaries under new temporal configs.",
+    rename_all = "kebab-case"
+)]
+struct Opt {
+    #[structopt(parse(from_os_str))]
+    /// The saved DBSummary files
+    summary_files: Vec<PathBuf>,
+    #[structopt(long, parse(from_os_str))]
+    /// The output directory where to store files
+    outdir: PathBuf,
+    #[structopt(long, parse(from_os_str))]
+    /// TOML file with [[tempconfig]] tables (see helpers/sweep-example.toml);
+    /// [[summarizer]] tables are ignored
+    config: PathBuf,
+    #[structopt(long, default_value = "text,pickle,csv", use_delimiter = true)]
+    /// Formats for path summaries and temporal behavior tables: text,
+    /// pickle, csv, jsonl
+    formats: Vec<sink::OutputFormat>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    let opts = Opt::from_args();
+
+    let sweep = sweep::SweepConfig::from_file(&opts.config)?;
+    if sweep.tempconfigs.is_empty() {
+        return Err(format!("{:?} has no [[tempconfig]] tables", opts.config).into());
+    }
+
+    for file in opts.summary_files.iter() {
+        let mut dbsum = match perfstats::DBSummary::load(file) {
+            Ok(dbsum) => dbsum,
+            Err(e) => {
+                error!("could not load DBSummary from {:?}", file);
+                error!("{:?}", e);
+                continue;
+            }
+        };
+        info!("loaded {} with {} paths", dbsum.summarizer, dbsum.pathid2summary.len());
+        for tempcfg in sweep.tempconfigs.iter() {
+            let mut dir: PathBuf = opts.outdir.clone();
+            dir.push(tempcfg.prefix());
+            dir.push(&dbsum.summarizer);
+            info!("processing {}", dir.to_str().unwrap());
+            dbsum.reclassify(tempcfg);
+            dbsum.dump(&dir, &opts.formats).unwrap_or_else(|e| {
+                error!("{}: could not dump DBSummary", dbsum.summarizer);
+                error!("{:?}", e);
+            });
+            ranking::RankedPaths::build(&dbsum, &ranking::RankConfig::default())
+                .dump(&dir, &opts.formats)
+                .unwrap_or_else(|e| {
+                    error!("{}: could not dump RankedPaths", dbsum.summarizer);
+                    error!("{:?}", e);
+                });
+            tempcfg.dump(&dir).unwrap_or_else(|e| {
+                error!("{}: could not dump TemporalConfig", dbsum.summarizer);
+                error!("{:?}", e);
+            });
+            summarizers::opportunity::dump_opportunity_vs_relationship(&dbsum, &dir)
+                .unwrap_or_else(|e| {
+                    error!("{}: could not dump opportunity_vs_relationship", dbsum.summarizer);
+                    error!("{:?}", e);
+                });
+        }
+    }
+    Ok(())
+}
@@ -7,7 +7,7 @@ use std::path::Path;
 
 // Accepts D: PartialOrd for convenience; will panic if not Ord.
 // Will sort `data`, hence the `&mut`.
-pub fn build<D>(data: &mut Vec<(D, f64)>, step: f64) -> Vec<(D, f64)>
+pub fn build<D>(data: &mut [(D, f64)], step: f64) -> Vec<(D, f64)>
 where
     D: Copy + Default + PartialOrd,
 {
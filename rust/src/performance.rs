@@ -1,3 +1,12 @@
-pub mod summarizers;
+pub mod comparison;
 pub mod db;
+pub mod explain;
+pub mod manifest;
 pub mod perfstats;
+pub mod ranking;
+pub mod sensitivity;
+pub mod sink;
+pub mod summarizers;
+pub mod sweep;
+pub mod timeseries;
+pub mod windows;
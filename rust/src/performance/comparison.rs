@@ -0,0 +1,223 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::path::Path;
+
+use crate::performance::perfstats::{DBSummary, PathSummary, TimeBinStats, TransitionMatrix};
+use crate::performance::sink::{OutputFormat, Table, TableGroup, Value};
+
+/// What a summarizer concluded about a bin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinOutcome {
+    /// Not `TimeBinSummary::Valid`, e.g., a wide CI or no route.
+    NotValid = 0,
+    NotShifted,
+    Shifted,
+}
+
+const BIN_OUTCOMES: [BinOutcome; 3] =
+    [BinOutcome::NotValid, BinOutcome::NotShifted, BinOutcome::Shifted];
+
+impl BinOutcome {
+    fn new(binstats: Option<&TimeBinStats>) -> BinOutcome {
+        match binstats {
+            None => BinOutcome::NotValid,
+            Some(bs) if bs.is_shifted => BinOutcome::Shifted,
+            Some(_) => BinOutcome::NotShifted,
+        }
+    }
+
+    fn column(self) -> &'static str {
+        match self {
+            BinOutcome::NotValid => "not_valid",
+            BinOutcome::NotShifted => "not_shifted",
+            BinOutcome::Shifted => "shifted",
+        }
+    }
+}
+
+/// How conclusions change between two summarizers applied to the same
+/// dataset and classified under the same `TemporalConfig`, e.g., MinRTT
+/// P50 and P25 opportunity.
+pub struct SummarizerComparison {
+    pub reference: String,
+    pub other: String,
+    /// Bins and their bytes indexed by [reference outcome][other
+    /// outcome].  Bins not valid under both summarizers are not
+    /// counted, as their bytes are not kept in `DBSummary`.
+    pub bins: [[u64; BIN_OUTCOMES.len()]; BIN_OUTCOMES.len()],
+    pub bytes: [[u128; BIN_OUTCOMES.len()]; BIN_OUTCOMES.len()],
+    /// Transitions from the behavior of each path under the reference
+    /// summarizer to that under the other; bytes are the path's total
+    /// traffic.
+    pub transitions: TransitionMatrix,
+}
+
+impl SummarizerComparison {
+    /// Compares paths present in both summaries.
+    pub fn build(reference: &DBSummary, other: &DBSummary) -> SummarizerComparison {
+        let mut cmp = SummarizerComparison {
+            reference: reference.summarizer.clone(),
+            other: other.summarizer.clone(),
+            bins: Default::default(),
+            bytes: Default::default(),
+            transitions: TransitionMatrix::default(),
+        };
+        for (pathid, refsum) in reference.pathid2summary.iter() {
+            let othsum: &PathSummary = match other.pathid2summary.get(pathid) {
+                Some(othsum) => othsum,
+                None => continue,
+            };
+            cmp.transitions.add(
+                refsum.temporal_behavior,
+                othsum.temporal_behavior,
+                refsum.existing_bytes,
+            );
+            let times: BTreeSet<u64> =
+                refsum.time2binstats.keys().chain(othsum.time2binstats.keys()).copied().collect();
+            for time in times {
+                let refstats: Option<&TimeBinStats> = refsum.time2binstats.get(&time);
+                let othstats: Option<&TimeBinStats> = othsum.time2binstats.get(&time);
+                let bytes: u64 = refstats.or(othstats).unwrap().bytes;
+                let i: usize = BinOutcome::new(refstats) as usize;
+                let j: usize = BinOutcome::new(othstats) as usize;
+                cmp.bins[i][j] += 1;
+                cmp.bytes[i][j] += u128::from(bytes);
+            }
+        }
+        cmp
+    }
+
+    /// Bins and bytes valid under both summarizers whose shift differs.
+    pub fn flipped(&self) -> (u64, u128) {
+        let (ns, s) = (BinOutcome::NotShifted as usize, BinOutcome::Shifted as usize);
+        (self.bins[ns][s] + self.bins[s][ns], self.bytes[ns][s] + self.bytes[s][ns])
+    }
+
+    pub fn bin_table(&self) -> TableGroup {
+        let total_bytes: u128 = self.bytes.iter().flatten().sum();
+        let mut rows: Vec<Vec<Value>> = Vec::new();
+        for from in BIN_OUTCOMES.iter() {
+            for to in BIN_OUTCOMES.iter() {
+                let bins: u64 = self.bins[*from as usize][*to as usize];
+                let bytes: u128 = self.bytes[*from as usize][*to as usize];
+                rows.push(vec![
+                    Value::Str(from.column().to_string()),
+                    Value::Str(to.column().to_string()),
+                    Value::Int(u128::from(bins)),
+                    Value::Int(bytes),
+                    Value::Float(bytes as f64 / total_bytes as f64, 3),
+                ]);
+            }
+        }
+        TableGroup {
+            text_name: "bin-outcomes",
+            pickle_name: Some("bin-outcomes"),
+            tables: vec![Table {
+                name: "bin-outcomes",
+                columns: vec!["reference", "other", "bins", "bytes", "frac_bytes"],
+                key_columns: 2,
+                key_separator: "+",
+                sections: vec![rows],
+            }],
+        }
+    }
+
+    /// Writes the bin outcome table, the path transitions and the
+    /// names of the compared summarizers under `path`.
+    pub fn dump(&self, path: &Path, formats: &[OutputFormat]) -> Result<(), io::Error> {
+        std::fs::create_dir_all(path)?;
+        std::fs::write(
+            path.join("summarizers.txt"),
+            format!("reference {}\nother {}\n", self.reference, self.other),
+        )?;
+        for format in formats.iter() {
+            format.sink().write(path, &self.bin_table())?;
+        }
+        self.transitions.dump(path, "path-transitions")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::db;
+    use crate::performance::perfstats::{TemporalBehavior, TemporalConfig, TimeBinSummarizer};
+    use crate::performance::summarizers::opportunity::MinRttPercentileImprovementSummarizer;
+
+    const TEMPCONFIG: TemporalConfig = TemporalConfig {
+        bin_duration_secs: 900,
+        min_days: 0,
+        min_frac_existing_bins: 0.0,
+        min_frac_bins_with_alternate: 0.0,
+        min_frac_valid_bins: 0.5,
+        continuous_min_frac_shifted_bins: 0.8,
+        diurnal_min_bad_bins: 24,
+        diurnal_bad_bin_min_prob_shift: 0.8,
+        diurnal_permutations: 0,
+        diurnal_max_pvalue: 1.0,
+        uneventful_max_frac_shifted_bins: 0.20,
+        weekly_min_weeks: 0,
+        weekly_min_autocorr: 1.0,
+        shift_enter_min_bins: 1,
+        shift_exit_min_bins: 1,
+    };
+
+    #[test]
+    fn test_summarizer_comparison() {
+        let summarizer = |percentile| MinRttPercentileImprovementSummarizer {
+            percentile,
+            minrtt_min_improv: 5.0,
+            max_minrtt_diff_ci_halfwidth: 10.0,
+            compare_lower_bound: false,
+        };
+        let p50 = summarizer(db::MinRttPercentile::P50);
+        let p25 = summarizer(db::MinRttPercentile::P25);
+
+        // P50s are equal in all bins; the alternate's P25 is 10 ms lower
+        // in even bins.  The last bin has no P25.
+        let mut database: db::DB = db::DB::default();
+        let mut time2bin = std::collections::BTreeMap::new();
+        for i in 0..9u64 {
+            let time: u64 = i * u64::from(TEMPCONFIG.bin_duration_secs);
+            let timebin = if i == 8 {
+                db::TimeBin::mock_minrtt_p50(time, 50, 50, 2)
+            } else if i % 2 == 0 {
+                db::TimeBin::mock_minrtt_p25(time, 30, 20, 2)
+            } else {
+                db::TimeBin::mock_minrtt_p25(time, 30, 30, 2)
+            };
+            time2bin.insert(time, timebin);
+        }
+        let pathid: db::PathId = db::tests::make_path_id();
+        assert!(database.insert(pathid.clone(), time2bin).is_none());
+
+        let reference: DBSummary = DBSummary::build(&database, &p50, &TEMPCONFIG);
+        let other: DBSummary = DBSummary::build(&database, &p25, &TEMPCONFIG);
+        let cmp = SummarizerComparison::build(&reference, &other);
+        assert!(cmp.reference == p50.prefix() && cmp.other == p25.prefix());
+
+        let (nv, ns, s) = (
+            BinOutcome::NotValid as usize,
+            BinOutcome::NotShifted as usize,
+            BinOutcome::Shifted as usize,
+        );
+        assert!(cmp.bins[ns][s] == 4);
+        assert!(cmp.bins[ns][ns] == 4);
+        assert!(cmp.bins[ns][nv] == 1);
+        assert!(cmp.bins.iter().flatten().sum::<u64>() == 9);
+        assert!(cmp.flipped() == (4, 4 * u128::from(db::TimeBin::MOCK_TOTAL_BYTES)));
+
+        let psum = &reference.pathid2summary[&pathid];
+        assert!(psum.temporal_behavior == TemporalBehavior::Uneventful);
+        assert!(cmp.transitions.total() == (1, psum.existing_bytes));
+        assert!(cmp.transitions.changed() == (1, psum.existing_bytes));
+
+        let group: TableGroup = cmp.bin_table();
+        let rows: Vec<&Vec<Value>> = group.tables[0].rows().collect();
+        assert!(rows.len() == BIN_OUTCOMES.len() * BIN_OUTCOMES.len());
+        assert!(
+            rows[5][..3]
+                == [Value::Str("not_shifted".into()), Value::Str("shifted".into()), Value::Int(4)]
+        );
+    }
+}
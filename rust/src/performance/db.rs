@@ -12,25 +12,51 @@ use flate2::bufread::GzDecoder;
 use ipnet::IpNet;
 use log::{info, trace};
 use num_enum::TryFromPrimitive;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 mod error;
 use error::{ParseError, ParseErrorKind};
 
-const CONFIDENCE_Z: f32 = 2.0;
+pub(crate) const CONFIDENCE_Z: f32 = 2.0;
 
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Ord, PartialOrd, TryFromPrimitive, Serialize)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    Hash,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    TryFromPrimitive,
+    Serialize,
+    Deserialize,
+)]
 pub enum PeerType {
     PeeringPrivate = 0,
     PeeringPublic = 1,
     PeeringPaid = 2,
     Transit = 3,
+    #[default]
     Uninitialized = 4,
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, TryFromPrimitive)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Hash,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    TryFromPrimitive,
+    Serialize,
+    Deserialize,
+)]
 pub enum ClientContinent {
     AF = 0,
     AS = 1,
@@ -51,7 +77,7 @@ pub struct DB {
     error_counts: HashMap<ParseErrorKind, u32>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct PathId {
     pub vip_metro: String,
     pub bgp_ip_prefix: IpNet,
@@ -83,13 +109,17 @@ pub struct RouteInfo {
     // pub bgp_as_path_prepending: bool,
     pub peer_type: PeerType,
     pub minrtt_num_samples: u32,
-    // pub minrtt_ms_p10: u16,
+    /// MinRTT P10 and its CI halfwidth, if exported.
+    pub minrtt_ms_p10: Option<(u16, u16)>,
+    /// MinRTT P25 and its CI halfwidth, if exported.
+    pub minrtt_ms_p25: Option<(u16, u16)>,
     pub minrtt_ms_p50: u16,
     pub minrtt_ms_p50_ci_halfwidth: u16,
     // pub minrtt_ms_p50_var: f32,
     pub hdratio_num_samples: u32,
-    // pub hdratio: f32,
-    // pub hdratio_var: f32,
+    /// Mean HD-ratio and the variance of its samples.
+    pub hdratio: f32,
+    pub hdratio_var: f32,
     pub hdratio_p50: f32,
     pub hdratio_p50_ci_halfwidth: f32,
     pub hdratio_boot: f32,
@@ -98,6 +128,34 @@ pub struct RouteInfo {
     pub px_nexthops: u64,
 }
 
+/// MinRTT percentiles summarizers can compare routes on.  Lower
+/// percentiles are closer to a route's propagation delay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MinRttPercentile {
+    P10,
+    P25,
+    P50,
+}
+
+impl MinRttPercentile {
+    pub fn new(percentile: u32) -> Option<MinRttPercentile> {
+        match percentile {
+            10 => Some(MinRttPercentile::P10),
+            25 => Some(MinRttPercentile::P25),
+            50 => Some(MinRttPercentile::P50),
+            _ => None,
+        }
+    }
+
+    pub fn value(self) -> u32 {
+        match self {
+            MinRttPercentile::P10 => 10,
+            MinRttPercentile::P25 => 25,
+            MinRttPercentile::P50 => 50,
+        }
+    }
+}
+
 impl PeerType {
     fn new(peer_type: &str, peer_subtype: &str) -> Result<PeerType, ParseError> {
         match (peer_type, peer_subtype) {
@@ -115,12 +173,6 @@ impl PeerType {
     }
 }
 
-impl Default for PeerType {
-    fn default() -> Self {
-        PeerType::Uninitialized
-    }
-}
-
 impl DB {
     pub fn merge(&mut self, db: DB) {
         self.pathid2info.extend(db.pathid2info);
@@ -131,6 +183,10 @@ impl DB {
             self.error_counts.entry(error).and_modify(|e| *e += cnt).or_insert(cnt);
         }
     }
+    /// Number of rows discarded for each kind of parse error.
+    pub fn error_counts(&self) -> BTreeMap<String, u32> {
+        self.error_counts.iter().map(|(kind, cnt)| (format!("{:?}", kind), *cnt)).collect()
+    }
     pub fn stats(&self) -> String {
         format!(
             "DB rows={} paths={} bins={} bytes={}\n{:?}",
@@ -154,12 +210,12 @@ impl DB {
             rows: 0,
             error_counts: HashMap::new(),
         };
-        let mut min_timestamp: u64 = std::u64::MAX;
+        let mut min_timestamp: u64 = u64::MAX;
         let mut max_timestamp: u64 = 0;
         for result in csvrdr.deserialize() {
             db.rows += 1;
             let record: HashMap<String, String> = result.unwrap();
-            if (db.rows % 100_000) == 0 {
+            if db.rows.is_multiple_of(100_000) {
                 trace!("{:?} {} rows", input, db.rows);
             }
             let pid: Arc<PathId> = match PathId::from_record(&record) {
@@ -178,7 +234,7 @@ impl DB {
             };
             min_timestamp = std::cmp::min(min_timestamp, timebin.time_bucket);
             max_timestamp = std::cmp::max(max_timestamp, timebin.time_bucket);
-            let mut pinfo = db.pathid2info.entry(Arc::clone(&pid)).or_insert_with(Default::default);
+            let pinfo = db.pathid2info.entry(Arc::clone(&pid)).or_default();
             pinfo.total_traffic += u128::from(timebin.bytes_acked_sum);
             db.total_traffic += u128::from(timebin.bytes_acked_sum);
             match pinfo.time2bin.entry(timebin.time_bucket) {
@@ -259,7 +315,7 @@ impl PathId {
 }
 
 impl TimeBin {
-    const MAX_ROUTES: usize = 7;
+    pub const MAX_ROUTES: usize = 7;
 
     fn from_record(rec: &HashMap<String, String>) -> Result<TimeBin, ParseError> {
         let mut timebin = TimeBin {
@@ -293,7 +349,7 @@ impl TimeBin {
     where
         F: Fn(&RouteInfo) -> bool,
     {
-        let optbox: &Option<Box<RouteInfo>> = self.num2route.get(0).unwrap();
+        let optbox: &Option<Box<RouteInfo>> = self.num2route.first().unwrap();
         match &optbox {
             None => &None,
             Some(rtbox) => {
@@ -306,6 +362,15 @@ impl TimeBin {
         }
     }
 
+    /// Index in `num2route` of `rtinfo`, if it is borrowed from this
+    /// bin.
+    pub fn route_index(&self, rtinfo: &RouteInfo) -> Option<u8> {
+        self.num2route
+            .iter()
+            .position(|rtopt| rtopt.as_deref().is_some_and(|rt| std::ptr::eq(rt, rtinfo)))
+            .map(|i| i as u8)
+    }
+
     pub fn get_primary_route_minrtt(&self) -> &Option<Box<RouteInfo>> {
         self.get_primary_route(RouteInfo::minrtt_valid)
     }
@@ -404,8 +469,8 @@ impl RouteInfo {
         let hdratio_num_samples: u32 = rec[&format!("r{}_num_samples_with_hdratio", i)].parse()?;
 
         let mut hdratio_p50_ci_halfwidth: f32 = 0.0;
-        // let mut hdratio: f32 = 0.0;
-        // let mut hdratio_var: f32 = 0.0;
+        let mut hdratio: f32 = 0.0;
+        let mut hdratio_var: f32 = 0.0;
         let mut hdratio_p50: f32 = 0.0;
         let mut hdratio_boot: f32 = 0.0;
         let mut r0_hdratio_boot_diff_ci_lb: f32 = 0.0;
@@ -414,8 +479,8 @@ impl RouteInfo {
             let hdratio_p50_ci_lb: f32 = rec[&format!("r{}_hdratio_p50_ci_lb", i)].parse().unwrap();
             let hdratio_p50_ci_ub: f32 = rec[&format!("r{}_hdratio_p50_ci_ub", i)].parse().unwrap();
             hdratio_p50_ci_halfwidth = (hdratio_p50_ci_ub - hdratio_p50_ci_lb) / 2.0;
-            // hdratio = rec[&format!("r{}_hdratio_avg", i)].parse().unwrap();
-            // hdratio_var = rec[&format!("r{}_hdratio_normal_var", i)].parse().unwrap();
+            hdratio = rec[&format!("r{}_hdratio_avg", i)].parse().unwrap();
+            hdratio_var = rec[&format!("r{}_hdratio_normal_var", i)].parse().unwrap();
             hdratio_p50 = rec[&format!("r{}_hdratio_p50", i)].parse().unwrap();
             hdratio_boot = rec[&format!("r{}_hdratio_avg_bootstrapped", i)].parse().unwrap();
             if i > 0 {
@@ -431,6 +496,8 @@ impl RouteInfo {
             }
         }
 
+        let minrtt_ms_p10 = RouteInfo::minrtt_percentile_from_record(i, "p10", rec);
+        let minrtt_ms_p25 = RouteInfo::minrtt_percentile_from_record(i, "p25", rec);
         let minrtt_ms_p50_ci_lb: f32 = rec[&format!("r{}_minrtt_ms_p50_ci_lb", i)].parse().unwrap();
         let minrtt_ms_p50_ci_ub: f32 = rec[&format!("r{}_minrtt_ms_p50_ci_ub", i)].parse().unwrap();
         let minrtt_ms_p50_ci_halfwidth = ((minrtt_ms_p50_ci_ub - minrtt_ms_p50_ci_lb) / 2.0) as u16;
@@ -449,13 +516,14 @@ impl RouteInfo {
                 &rec[&format!("r{}_peer_subtype", i)],
             )?,
             minrtt_num_samples,
-            // minrtt_ms_p10: rec[&format!("r{}_minrtt_ms_p10", i)].parse().unwrap(),
+            minrtt_ms_p10,
+            minrtt_ms_p25,
             minrtt_ms_p50: rec[&format!("r{}_minrtt_ms_p50", i)].parse::<f32>().unwrap() as u16,
             minrtt_ms_p50_ci_halfwidth,
             // minrtt_ms_p50_var: rec[&format!("r{}_minrtt_ms_p50_var", i)].parse().unwrap(),
             hdratio_num_samples,
-            // hdratio,
-            // hdratio_var,
+            hdratio,
+            hdratio_var,
             hdratio_p50,
             hdratio_p50_ci_halfwidth,
             hdratio_boot,
@@ -465,12 +533,53 @@ impl RouteInfo {
         }))
     }
 
+    /// The `r{i}_minrtt_ms_{name}` percentile and the halfwidth of its
+    /// CI, or `None` if older exports do not include it.
+    fn minrtt_percentile_from_record(
+        i: usize,
+        name: &str,
+        rec: &HashMap<String, String>,
+    ) -> Option<(u16, u16)> {
+        let field = |suffix: &str| -> Option<f32> {
+            rec.get(&format!("r{}_minrtt_ms_{}{}", i, name, suffix))?.parse::<f32>().ok()
+        };
+        let (value, lb, ub) = (field("")?, field("_ci_lb")?, field("_ci_ub")?);
+        Some((value as u16, ((ub - lb) / 2.0) as u16))
+    }
+
     pub fn minrtt_median_diff_ci(rt1: &RouteInfo, rt2: &RouteInfo) -> (f32, f32) {
-        let med1 = rt1.minrtt_ms_p50;
-        let med2 = rt2.minrtt_ms_p50;
-        let var1 = (f32::from(rt1.minrtt_ms_p50_ci_halfwidth) / CONFIDENCE_Z).powf(2.0);
-        let var2 = (f32::from(rt2.minrtt_ms_p50_ci_halfwidth) / CONFIDENCE_Z).powf(2.0);
-        let md: f32 = f32::from(med1) - f32::from(med2);
+        RouteInfo::minrtt_diff_ci(
+            (rt1.minrtt_ms_p50, rt1.minrtt_ms_p50_ci_halfwidth),
+            (rt2.minrtt_ms_p50, rt2.minrtt_ms_p50_ci_halfwidth),
+        )
+    }
+
+    /// The MinRTT `percentile` of the route and its CI halfwidth.
+    pub fn minrtt_percentile(&self, percentile: MinRttPercentile) -> Option<(u16, u16)> {
+        match percentile {
+            MinRttPercentile::P10 => self.minrtt_ms_p10,
+            MinRttPercentile::P25 => self.minrtt_ms_p25,
+            MinRttPercentile::P50 => Some((self.minrtt_ms_p50, self.minrtt_ms_p50_ci_halfwidth)),
+        }
+    }
+
+    /// As `minrtt_median_diff_ci` for `percentile`; both routes must
+    /// be `minrtt_percentile_valid`.
+    pub fn minrtt_percentile_diff_ci(
+        rt1: &RouteInfo,
+        rt2: &RouteInfo,
+        percentile: MinRttPercentile,
+    ) -> (f32, f32) {
+        RouteInfo::minrtt_diff_ci(
+            rt1.minrtt_percentile(percentile).unwrap(),
+            rt2.minrtt_percentile(percentile).unwrap(),
+        )
+    }
+
+    fn minrtt_diff_ci((ms1, halfwidth1): (u16, u16), (ms2, halfwidth2): (u16, u16)) -> (f32, f32) {
+        let var1 = (f32::from(halfwidth1) / CONFIDENCE_Z).powf(2.0);
+        let var2 = (f32::from(halfwidth2) / CONFIDENCE_Z).powf(2.0);
+        let md: f32 = f32::from(ms1) - f32::from(ms2);
         let halfwidth: f32 = CONFIDENCE_Z * (var1 + var2).sqrt();
         (md, halfwidth)
     }
@@ -485,15 +594,21 @@ impl RouteInfo {
         (md, halfwidth)
     }
 
-    // pub fn hdratio_diff_ci_do_not_use(rt1: &RouteInfo, rt2: &RouteInfo) -> (f32, f32) {
-    //     let diff: f32 = rt1.hdratio - rt2.hdratio;
-    //     let var1: f32 = rt1.hdratio_var;
-    //     let var2: f32 = rt2.hdratio_var;
-    //     let n1: f32 = rt1.hdratio_num_samples as f32;
-    //     let n2: f32 = rt2.hdratio_num_samples as f32;
-    //     let halfwidth: f32 = CONFIDENCE_Z * (var1 / n1 + var2 / n2).sqrt();
-    //     (diff, halfwidth)
-    // }
+    /// Difference of mean HD-ratios and the halfwidth of its CI, with
+    /// Welch's unequal-variance standard error.  Routes have enough
+    /// samples for the normal approximation.
+    pub fn hdratio_mean_diff_ci(rt1: &RouteInfo, rt2: &RouteInfo) -> (f32, f32) {
+        let diff: f32 = rt1.hdratio - rt2.hdratio;
+        let var1: f32 = rt1.hdratio_var / rt1.hdratio_num_samples as f32;
+        let var2: f32 = rt2.hdratio_var / rt2.hdratio_num_samples as f32;
+        let halfwidth: f32 = CONFIDENCE_Z * (var1 + var2).sqrt();
+        (diff, halfwidth)
+    }
+
+    /// Halfwidth of the CI of the mean HD-ratio.
+    pub fn hdratio_mean_ci_halfwidth(&self) -> f32 {
+        CONFIDENCE_Z * (self.hdratio_var / self.hdratio_num_samples as f32).sqrt()
+    }
 
     pub fn hdratio_boot_diff_ci(bestalt: &RouteInfo, primary: &RouteInfo) -> (f32, f32, f32) {
         let mut diff: f32 = bestalt.hdratio_boot - primary.hdratio_boot;
@@ -510,14 +625,31 @@ impl RouteInfo {
         // rt1.minrtt_ms_p50.cmp(&rt2.minrtt_ms_p50)
     }
 
+    /// Greater if `rt1` has the lower MinRTT `percentile`; both routes
+    /// must be `minrtt_percentile_valid`.
+    pub fn compare_minrtt_percentile(
+        rt1: &RouteInfo,
+        rt2: &RouteInfo,
+        percentile: MinRttPercentile,
+    ) -> Ordering {
+        rt2.minrtt_percentile(percentile)
+            .unwrap()
+            .0
+            .cmp(&rt1.minrtt_percentile(percentile).unwrap().0)
+    }
+
+    /// Total order on HD-ratio P50s; NaN is greater than any number so
+    /// that sorted windows and binary searches stay consistent.
     pub fn compare_median_hdratio(rt1: &RouteInfo, rt2: &RouteInfo) -> Ordering {
         // Return Greater if rt1.hdratio_p50 > rt2.hdratio_p50
-        rt1.hdratio_p50.partial_cmp(&rt2.hdratio_p50).unwrap_or(Ordering::Equal)
+        rt1.hdratio_p50.total_cmp(&rt2.hdratio_p50)
     }
 
-    // pub fn compare_hdratio(rt1: &RouteInfo, rt2: &RouteInfo) -> Ordering {
-    //     rt1.hdratio.partial_cmp(&rt2.hdratio).unwrap_or(Ordering::Equal)
-    // }
+    /// Total order on mean HD-ratios, as `compare_median_hdratio`.
+    pub fn compare_mean_hdratio(rt1: &RouteInfo, rt2: &RouteInfo) -> Ordering {
+        // Return Greater if rt1.hdratio > rt2.hdratio
+        rt1.hdratio.total_cmp(&rt2.hdratio)
+    }
 
     pub fn compare_hdratio_bootstrap(rt1: &RouteInfo, rt2: &RouteInfo) -> Ordering {
         rt1.hdratio_boot.partial_cmp(&rt2.hdratio_boot).unwrap_or(Ordering::Equal)
@@ -527,6 +659,10 @@ impl RouteInfo {
         rtinfo.minrtt_num_samples >= RouteInfo::MIN_SAMPLES
     }
 
+    pub fn minrtt_percentile_valid(rtinfo: &RouteInfo, percentile: MinRttPercentile) -> bool {
+        RouteInfo::minrtt_valid(rtinfo) && rtinfo.minrtt_percentile(percentile).is_some()
+    }
+
     pub fn hdratio_valid(rtinfo: &RouteInfo) -> bool {
         rtinfo.hdratio_num_samples >= RouteInfo::MIN_SAMPLES
     }
@@ -650,6 +786,21 @@ pub(crate) mod tests {
             timebin
         }
 
+        /// As `mock_minrtt_p50` with P50s of 50 ms and the given P25s.
+        pub(crate) fn mock_minrtt_p25(
+            time: u64,
+            pri_minrtt_p25: u16,
+            alt_minrtt_p25: u16,
+            minrtt_p25_ci_halfwidth: u16,
+        ) -> TimeBin {
+            let mut timebin = TimeBin::mock_minrtt_p50(time, 50, 50, minrtt_p25_ci_halfwidth);
+            for (rtopt, p25) in timebin.num2route.iter_mut().zip(&[pri_minrtt_p25, alt_minrtt_p25])
+            {
+                rtopt.as_mut().unwrap().minrtt_ms_p25 = Some((*p25, minrtt_p25_ci_halfwidth));
+            }
+            timebin
+        }
+
         pub(crate) fn mock_week_hdratio_p50(
             bin_duration_secs: u64,
             pri_hdratio50_even: f32,
@@ -701,6 +852,23 @@ pub(crate) mod tests {
             timebin
         }
 
+        /// As `mock_hdratio_p50` with the given mean HD-ratios and
+        /// sample variance.
+        pub(crate) fn mock_hdratio_mean(
+            time: u64,
+            pri_hdratio: f32,
+            alt_hdratio: f32,
+            hdratio_var: f32,
+        ) -> TimeBin {
+            let mut timebin = TimeBin::mock_hdratio_p50(time, 0.9, 0.9, 0.01);
+            for (rtopt, hdratio) in timebin.num2route.iter_mut().zip(&[pri_hdratio, alt_hdratio]) {
+                let rtinfo: &mut RouteInfo = rtopt.as_mut().unwrap();
+                rtinfo.hdratio = *hdratio;
+                rtinfo.hdratio_var = hdratio_var;
+            }
+            timebin
+        }
+
         pub(crate) fn mock_hdratio_boot(
             time: u64,
             pri_hdratio_boot: f32,
@@ -745,11 +913,13 @@ pub(crate) mod tests {
                     PeerType::Transit
                 },
                 minrtt_num_samples: 200,
+                minrtt_ms_p10: None,
+                minrtt_ms_p25: None,
                 minrtt_ms_p50,
                 minrtt_ms_p50_ci_halfwidth,
                 hdratio_num_samples: 200,
-                // hdratio: 0.9,
-                // hdratio_var: 0.01,
+                hdratio: 0.9,
+                hdratio_var: 0.01,
                 hdratio_p50: 1.0,
                 hdratio_p50_ci_halfwidth: 0.01,
                 hdratio_boot: 0.9,
@@ -775,11 +945,13 @@ pub(crate) mod tests {
                     PeerType::Transit
                 },
                 minrtt_num_samples: RouteInfo::MOCK_NUM_SAMPLES,
+                minrtt_ms_p10: None,
+                minrtt_ms_p25: None,
                 minrtt_ms_p50: 20,
                 minrtt_ms_p50_ci_halfwidth: 1,
                 hdratio_num_samples: RouteInfo::MOCK_NUM_SAMPLES,
-                // hdratio: 0.9,
-                // hdratio_var: 0.2,
+                hdratio: 0.9,
+                hdratio_var: 0.2,
                 hdratio_p50,
                 hdratio_p50_ci_halfwidth,
                 hdratio_boot: 0.9,
@@ -802,11 +974,13 @@ pub(crate) mod tests {
                 bgp_as_path_prepends: 1,
                 peer_type: PeerType::Transit,
                 minrtt_num_samples: RouteInfo::MOCK_NUM_SAMPLES,
+                minrtt_ms_p10: None,
+                minrtt_ms_p25: None,
                 minrtt_ms_p50: 20,
                 minrtt_ms_p50_ci_halfwidth: 1,
                 hdratio_num_samples: RouteInfo::MOCK_NUM_SAMPLES,
-                // hdratio: 0.9,
-                // hdratio_var: 0.1,
+                hdratio: 0.9,
+                hdratio_var: 0.1,
                 hdratio_p50: 1.0,
                 hdratio_p50_ci_halfwidth: 0.01,
                 hdratio_boot,
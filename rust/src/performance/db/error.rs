@@ -51,7 +51,7 @@ impl From<ipnet::AddrParseError> for ParseError {
     fn from(error: ipnet::AddrParseError) -> Self {
         ParseError {
             kind: ParseErrorKind::AddrParseError,
-            message: format!("Cannot parse address: {}", error.to_string()),
+            message: format!("Cannot parse address: {}", error),
         }
     }
 }
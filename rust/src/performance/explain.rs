@@ -0,0 +1,226 @@
+use crate::performance::db;
+use crate::performance::perfstats::{PathSummary, TemporalConfig, TimeBinSummarizer};
+
+/// Bitmask accepting routes of all `db::PeerType`s.
+pub(crate) const ANY_PEER_TYPE: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Metric {
+    MinRtt,
+    HdRatio,
+    /// Routes need enough samples for both metrics.
+    Both,
+}
+
+/// How a `TimeBinSummarizer` picked routes from a `TimeBin`, used to
+/// describe why each route was selected or skipped.
+pub(crate) struct Selection<'a> {
+    /// Describes how the alternate is chosen among valid routes.
+    pub rule: &'static str,
+    /// Routes need enough samples for this metric to be valid.
+    pub metric: Metric,
+    pub primary_bitmask: u32,
+    /// `None` for summarizers that do not use alternate routes.
+    pub alternate_bitmask: Option<u32>,
+    pub primary: Option<&'a db::RouteInfo>,
+    pub alternate: Option<&'a db::RouteInfo>,
+}
+
+impl Metric {
+    fn name(self) -> &'static str {
+        match self {
+            Metric::MinRtt => "MinRTT",
+            Metric::HdRatio => "HD-ratio",
+            Metric::Both => "MinRTT or HD-ratio",
+        }
+    }
+
+    fn samples(self, rtinfo: &db::RouteInfo) -> u32 {
+        match self {
+            Metric::MinRtt => rtinfo.minrtt_num_samples,
+            Metric::HdRatio => rtinfo.hdratio_num_samples,
+            Metric::Both => rtinfo.minrtt_num_samples.min(rtinfo.hdratio_num_samples),
+        }
+    }
+}
+
+impl<'a> Selection<'a> {
+    /// One line per route in `bin`.
+    pub fn describe(&self, bin: &db::TimeBin) -> Vec<String> {
+        let mut lines: Vec<String> = vec![self.rule.to_string()];
+        for (i, rtopt) in bin.num2route.iter().enumerate() {
+            let rtinfo: &db::RouteInfo = match rtopt {
+                None => continue,
+                Some(rtbox) => rtbox,
+            };
+            let status: String = if i == 0 {
+                match self.invalid_reason(rtinfo, self.primary_bitmask) {
+                    None => "primary".to_string(),
+                    Some(reason) => format!("primary skipped, {}", reason),
+                }
+            } else {
+                match self.alternate_bitmask {
+                    None => "unused".to_string(),
+                    Some(_) if rtinfo.apm_route_num == 1 => {
+                        "skipped, apm_route_num == 1 (preferred route)".to_string()
+                    }
+                    Some(bitmask) => match self.invalid_reason(rtinfo, bitmask) {
+                        Some(reason) => format!("skipped, {}", reason),
+                        None if is_same(self.alternate, rtinfo) => "alternate".to_string(),
+                        None => "not selected".to_string(),
+                    },
+                }
+            };
+            lines.push(format!("r{} {}: {}", i, describe_route(rtinfo), status));
+        }
+        if self.primary.is_none() {
+            lines.push("no valid primary route".to_string());
+        } else if self.alternate_bitmask.is_some() && self.alternate.is_none() {
+            lines.push("no valid alternate route".to_string());
+        }
+        lines
+    }
+
+    fn invalid_reason(&self, rtinfo: &db::RouteInfo, bitmask: u32) -> Option<String> {
+        if bitmask & (1 << rtinfo.peer_type as u8) == 0 {
+            Some(format!("peer type {:?} not in bitmask {}", rtinfo.peer_type, bitmask))
+        } else if self.metric.samples(rtinfo) < db::RouteInfo::MIN_SAMPLES {
+            Some(format!(
+                "{} samples {} < {}",
+                self.metric.name(),
+                self.metric.samples(rtinfo),
+                db::RouteInfo::MIN_SAMPLES
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+fn is_same(selected: Option<&db::RouteInfo>, rtinfo: &db::RouteInfo) -> bool {
+    selected.is_some_and(|sel| std::ptr::eq(sel, rtinfo))
+}
+
+pub(crate) fn describe_route(rtinfo: &db::RouteInfo) -> String {
+    format!(
+        "apm_route_num {} {:?} minrtt50 {}+-{} ({} samples) hdratio50 {:0.3}+-{:0.3} ({} samples)",
+        rtinfo.apm_route_num,
+        rtinfo.peer_type,
+        rtinfo.minrtt_ms_p50,
+        rtinfo.minrtt_ms_p50_ci_halfwidth,
+        rtinfo.minrtt_num_samples,
+        rtinfo.hdratio_p50,
+        rtinfo.hdratio_p50_ci_halfwidth,
+        rtinfo.hdratio_num_samples
+    )
+}
+
+/// Compares a CI width against the maximum above which a bin is a
+/// `WideConfidenceInterval`.
+pub(crate) fn check_width(name: &str, width: f32, max: f32) -> String {
+    if width > max {
+        format!("{} {:0.3} > {:0.3}: WideConfidenceInterval", name, width, max)
+    } else {
+        format!("{} {:0.3} <= {:0.3}", name, width, max)
+    }
+}
+
+/// Compares `value` against the threshold for a bin to be shifted.
+pub(crate) fn check_shift(name: &str, value: f32, op: &str, thresh: f32, holds: bool) -> String {
+    format!(
+        "{} {:0.3} {} {:0.3}: {}",
+        name,
+        value,
+        op,
+        thresh,
+        if holds {
+            "holds"
+        } else {
+            "fails"
+        }
+    )
+}
+
+/// Describes every bin decision of `summarizer` for `pathid` and how
+/// the path is classified under each of `tempconfigs`.  Returns `None`
+/// if `pathid` is not in `db`.
+pub fn explain_path(
+    db: &db::DB,
+    pathid: &db::PathId,
+    summarizer: &dyn TimeBinSummarizer,
+    tempconfigs: &[TemporalConfig],
+) -> Option<Vec<String>> {
+    let pinfo: &db::PathInfo = db.pathid2info.get(pathid)?;
+    let mut lines: Vec<String> = vec![
+        format!(
+            "path {} bins {} total_bins {} bytes {}",
+            pathid.text(),
+            pinfo.time2bin.len(),
+            db.total_bins,
+            pinfo.total_traffic
+        ),
+        format!("summarizer {}", summarizer.prefix()),
+    ];
+    for (time, bin) in pinfo.time2bin.iter() {
+        lines.push(format!("time {} bytes {}", time, bin.bytes_acked_sum));
+        for line in summarizer.explain(pathid, bin) {
+            lines.push(format!("  {}", line));
+        }
+        lines.push(format!("  => {}", summarizer.summarize(pathid, bin).describe()));
+    }
+    for tempconfig in tempconfigs.iter() {
+        let psum = PathSummary::build_from_bins(
+            pathid,
+            pinfo.time2bin.iter(),
+            db.total_bins,
+            summarizer,
+            tempconfig,
+        );
+        lines.push(format!("tempconfig {}", tempconfig.prefix()));
+        for line in psum.explain_class() {
+            lines.push(format!("  {}", line));
+        }
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::performance::summarizers::opportunity::MinRtt50ImprovementSummarizer;
+    use crate::performance::summarizers::relationships::MinRtt50RelationshipSummarizer;
+
+    #[test]
+    fn test_selection() {
+        let pid: db::PathId = db::tests::make_path_id();
+        let mut bin = db::TimeBin::mock_minrtt_p50(0, 60, 50, 1);
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: true,
+        };
+        let lines: Vec<String> = summarizer.explain(&pid, &bin);
+        assert!(lines[1].starts_with("r0 ") && lines[1].ends_with(": primary"));
+        assert!(lines[2].starts_with("r1 ") && lines[2].ends_with(": alternate"));
+        assert!(lines.iter().any(|l| l.starts_with("MinRTT P50 diff lower bound")));
+
+        bin.num2route[1].as_mut().unwrap().minrtt_num_samples = 10;
+        let lines: Vec<String> = summarizer.explain(&pid, &bin);
+        assert!(lines[2].ends_with("skipped, MinRTT samples 10 < 30"));
+        assert!(lines.last().unwrap() == "no valid alternate route");
+
+        let relationship = MinRtt50RelationshipSummarizer {
+            primary_bitmask: 1 << db::PeerType::Transit as u8,
+            alternate_bitmask: ANY_PEER_TYPE,
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 10.0,
+            compare_lower_bound: true,
+        };
+        let lines: Vec<String> = relationship.explain(&pid, &bin);
+        let primary_type = bin.num2route[0].as_ref().unwrap().peer_type;
+        assert!(lines[1].contains(&format!("peer type {:?} not in bitmask", primary_type)));
+        assert!(lines.last().unwrap() == "no valid primary route");
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::error;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::performance::db;
+use crate::performance::perfstats::TemporalConfig;
+use crate::performance::summarizers::registry::{Params, SummarizerSpec};
+use crate::performance::windows::WindowConfig;
+
+/// Information shared by all output directories of a run.
+#[derive(Clone, Debug, Serialize)]
+pub struct RunInfo {
+    pub crate_version: String,
+    /// Seconds since the UNIX epoch when the run started.
+    pub timestamp: u64,
+    pub inputs: Vec<InputFile>,
+    /// `None` for runs over saved summaries, e.g., `perfstats merge`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db: Option<DBStats>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InputFile {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DBStats {
+    pub rows: u32,
+    pub paths: usize,
+    pub total_bins: u32,
+    pub total_traffic: u128,
+    pub error_counts: BTreeMap<String, u32>,
+}
+
+/// One output directory and the configuration that produced it.
+#[derive(Clone, Debug, Serialize)]
+pub struct OutputEntry {
+    /// Path relative to the run's output directory.
+    pub dir: PathBuf,
+    /// One of `summary`, `windows`, `sensitivity` or `comparison`.
+    pub kind: &'static str,
+    pub summarizer: SummarizerInfo,
+    /// The summarizer `summarizer` is compared against in a
+    /// `comparison`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<SummarizerInfo>,
+    pub tempconfig: TemporalConfig,
+    pub window: Option<WindowConfig>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SummarizerInfo {
+    /// Summarizer type, `None` if the spec that built the summarizer
+    /// is unknown, e.g., for summaries saved by older versions.
+    #[serde(rename = "type")]
+    pub stype: Option<String>,
+    pub prefix: String,
+    /// The spec's parameters, as given to `SummarizerSpec::build`.
+    pub params: Params,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    #[serde(flatten)]
+    run: &'a RunInfo,
+    #[serde(flatten)]
+    output: &'a OutputEntry,
+}
+
+#[derive(Serialize)]
+struct Index<'a> {
+    #[serde(flatten)]
+    run: &'a RunInfo,
+    outputs: &'a [OutputEntry],
+}
+
+impl RunInfo {
+    /// Inputs that cannot be read are left out, as they are left out
+    /// of `db`.
+    pub fn build(inputs: &[PathBuf], db: Option<&db::DB>) -> RunInfo {
+        let inputs: Vec<InputFile> = inputs
+            .iter()
+            .filter_map(|path| {
+                InputFile::build(path)
+                    .map_err(|e| {
+                        error!("could not hash {:?}", path);
+                        error!("{:?}", e);
+                    })
+                    .ok()
+            })
+            .collect();
+        RunInfo {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            inputs,
+            db: db.map(DBStats::build),
+        }
+    }
+
+    /// Writes `manifest.json` into `outdir/entry.dir`.
+    pub fn dump_manifest(&self, outdir: &Path, entry: &OutputEntry) -> Result<(), io::Error> {
+        let mut filepath = outdir.to_path_buf();
+        filepath.push(&entry.dir);
+        fs::create_dir_all(&filepath)?;
+        filepath.push("manifest.json");
+        let manifest = Manifest {
+            run: self,
+            output: entry,
+        };
+        dump_json(&filepath, &manifest)
+    }
+
+    /// Writes `index.json` into `outdir` listing all `entries`.
+    pub fn dump_index(&self, outdir: &Path, entries: &[OutputEntry]) -> Result<(), io::Error> {
+        fs::create_dir_all(outdir)?;
+        let mut filepath = outdir.to_path_buf();
+        filepath.push("index.json");
+        let index = Index {
+            run: self,
+            outputs: entries,
+        };
+        dump_json(&filepath, &index)
+    }
+}
+
+impl InputFile {
+    pub fn build(path: &Path) -> Result<InputFile, io::Error> {
+        let mut file = fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; 1 << 20];
+        let mut bytes: u64 = 0;
+        loop {
+            let n: usize = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            bytes += n as u64;
+        }
+        let sha256: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(InputFile {
+            path: path.to_path_buf(),
+            bytes,
+            sha256,
+        })
+    }
+}
+
+impl DBStats {
+    pub fn build(db: &db::DB) -> DBStats {
+        DBStats {
+            rows: db.rows,
+            paths: db.pathid2info.len(),
+            total_bins: db.total_bins,
+            total_traffic: db.total_traffic,
+            error_counts: db.error_counts(),
+        }
+    }
+}
+
+impl OutputEntry {
+    pub fn new(
+        dir: PathBuf,
+        kind: &'static str,
+        summarizer: SummarizerInfo,
+        tempconfig: &TemporalConfig,
+        window: Option<WindowConfig>,
+    ) -> OutputEntry {
+        OutputEntry {
+            dir,
+            kind,
+            summarizer,
+            reference: None,
+            tempconfig: *tempconfig,
+            window,
+        }
+    }
+}
+
+impl SummarizerInfo {
+    /// Describes a summarizer with its `prefix()` and the spec it was
+    /// built from.
+    pub fn new(prefix: String, spec: Option<&SummarizerSpec>) -> SummarizerInfo {
+        SummarizerInfo {
+            stype: spec.map(|s| s.name.clone()),
+            prefix,
+            params: spec.map(|s| s.params.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+fn dump_json<T: Serialize>(filepath: &Path, value: &T) -> Result<(), io::Error> {
+    let file =
+        fs::OpenOptions::new().read(true).write(true).truncate(true).create(true).open(filepath)?;
+    let mut bw = io::BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut bw, value)?;
+    writeln!(bw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use crate::performance::perfstats::TimeBinSummarizer;
+
+    const TEMPCONFIG: TemporalConfig = TemporalConfig {
+        bin_duration_secs: 900,
+        min_days: 7,
+        min_frac_existing_bins: 1.0,
+        min_frac_bins_with_alternate: 1.0,
+        min_frac_valid_bins: 0.8,
+        continuous_min_frac_shifted_bins: 0.8,
+        diurnal_min_bad_bins: 24,
+        diurnal_bad_bin_min_prob_shift: 0.8,
+        diurnal_permutations: 0,
+        diurnal_max_pvalue: 1.0,
+        uneventful_max_frac_shifted_bins: 0.20,
+        weekly_min_weeks: 0,
+        weekly_min_autocorr: 1.0,
+        shift_enter_min_bins: 1,
+        shift_exit_min_bins: 1,
+    };
+
+    #[test]
+    fn test_manifest() -> Result<(), Box<dyn std::error::Error>> {
+        let mut outdir = std::env::temp_dir();
+        outdir.push(format!("fbperf-test-manifest-{}", std::process::id()));
+        fs::create_dir_all(&outdir)?;
+        let mut input = outdir.clone();
+        input.push("input.csv.gz");
+        fs::write(&input, "abc")?;
+
+        let database = db::DB::default();
+        let run = RunInfo::build(std::slice::from_ref(&input), Some(&database));
+        assert!(run.inputs[0].bytes == 3);
+        assert!(
+            run.inputs[0].sha256
+                == "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        // The prefix rounds thresh to 0.13.
+        let spec = SummarizerSpec::parse("hdratio50-opp:thresh=0.125,diff_ci=0.1,bound=true")?;
+        let summarizer: Arc<dyn TimeBinSummarizer> = spec.build(&database)?;
+        let mut dir = PathBuf::from(TEMPCONFIG.prefix());
+        dir.push(summarizer.prefix());
+        let info = SummarizerInfo::new(summarizer.prefix(), Some(&spec));
+        let entry = OutputEntry::new(dir.clone(), "summary", info, &TEMPCONFIG, None);
+        run.dump_manifest(&outdir, &entry)?;
+        run.dump_index(&outdir, &[entry])?;
+
+        let mut filepath = outdir.clone();
+        filepath.push(&dir);
+        filepath.push("manifest.json");
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(filepath)?)?;
+        assert!(manifest["summarizer"]["type"] == "hdratio50-opp");
+        assert!(manifest["summarizer"]["params"]["thresh"] == 0.125);
+        assert!(manifest.get("reference").is_none());
+        assert!(manifest["tempconfig"]["diurnal_min_bad_bins"] == 24);
+        assert!(manifest["crate_version"] == env!("CARGO_PKG_VERSION"));
+        assert!(manifest["db"]["rows"] == 0);
+
+        let mut filepath = outdir.clone();
+        filepath.push("index.json");
+        let index: serde_json::Value = serde_json::from_str(&fs::read_to_string(filepath)?)?;
+        assert!(index["outputs"].as_array().unwrap().len() == 1);
+        assert!(index["inputs"][0]["bytes"] == 3);
+
+        fs::remove_dir_all(&outdir)?;
+        Ok(())
+    }
+}
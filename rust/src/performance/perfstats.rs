@@ -1,17 +1,25 @@
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::fmt;
 use std::fs;
 use std::io;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::Arc;
 
+use flate2::bufread::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use log::{error, info};
 use num_enum::TryFromPrimitive;
+use serde::{Deserialize, Serialize};
 use serde_pickle;
 
 use crate::cdf;
 use crate::performance::db;
+use crate::performance::sink::{OutputFormat, Table, TableGroup, Value};
 
 #[derive(Debug, PartialEq)]
 pub enum TimeBinSummary {
@@ -22,30 +30,297 @@ pub enum TimeBinSummary {
 
 pub trait TimeBinSummarizer: Send + Sync {
     fn summarize(&self, pathid: &db::PathId, bin: &db::TimeBin) -> TimeBinSummary;
+    /// Resolves the routes `summarize` recorded in
+    /// `TimeBinStats::selection` for `bin`.  The default resolves routes
+    /// within the bin; summarizers comparing against baselines return
+    /// theirs.
     fn get_routes<'s: 'd, 'd>(
         &'s self,
-        pathid: &db::PathId,
-        time: u64,
-        db: &'d db::DB,
-    ) -> (&'d db::RouteInfo, &'d db::RouteInfo);
+        _pathid: &db::PathId,
+        bin: &'d db::TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        selection.resolve(bin)
+    }
     fn prefix(&self) -> String;
+    /// Describes the routes and thresholds `summarize` considers for
+    /// `bin`, one line per step, for `perfstats explain`.
+    fn explain(&self, _pathid: &db::PathId, _bin: &db::TimeBin) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl TimeBinSummary {
+    pub fn describe(&self) -> String {
+        match self {
+            TimeBinSummary::NoRoute => "NoRoute".to_string(),
+            TimeBinSummary::WideConfidenceInterval => "WideConfidenceInterval".to_string(),
+            TimeBinSummary::Valid(bs) => format!(
+                "Valid diff {:0.3} [{:0.3}, {:0.3}] {:?}->{:?} shifted {}",
+                bs.diff_ci,
+                bs.diff_ci_lb,
+                bs.diff_ci_ub,
+                bs.primary_peer_type,
+                bs.alternate_peer_type,
+                bs.is_shifted
+            ),
+        }
+    }
 }
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct TimeBinStats {
     pub bytes: u64,
     pub diff_ci: f32,
-    pub diff_ci_halfwidth: f32,
+    /// Bounds of the CI of `diff_ci`, which need not be symmetric, e.g.,
+    /// for bootstrapped CIs.
+    pub diff_ci_lb: f32,
+    pub diff_ci_ub: f32,
     pub primary_peer_type: db::PeerType,
     pub alternate_peer_type: db::PeerType,
     pub bitmask: u8,
+    /// Effective shift decision after `TemporalConfig` hysteresis.
     pub is_shifted: bool,
+    /// Shift decision as made by the `TimeBinSummarizer`; set by
+    /// `PathSummary::build`.
+    pub is_shifted_raw: bool,
+    /// Set by `DBSummary::build`.
+    pub route_diffs: RouteDiffs,
+    /// Set by summarizers that compare routes on both MinRTT and
+    /// HD-ratio.
+    #[serde(default)]
+    pub pareto: Option<ParetoStats>,
+    /// Set by summarizers that test every alternate against the
+    /// primary route.
+    #[serde(default)]
+    pub alternates: Option<AlternateCounts>,
+    /// Set by summarizers that compare every route against its own
+    /// baseline.
+    #[serde(default)]
+    pub attribution: Option<DegradationAttribution>,
+    /// The routes compared to compute `diff_ci`.
+    #[serde(default)]
+    pub selection: Option<RouteSelection>,
+}
+
+/// The routes a summarizer compared in a bin, as indices into
+/// `db::TimeBin::num2route`.  The variant gives the role of the route
+/// the primary is compared against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RouteSelection {
+    /// The primary route against an alternate in the same bin.
+    Alternate {
+        primary: u8,
+        alternate: u8,
+    },
+    /// The primary route against a baseline route the summarizer chose
+    /// among other bins.
+    Baseline {
+        primary: u8,
+    },
+}
+
+impl RouteSelection {
+    /// `None` if the routes are not borrowed from `bin`.
+    pub fn alternate(
+        bin: &db::TimeBin,
+        primary: &db::RouteInfo,
+        alternate: &db::RouteInfo,
+    ) -> Option<RouteSelection> {
+        Some(RouteSelection::Alternate {
+            primary: bin.route_index(primary)?,
+            alternate: bin.route_index(alternate)?,
+        })
+    }
+
+    /// `None` if `primary` is not borrowed from `bin`.
+    pub fn baseline(bin: &db::TimeBin, primary: &db::RouteInfo) -> Option<RouteSelection> {
+        Some(RouteSelection::Baseline {
+            primary: bin.route_index(primary)?,
+        })
+    }
+
+    pub fn primary(self) -> u8 {
+        match self {
+            RouteSelection::Alternate {
+                primary,
+                ..
+            } => primary,
+            RouteSelection::Baseline {
+                primary,
+            } => primary,
+        }
+    }
+
+    pub fn primary_route(self, bin: &db::TimeBin) -> Option<&db::RouteInfo> {
+        bin.num2route.get(usize::from(self.primary()))?.as_deref()
+    }
+
+    /// Exchanges the roles of the primary and the alternate; baselines
+    /// are kept.
+    pub fn swapped(self) -> RouteSelection {
+        match self {
+            RouteSelection::Alternate {
+                primary,
+                alternate,
+            } => RouteSelection::Alternate {
+                primary: alternate,
+                alternate: primary,
+            },
+            baseline => baseline,
+        }
+    }
+
+    /// The routes in `bin`, or `None` for baselines, which only the
+    /// summarizer knows.
+    pub fn resolve(self, bin: &db::TimeBin) -> Option<SelectedRoutes<'_>> {
+        match self {
+            RouteSelection::Alternate {
+                alternate,
+                ..
+            } => Some(SelectedRoutes::Alternate {
+                primary: self.primary_route(bin)?,
+                alternate: bin.num2route.get(usize::from(alternate))?.as_deref()?,
+            }),
+            RouteSelection::Baseline {
+                ..
+            } => None,
+        }
+    }
+}
+
+/// The routes of a `RouteSelection`.
+#[derive(Clone, Copy, Debug)]
+pub enum SelectedRoutes<'a> {
+    Alternate {
+        primary: &'a db::RouteInfo,
+        alternate: &'a db::RouteInfo,
+    },
+    Baseline {
+        baseline: &'a db::RouteInfo,
+        primary: &'a db::RouteInfo,
+    },
+}
+
+impl SelectedRoutes<'_> {
+    /// How much worse the alternate is than the primary route, or the
+    /// primary route than its baseline.
+    pub fn route_diffs(&self) -> RouteDiffs {
+        let (reference, compared) = match *self {
+            SelectedRoutes::Alternate {
+                primary,
+                alternate,
+            } => (primary, alternate),
+            SelectedRoutes::Baseline {
+                baseline,
+                primary,
+            } => (baseline, primary),
+        };
+        RouteDiffs {
+            minrtt50: db::RouteInfo::minrtt_median_diff_ci(compared, reference),
+            hdratio50: db::RouteInfo::hdratio_median_diff_ci(reference, compared),
+        }
+    }
+}
+
+/// Differences and confidence interval half-widths between the routes
+/// of `TimeBinStats::selection`, kept so CDFs can be computed without
+/// the `db::DB`.  Positive values are worse for the alternate, or for
+/// the primary route relative to its baseline.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouteDiffs {
+    pub minrtt50: (f32, f32),
+    pub hdratio50: (f32, f32),
+}
+
+/// How an alternate compares to the primary route on MinRTT P50 and
+/// HD-ratio P50.  A metric is better if the lower bound of its
+/// improvement reaches the summarizer's threshold, and a significant
+/// loss if the upper bound of its improvement is below zero.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum ParetoOutcome {
+    NoOpportunity = 0,
+    /// Better on one metric and significantly worse on the other.
+    TradeOff = 1,
+    /// Better on one metric without a significant loss on the other.
+    OneBetter = 2,
+    BothBetter = 3,
+}
+
+pub const PARETO_OUTCOMES: [ParetoOutcome; 4] = [
+    ParetoOutcome::NoOpportunity,
+    ParetoOutcome::TradeOff,
+    ParetoOutcome::OneBetter,
+    ParetoOutcome::BothBetter,
+];
+
+/// Improvements of the alternate over the primary route and their CI
+/// halfwidths; positive values favor the alternate.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParetoStats {
+    pub minrtt50_improv: (f32, f32),
+    pub hdratio50_improv: (f32, f32),
+    pub outcome: ParetoOutcome,
+}
+
+/// Which routes of a bin degraded relative to their own baselines.
+/// Degradation of all routes points to a client or access problem,
+/// degradation of the primary only to a problem on its path.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum DegradationAttribution {
+    NoDegradation = 0,
+    /// The primary degraded and at least one alternate did not.
+    PrimaryOnly = 1,
+    /// The primary and every alternate degraded.
+    AllRoutes = 2,
+    /// The primary did not degrade and at least one alternate did.
+    AlternateOnly = 3,
+}
+
+pub const DEGRADATION_ATTRIBUTIONS: [DegradationAttribution; 4] = [
+    DegradationAttribution::NoDegradation,
+    DegradationAttribution::PrimaryOnly,
+    DegradationAttribution::AllRoutes,
+    DegradationAttribution::AlternateOnly,
+];
+
+impl DegradationAttribution {
+    pub fn classify(primary_degraded: bool, degraded_alternates: usize, alternates: usize) -> Self {
+        match (primary_degraded, degraded_alternates) {
+            (true, d) if d == alternates => DegradationAttribution::AllRoutes,
+            (true, _) => DegradationAttribution::PrimaryOnly,
+            (false, 0) => DegradationAttribution::NoDegradation,
+            (false, _) => DegradationAttribution::AlternateOnly,
+        }
+    }
+
+    fn column(self) -> &'static str {
+        match self {
+            DegradationAttribution::NoDegradation => "no_degradation_bytes",
+            DegradationAttribution::PrimaryOnly => "primary_only_bytes",
+            DegradationAttribution::AllRoutes => "all_routes_bytes",
+            DegradationAttribution::AlternateOnly => "alternate_only_bytes",
+        }
+    }
+}
+
+/// Alternates tested against the primary route and how many of them
+/// are significantly better after correcting for multiple comparisons.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlternateCounts {
+    pub compared: u8,
+    pub better: u8,
 }
 
 impl TimeBinStats {
     pub const ALTERNATE_IS_LONGER: u8 = 1;
     pub const ALTERNATE_IS_PREPENDED_MORE: u8 = (1 << 1);
     pub const BEST_ALTERNATE_IS_BGP_PREFERRED: u8 = (1 << 2);
+
+    /// Half the width of the CI of `diff_ci`.
+    pub fn diff_ci_halfwidth(&self) -> f32 {
+        (self.diff_ci_ub - self.diff_ci_lb) / 2.0
+    }
 }
 
 /// Classes of temporal behavior over time.
@@ -87,10 +362,24 @@ impl TimeBinStats {
 /// least X days. This class captures `PathId`s where there is
 /// degradation/opportunity for part of the day over multiple days.
 ///
+/// The *weekday diurnal* and *weekend diurnal* classes apply the
+/// diurnal rule to weekdays (Monday--Friday) or weekend days only. These
+/// classes capture `PathId`s with degradation/opportunity during
+/// business hours or only during weekends. Days are computed in UTC.
+///
+/// The *weekly* class includes `PathId`s whose shift series has
+/// autocorrelation at a lag of one week above a threshold. This class
+/// captures `PathId`s where degradation/opportunity repeats on the same
+/// days and times every week.
+///
+/// The weekday diurnal, weekend diurnal, and weekly classes are only
+/// considered for `PathId`s with enough weeks of data (configured in
+/// the `TemporalConfig`).
+///
 /// The *episodic* class includes all remaining `PathId`s. This class
 /// captures `PathId`s that have some degradation/opportunity but do not
-/// fit into the consistent or diurnal classes.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+/// fit into the consistent, diurnal, or weekly classes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, TryFromPrimitive, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum TemporalBehavior {
     Uneventful = 0,
@@ -100,17 +389,34 @@ pub enum TemporalBehavior {
     Undersampled = 4,
     NoRoute = 5,
     MissingBins = 6,
-    Uninitialized = 7,
-    SIZE = 8,
+    WeekdayDiurnal = 7,
+    WeekendDiurnal = 8,
+    Weekly = 9,
+    #[default]
+    Uninitialized = 10,
+    SIZE = 11,
 }
-pub const VALID_TEMPORAL_BEHAVIORS: [TemporalBehavior; 4] = [
+impl FromStr for TemporalBehavior {
+    type Err = String;
+    fn from_str(s: &str) -> Result<TemporalBehavior, String> {
+        (0..TemporalBehavior::SIZE as u8)
+            .filter_map(|i| TemporalBehavior::try_from(i).ok())
+            .find(|behavior| format!("{:?}", behavior) == s)
+            .ok_or_else(|| format!("unknown temporal behavior {}", s))
+    }
+}
+
+pub const VALID_TEMPORAL_BEHAVIORS: [TemporalBehavior; 7] = [
     TemporalBehavior::Uneventful,
     TemporalBehavior::Continuous,
     TemporalBehavior::Diurnal,
     TemporalBehavior::Episodic,
+    TemporalBehavior::WeekdayDiurnal,
+    TemporalBehavior::WeekendDiurnal,
+    TemporalBehavior::Weekly,
 ];
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize)]
 pub struct TemporalConfig {
     pub bin_duration_secs: u32,
     pub min_days: u32,                         // missing class
@@ -120,11 +426,24 @@ pub struct TemporalConfig {
     pub continuous_min_frac_shifted_bins: f32, // persistent class
     pub diurnal_min_bad_bins: u16,             // diurnal class
     pub diurnal_bad_bin_min_prob_shift: f32,   // diurnal class
+    pub diurnal_permutations: u32,             // diurnal significance, 0 disables
+    pub diurnal_max_pvalue: f32,               // diurnal significance
     pub uneventful_max_frac_shifted_bins: f32, // uneventful class
+    pub weekly_min_weeks: u32,                 // weekday/weekend/weekly, 0 disables
+    pub weekly_min_autocorr: f32,              // weekly class
+    pub shift_enter_min_bins: u16,             // hysteresis, 1 disables
+    pub shift_exit_min_bins: u16,              // hysteresis, 1 disables
 }
 
 #[derive(Default)]
 pub struct DBSummary {
+    /// Prefix of the `TimeBinSummarizer` used to build the summary.
+    pub summarizer: String,
+    /// Spec the `TimeBinSummarizer` was built from, as written by
+    /// `SummarizerSpec`'s `Display`, if known.
+    pub spec: Option<String>,
+    /// Number of bins in the dataset, `db::DB::total_bins`.
+    pub total_bins: u32,
     pub pathid2summary: HashMap<Arc<db::PathId>, PathSummary>,
     pub total_shifted_bytes: u128,
     pub total_valid_bytes: u128,
@@ -133,29 +452,207 @@ pub struct DBSummary {
     total_bytes: [[u128; db::ClientContinent::SIZE as usize]; TemporalBehavior::SIZE as usize],
 }
 
-#[derive(Debug, Default)]
+/// Error merging `DBSummary`s.
+#[derive(Debug, PartialEq)]
+pub enum MergeError {
+    /// The summaries were built with different summarizers.
+    Summarizer {
+        /// Prefix of the summarizer of the summary merged into.
+        summarizer: String,
+        /// `DBSummary::spec` of the summary merged into.
+        spec: Option<String>,
+        /// Prefix of the summarizer of the summary merged.
+        other: String,
+        /// `DBSummary::spec` of the summary merged.
+        other_spec: Option<String>,
+    },
+    /// Both summaries have `count` paths in common, the lowest of which
+    /// is `path`.
+    OverlappingPaths {
+        path: db::PathId,
+        count: usize,
+    },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeError::Summarizer {
+                summarizer,
+                spec,
+                other,
+                other_spec,
+            } => {
+                let describe = |prefix: &str, spec: &Option<String>| match spec {
+                    Some(spec) => format!("{} ({})", prefix, spec),
+                    None => prefix.to_string(),
+                };
+                write!(
+                    f,
+                    "cannot merge a summary built with {} into one built with {}",
+                    describe(other, other_spec),
+                    describe(summarizer, spec)
+                )
+            }
+            MergeError::OverlappingPaths {
+                path,
+                count,
+            } => write!(
+                f,
+                "cannot merge summaries sharing {} paths, e.g., {}; shards must not overlap",
+                count,
+                path.text()
+            ),
+        }
+    }
+}
+
+impl StdError for MergeError {}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct PathSummary {
     pub time2binstats: BTreeMap<u64, TimeBinStats>,
     pub day2shifts: HashMap<u32, u32>,
     pub distinct_shifts: u16,
+    pub raw_distinct_shifts: u16,
     pub bad_bytes: u128,
-    // existing_bytes needs to come from db::DB's pathid2traffic[pathid]
+    pub existing_bytes: u128,
     pub noroute_bytes: u128,
     pub shifted_bytes: u128,
     pub valid_bytes: u128,
     pub wideci_bytes: u128,
     pub bad_bins: u16,
-    // existing_bins needs to come from db::DB's pathid2time2bin[pathid].len()
+    pub weekday_bad_bins: u16,
+    pub weekend_bad_bins: u16,
+    pub weekly_autocorr: f32,
+    /// P-value of the last diurnal permutation test run by
+    /// `classify`; the weekday and weekend tests follow the test over
+    /// all days.
+    pub diurnal_pvalue: Option<f32>,
+    pub existing_bins: u32,
     pub noroute_bins: u16,
     pub shifted_bins: u16,
+    pub raw_shifted_bins: u16,
     // valid_bins = time2binstats.len()
     pub wideci_bins: u16,
     pub temporal_behavior: TemporalBehavior,
+    /// Classes checked by `classify`, in order, up to the one that
+    /// decided `temporal_behavior`.
+    #[serde(default)]
+    pub class_steps: Vec<ClassStep>,
+}
+
+/// Comparison made by a `ClassCheck`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ClassCmp {
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+impl fmt::Display for ClassCmp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ClassCmp::Less => "<",
+            ClassCmp::LessEq => "<=",
+            ClassCmp::Greater => ">",
+            ClassCmp::GreaterEq => ">=",
+        })
+    }
+}
+
+/// A value `PathSummary::classify` compared against a threshold.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClassCheck {
+    /// Quantity computed from the path, e.g., `frac_valid_bins`.
+    pub name: String,
+    pub value: f64,
+    pub cmp: ClassCmp,
+    /// `TemporalConfig` fields the threshold comes from, if any.
+    pub thresh_name: Option<String>,
+    pub thresh: f64,
+}
+
+impl ClassCheck {
+    fn new<V, T>(name: &str, value: V, cmp: ClassCmp, thresh_name: Option<&str>, thresh: T) -> Self
+    where
+        V: Into<f64>,
+        T: Into<f64>,
+    {
+        ClassCheck {
+            name: name.to_string(),
+            value: value.into(),
+            cmp,
+            thresh_name: thresh_name.map(str::to_string),
+            thresh: thresh.into(),
+        }
+    }
+
+    pub fn holds(&self) -> bool {
+        match self.cmp {
+            ClassCmp::Less => self.value < self.thresh,
+            ClassCmp::LessEq => self.value <= self.thresh,
+            ClassCmp::Greater => self.value > self.thresh,
+            ClassCmp::GreaterEq => self.value >= self.thresh,
+        }
+    }
+}
+
+impl fmt::Display for ClassCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let number = |x: f64| {
+            if x.fract() == 0.0 {
+                format!("{}", x)
+            } else {
+                format!("{:0.4}", x)
+            }
+        };
+        write!(f, "{} {} {} ", self.name, number(self.value), self.cmp)?;
+        if let Some(thresh_name) = &self.thresh_name {
+            write!(f, "{} ", thresh_name)?;
+        }
+        f.write_str(&number(self.thresh))
+    }
+}
+
+/// A class `PathSummary::classify` considered; the path has the class
+/// if all checks hold.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClassStep {
+    pub class: TemporalBehavior,
+    pub checks: Vec<ClassCheck>,
+}
+
+impl ClassStep {
+    pub fn holds(&self) -> bool {
+        self.checks.iter().all(ClassCheck::holds)
+    }
+}
+
+impl fmt::Display for ClassStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let checks: Vec<String> = self.checks.iter().map(ClassCheck::to_string).collect();
+        write!(f, "{}: ", checks.join(" and "))?;
+        if self.holds() {
+            write!(f, "-> {:?}", self.class)
+        } else {
+            f.write_str("no")
+        }
+    }
+}
+
+/// Counts of paths (and their bytes) moving between `TemporalBehavior`s,
+/// indexed by [from][to].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransitionMatrix {
+    pub paths: [[u64; TemporalBehavior::SIZE as usize]; TemporalBehavior::SIZE as usize],
+    pub bytes: [[u128; TemporalBehavior::SIZE as usize]; TemporalBehavior::SIZE as usize],
 }
 
 impl TemporalConfig {
-    pub fn dump(&self, dir: &PathBuf) -> Result<(), io::Error> {
-        let mut filename = dir.clone();
+    pub fn dump(&self, dir: &Path) -> Result<(), io::Error> {
+        let mut filename = dir.to_path_buf();
         filename.push("temporal-config.txt");
         let file = fs::OpenOptions::new()
             .read(true)
@@ -166,8 +663,41 @@ impl TemporalConfig {
         let mut bw = io::BufWriter::new(file);
         writeln!(bw, "{:?}", self)
     }
+    /// Parameter names and values, in declaration order.
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("bin_duration_secs", self.bin_duration_secs.to_string()),
+            ("min_days", self.min_days.to_string()),
+            ("min_frac_existing_bins", self.min_frac_existing_bins.to_string()),
+            ("min_frac_bins_with_alternate", self.min_frac_bins_with_alternate.to_string()),
+            ("min_frac_valid_bins", self.min_frac_valid_bins.to_string()),
+            ("continuous_min_frac_shifted_bins", self.continuous_min_frac_shifted_bins.to_string()),
+            ("diurnal_min_bad_bins", self.diurnal_min_bad_bins.to_string()),
+            ("diurnal_bad_bin_min_prob_shift", self.diurnal_bad_bin_min_prob_shift.to_string()),
+            ("diurnal_permutations", self.diurnal_permutations.to_string()),
+            ("diurnal_max_pvalue", self.diurnal_max_pvalue.to_string()),
+            ("uneventful_max_frac_shifted_bins", self.uneventful_max_frac_shifted_bins.to_string()),
+            ("weekly_min_weeks", self.weekly_min_weeks.to_string()),
+            ("weekly_min_autocorr", self.weekly_min_autocorr.to_string()),
+            ("shift_enter_min_bins", self.shift_enter_min_bins.to_string()),
+            ("shift_exit_min_bins", self.shift_exit_min_bins.to_string()),
+        ]
+    }
+    /// Parameters whose values differ from `reference`, as
+    /// `name=value` strings.
+    pub fn diff(&self, reference: &TemporalConfig) -> Vec<String> {
+        self.params()
+            .into_iter()
+            .zip(reference.params())
+            .filter(|((_, value), (_, refvalue))| value != refvalue)
+            .map(|((name, value), _)| format!("{}={}", name, value))
+            .collect()
+    }
+    pub fn is_smoothed(&self) -> bool {
+        self.shift_enter_min_bins > 1 || self.shift_exit_min_bins > 1
+    }
     pub fn prefix(&self) -> String {
-        format!(
+        let mut prefix = format!(
             "tempconfig--bin-{}--days-{}--fracExisting-{:0.2}--fracWithAlternate-{:0.2}--fracValid-{:0.2}--cont-{:0.2}--minBadBins-{}--badBinPrev-{:0.2}--uneventful-{:0.2}",
             self.bin_duration_secs,
             self.min_days,
@@ -178,7 +708,26 @@ impl TemporalConfig {
             self.diurnal_min_bad_bins,
             self.diurnal_bad_bin_min_prob_shift,
             self.uneventful_max_frac_shifted_bins
-        )
+        );
+        if self.diurnal_permutations > 0 {
+            prefix.push_str(&format!(
+                "--perms-{}--pvalue-{:0.3}",
+                self.diurnal_permutations, self.diurnal_max_pvalue
+            ));
+        }
+        if self.weekly_min_weeks > 0 {
+            prefix.push_str(&format!(
+                "--weeks-{}--weeklyAutocorr-{:0.2}",
+                self.weekly_min_weeks, self.weekly_min_autocorr
+            ));
+        }
+        if self.is_smoothed() {
+            prefix.push_str(&format!(
+                "--enter-{}--exit-{}",
+                self.shift_enter_min_bins, self.shift_exit_min_bins
+            ));
+        }
+        prefix
     }
 }
 
@@ -188,76 +737,169 @@ impl DBSummary {
         summarizer: &dyn TimeBinSummarizer,
         tempconfig: &TemporalConfig,
     ) -> DBSummary {
-        let mut dbsum = DBSummary::default();
+        let mut dbsum = DBSummary {
+            summarizer: summarizer.prefix(),
+            total_bins: db.total_bins,
+            ..Default::default()
+        };
         for (pid, pinfo) in &db.pathid2info {
-            let psum =
-                PathSummary::build(&pid, &pinfo.time2bin, db.total_bins, summarizer, tempconfig);
+            let mut psum =
+                PathSummary::build(pid, &pinfo.time2bin, db.total_bins, summarizer, tempconfig);
             // if psum.valid_bytes == 0 {
             //     continue;
             // }
-            // dbsum.valid_bytes += psum.valid_bytes;
-            dbsum.shifted_bytes[psum.temporal_behavior as usize][pid.client_continent as usize] +=
-                psum.shifted_bytes;
-            dbsum.valid_bytes[psum.temporal_behavior as usize][pid.client_continent as usize] +=
-                psum.valid_bytes;
-            dbsum.total_bytes[psum.temporal_behavior as usize][pid.client_continent as usize] +=
-                db.pathid2info[pid].total_traffic;
-            dbsum.pathid2summary.insert(Arc::clone(&pid), psum);
+            for (time, binstats) in psum.time2binstats.iter_mut() {
+                let bin: &db::TimeBin = &pinfo.time2bin[time];
+                if let Some(routes) =
+                    binstats.selection.and_then(|sel| summarizer.get_routes(pid, bin, sel))
+                {
+                    binstats.route_diffs = routes.route_diffs();
+                }
+            }
+            dbsum.pathid2summary.insert(Arc::clone(pid), psum);
         }
+        dbsum.accumulate();
         dbsum
     }
 
-    pub fn reclassify(&mut self, db: &db::DB, tempconfig: &TemporalConfig) {
+    /// Classifies all paths again under `tempconfig`, which must use
+    /// the same `bin_duration_secs` as the config used to build the
+    /// summary.
+    pub fn reclassify(&mut self, tempconfig: &TemporalConfig) {
+        for psum in self.pathid2summary.values_mut() {
+            psum.classify(self.total_bins, tempconfig);
+        }
+        self.accumulate();
+    }
+
+    /// Recomputes the bytes per class and continent from the
+    /// `PathSummary`s.
+    fn accumulate(&mut self) {
         self.shifted_bytes =
             [[0u128; db::ClientContinent::SIZE as usize]; TemporalBehavior::SIZE as usize];
         self.valid_bytes =
             [[0u128; db::ClientContinent::SIZE as usize]; TemporalBehavior::SIZE as usize];
         self.total_bytes =
             [[0u128; db::ClientContinent::SIZE as usize]; TemporalBehavior::SIZE as usize];
-        for (pid, psum) in self.pathid2summary.iter_mut() {
-            let pinfo = &db.pathid2info[pid];
-            psum.classify(db.total_bins, pinfo.time2bin.len() as u32, tempconfig);
+        for (pid, psum) in self.pathid2summary.iter() {
             self.shifted_bytes[psum.temporal_behavior as usize][pid.client_continent as usize] +=
                 psum.shifted_bytes;
             self.valid_bytes[psum.temporal_behavior as usize][pid.client_continent as usize] +=
                 psum.valid_bytes;
             self.total_bytes[psum.temporal_behavior as usize][pid.client_continent as usize] +=
-                pinfo.total_traffic;
+                psum.existing_bytes;
         }
     }
 
-    pub fn dump(
-        &self,
-        path: &PathBuf,
-        db: &db::DB,
-        sum: &dyn TimeBinSummarizer,
-    ) -> Result<(), io::Error> {
-        self.dump_cdfs(path, db, sum)?;
-        self.dump_temporal_tables(path)?;
-        self.dump_path_summaries(path)?;
+    /// Paths sorted by `PathId` so outputs are deterministic.
+    pub fn sorted_paths(&self) -> Vec<(&db::PathId, &PathSummary)> {
+        let mut paths: Vec<(&db::PathId, &PathSummary)> =
+            self.pathid2summary.iter().map(|(pid, psum)| (pid.as_ref(), psum)).collect();
+        paths.sort_unstable_by_key(|(pid, _psum)| *pid);
+        paths
+    }
+
+    /// Writes the summary as gzipped JSON.
+    pub fn save(&self, file: &Path) -> Result<(), io::Error> {
+        let saved = SavedSummaryRef {
+            summarizer: &self.summarizer,
+            spec: self.spec.as_deref(),
+            total_bins: self.total_bins,
+            paths: self.sorted_paths(),
+        };
+        let f =
+            fs::OpenOptions::new().read(true).write(true).truncate(true).create(true).open(file)?;
+        let mut gzw = GzEncoder::new(io::BufWriter::new(f), Compression::default());
+        serde_json::to_writer(&mut gzw, &saved)?;
+        gzw.finish()?.flush()
+    }
+
+    /// Loads a summary written by `save`; paths keep the
+    /// classification they had when saved.
+    pub fn load(file: &Path) -> Result<DBSummary, io::Error> {
+        let f = fs::File::open(file)?;
+        let gzrdr = GzDecoder::new(BufReader::new(f));
+        let saved: SavedSummary = serde_json::from_reader(BufReader::new(gzrdr))?;
+        let mut dbsum = DBSummary {
+            summarizer: saved.summarizer,
+            spec: saved.spec,
+            total_bins: saved.total_bins,
+            pathid2summary: saved
+                .paths
+                .into_iter()
+                .map(|(pid, psum)| (Arc::new(pid), psum))
+                .collect(),
+            ..Default::default()
+        };
+        dbsum.accumulate();
+        Ok(dbsum)
+    }
+
+    /// Merges a summary built with the same summarizer over another
+    /// shard of the dataset.  Paths keep the classes computed against
+    /// their shard's `total_bins`; call `reclassify` after merging all
+    /// shards to get the same classes as a single pass over the dataset.
+    /// Fails if the summaries' prefixes differ, or if both know their
+    /// specs and the specs differ, as prefixes round parameters.  Also
+    /// fails if a path is in both summaries, as the result would then
+    /// depend on the order of the merges.
+    pub fn merge(&mut self, dbsum: DBSummary) -> Result<(), MergeError> {
+        let specs_differ: bool = match (&self.spec, &dbsum.spec) {
+            (Some(spec), Some(other)) => spec != other,
+            _ => false,
+        };
+        if self.summarizer != dbsum.summarizer || specs_differ {
+            return Err(MergeError::Summarizer {
+                summarizer: self.summarizer.clone(),
+                spec: self.spec.clone(),
+                other: dbsum.summarizer,
+                other_spec: dbsum.spec,
+            });
+        }
+        let shared =
+            || dbsum.pathid2summary.keys().filter(|pid| self.pathid2summary.contains_key(*pid));
+        if let Some(path) = shared().min() {
+            return Err(MergeError::OverlappingPaths {
+                path: path.as_ref().clone(),
+                count: shared().count(),
+            });
+        }
+        self.total_bins = std::cmp::max(self.total_bins, dbsum.total_bins);
+        self.pathid2summary.extend(dbsum.pathid2summary);
+        self.accumulate();
         Ok(())
     }
 
-    fn dump_cdfs(
-        &self,
-        path: &PathBuf,
-        db: &db::DB,
-        sum: &dyn TimeBinSummarizer,
-    ) -> Result<(), io::Error> {
+    pub fn dump(&self, path: &Path, formats: &[OutputFormat]) -> Result<(), io::Error> {
+        self.dump_cdfs(path)?;
+        for format in formats.iter() {
+            let sink = format.sink();
+            sink.write(path, &self.temporal_tables())?;
+            sink.write(path, &self.path_summary_table())?;
+            if let Some(group) = self.pareto_outcome_table() {
+                sink.write(path, &group)?;
+            }
+            if let Some(group) = self.better_alternates_table() {
+                sink.write(path, &group)?;
+            }
+            if let Some(group) = self.attribution_tables() {
+                sink.write(path, &group)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn dump_cdfs(&self, path: &Path) -> Result<(), io::Error> {
         std::fs::create_dir_all(path)?;
 
-        let data_specs: [(&str, &dyn Fn(f32, f32) -> f32); 3] = [
+        let data_specs: [(&str, &CdfDataFn); 3] = [
             ("ci_diff", &|diff: f32, _interval: f32| diff),
             ("ci_lb", &|diff: f32, interval: f32| diff - interval),
             ("ci_ub", &|diff: f32, interval: f32| diff + interval),
         ];
-        let metric_specs: [(&str, &dyn Fn(&db::RouteInfo, &db::RouteInfo) -> (f32, f32)); 2] = [
-            ("minrtt50", &|primary: &db::RouteInfo, alternate: &db::RouteInfo| {
-                db::RouteInfo::minrtt_median_diff_ci(alternate, primary)
-            }),
-            ("hdratio50", &|primary: &db::RouteInfo, alternate: &db::RouteInfo| {
-                db::RouteInfo::hdratio_median_diff_ci(primary, alternate)
-            }),
+        let metric_specs: [(&str, &CdfDiffCiFn); 2] = [
+            ("minrtt50", &|diffs: &RouteDiffs| diffs.minrtt50),
+            ("hdratio50", &|diffs: &RouteDiffs| diffs.hdratio50),
         ];
 
         for &weighted in [true, false].iter() {
@@ -265,7 +907,7 @@ impl DBSummary {
                 // `only_shifted_bins` used here to control whether to
                 // consider all TimeBinStats or just TimeBinStats in the
                 // table
-                let mut fpath = path.clone();
+                let mut fpath = path.to_path_buf();
                 fpath.push(format!(
                     "main_diff_weighted_{}_table_{}.cdf",
                     weighted, only_shifted_bins
@@ -283,7 +925,7 @@ impl DBSummary {
                         Some((bs.diff_ci, 1.0))
                     }
                 })?;
-                let mut fpath = path.clone();
+                let mut fpath = path.to_path_buf();
                 fpath
                     .push(format!("main_lb_weighted_{}_table_{}.cdf", weighted, only_shifted_bins));
                 self.dump_bin_cdf(&fpath, |pid: &db::PathId, _time: u64, bs: &TimeBinStats| {
@@ -294,12 +936,12 @@ impl DBSummary {
                         }
                     }
                     if weighted {
-                        Some((bs.diff_ci - bs.diff_ci_halfwidth, bs.bytes as f64))
+                        Some((bs.diff_ci_lb, bs.bytes as f64))
                     } else {
-                        Some((bs.diff_ci - bs.diff_ci_halfwidth, 1.0))
+                        Some((bs.diff_ci_lb, 1.0))
                     }
                 })?;
-                let mut fpath = path.clone();
+                let mut fpath = path.to_path_buf();
                 fpath
                     .push(format!("main_ub_weighted_{}_table_{}.cdf", weighted, only_shifted_bins));
                 self.dump_bin_cdf(&fpath, |pid: &db::PathId, _time: u64, bs: &TimeBinStats| {
@@ -310,28 +952,24 @@ impl DBSummary {
                         }
                     }
                     if weighted {
-                        Some((bs.diff_ci + bs.diff_ci_halfwidth, bs.bytes as f64))
+                        Some((bs.diff_ci_ub, bs.bytes as f64))
                     } else {
-                        Some((bs.diff_ci + bs.diff_ci_halfwidth, 1.0))
+                        Some((bs.diff_ci_ub, 1.0))
                     }
                 })?;
 
                 for (data, compute_data) in data_specs.iter() {
                     for (metric, compute_diff_ci) in metric_specs.iter() {
-                        let mut fpath = path.clone();
+                        let mut fpath = path.to_path_buf();
                         fpath.push(format!(
                             "{}_{}_weighted_{}_shifted_{}.cdf",
                             data, metric, weighted, only_shifted_bins
                         ));
                         self.dump_bin_cdf(
                             &fpath,
-                            |pathid: &db::PathId, time: u64, bs: &TimeBinStats| {
+                            |_pathid: &db::PathId, _time: u64, bs: &TimeBinStats| {
                                 compute_cdf_data(
-                                    pathid,
-                                    time,
                                     bs,
-                                    db,
-                                    sum,
                                     CdfDataConfig {
                                         only_shifted_bins,
                                         weighted,
@@ -554,7 +1192,7 @@ impl DBSummary {
         //     Some((diff + interval, bs.bytes as f32))
         // })?;
 
-        let mut fpath = path.clone();
+        let mut fpath = path.to_path_buf();
         fpath.push("frac_shifted_bins_paths.cdf");
         self.dump_path_cdf(&fpath, |_pathid, ps: &PathSummary| {
             if ps.time2binstats.is_empty() {
@@ -563,7 +1201,7 @@ impl DBSummary {
                 Some((f32::from(ps.shifted_bins) / (ps.time2binstats.len() as f32), 1.0))
             }
         })?;
-        let mut fpath = path.clone();
+        let mut fpath = path.to_path_buf();
         fpath.push("frac_shifted_bins_paths_weighted.cdf");
         self.dump_path_cdf(&fpath, |_pathid, ps: &PathSummary| {
             if ps.time2binstats.is_empty() || ps.valid_bytes == 0 {
@@ -578,7 +1216,7 @@ impl DBSummary {
 
         // This code assumes that the dataset has "full days" (otherwise
         // the number of shifts per day will be underestimated).
-        let mut fpath = path.clone();
+        let mut fpath = path.to_path_buf();
         fpath.push("average_shifts_per_day_paths.cdf");
         self.dump_path_cdf(&fpath, |_pathid, ps: &PathSummary| {
             if ps.day2shifts.is_empty() {
@@ -587,7 +1225,7 @@ impl DBSummary {
                 Some((f32::from(ps.distinct_shifts) / (ps.day2shifts.len() as f32), 1.0))
             }
         })?;
-        let mut fpath = path.clone();
+        let mut fpath = path.to_path_buf();
         fpath.push("average_shifts_per_day_paths_weighted.cdf");
         self.dump_path_cdf(&fpath, |_pathid, ps: &PathSummary| {
             if ps.day2shifts.is_empty() || ps.valid_bytes == 0 {
@@ -600,10 +1238,55 @@ impl DBSummary {
             }
         })?;
 
+        // Same as above but using the summarizer's raw decisions,
+        // before TemporalConfig hysteresis is applied.
+        let mut fpath = path.to_path_buf();
+        fpath.push("frac_raw_shifted_bins_paths.cdf");
+        self.dump_path_cdf(&fpath, |_pathid, ps: &PathSummary| {
+            if ps.time2binstats.is_empty() {
+                None
+            } else {
+                Some((f32::from(ps.raw_shifted_bins) / (ps.time2binstats.len() as f32), 1.0))
+            }
+        })?;
+        let mut fpath = path.to_path_buf();
+        fpath.push("frac_raw_shifted_bins_paths_weighted.cdf");
+        self.dump_path_cdf(&fpath, |_pathid, ps: &PathSummary| {
+            if ps.time2binstats.is_empty() || ps.valid_bytes == 0 {
+                None
+            } else {
+                Some((
+                    f32::from(ps.raw_shifted_bins) / (ps.time2binstats.len() as f32),
+                    ps.valid_bytes as f64,
+                ))
+            }
+        })?;
+        let mut fpath = path.to_path_buf();
+        fpath.push("average_raw_shifts_per_day_paths.cdf");
+        self.dump_path_cdf(&fpath, |_pathid, ps: &PathSummary| {
+            if ps.day2shifts.is_empty() {
+                None
+            } else {
+                Some((f32::from(ps.raw_distinct_shifts) / (ps.day2shifts.len() as f32), 1.0))
+            }
+        })?;
+        let mut fpath = path.to_path_buf();
+        fpath.push("average_raw_shifts_per_day_paths_weighted.cdf");
+        self.dump_path_cdf(&fpath, |_pathid, ps: &PathSummary| {
+            if ps.day2shifts.is_empty() || ps.valid_bytes == 0 {
+                None
+            } else {
+                Some((
+                    f32::from(ps.raw_distinct_shifts) / (ps.day2shifts.len() as f32),
+                    ps.valid_bytes as f64,
+                ))
+            }
+        })?;
+
         Ok(())
     }
 
-    pub fn dump_bin_cdf<F>(&self, file: &PathBuf, getdata: F) -> Result<(), io::Error>
+    pub fn dump_bin_cdf<F>(&self, file: &Path, getdata: F) -> Result<(), io::Error>
     where
         F: Fn(&db::PathId, u64, &TimeBinStats) -> Option<(f32, f64)>,
     {
@@ -619,7 +1302,7 @@ impl DBSummary {
         Ok(())
     }
 
-    pub fn dump_path_cdf<F>(&self, file: &PathBuf, getdata: F) -> Result<(), io::Error>
+    pub fn dump_path_cdf<F>(&self, file: &Path, getdata: F) -> Result<(), io::Error>
     where
         F: Fn(&db::PathId, &PathSummary) -> Option<(f32, f64)>,
     {
@@ -633,19 +1316,33 @@ impl DBSummary {
         Ok(())
     }
 
-    fn dump_temporal_tables(&self, path: &PathBuf) -> Result<(), io::Error> {
-        let mut key2data: BTreeMap<String, (String, String, String, f64, f64, f64)> =
-            BTreeMap::new();
-
-        let mut filepath = path.clone();
-        filepath.push("temporal-behavior-table.txt");
-        let file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(filepath)?;
-        let mut bw = io::BufWriter::new(file);
+    /// Bytes per continent, per class, and per class and continent.
+    pub fn temporal_tables(&self) -> TableGroup {
+        const COLUMNS: [&str; 6] = [
+            "shifted_bytes",
+            "valid_bytes",
+            "total_bytes",
+            "frac_shifted",
+            "frac_valid",
+            "frac_total",
+        ];
+        let make_row = |keys: Vec<String>, shifted: u128, valid: u128, total: u128, denom: u128| {
+            let mut row: Vec<Value> = keys.into_iter().map(Value::Str).collect();
+            row.push(Value::Int(shifted));
+            row.push(Value::Int(valid));
+            row.push(Value::Int(total));
+            row.push(Value::Float(shifted as f64 / denom as f64, 3));
+            row.push(Value::Float(valid as f64 / denom as f64, 3));
+            row.push(Value::Float(total as f64 / denom as f64, 3));
+            row
+        };
+        let make_table = |name: &'static str, keys: &[&'static str]| Table {
+            name,
+            columns: keys.iter().chain(COLUMNS.iter()).copied().collect(),
+            key_columns: keys.len(),
+            key_separator: "+",
+            sections: Vec::new(),
+        };
 
         let mut continent_shifted: Vec<u128> =
             Vec::with_capacity(db::ClientContinent::SIZE as usize);
@@ -660,71 +1357,270 @@ impl DBSummary {
         let global_total: u128 = continent_total.iter().sum::<u128>();
         assert!(continent_total.len() == db::ClientContinent::SIZE as usize);
 
+        let mut continents = make_table("temporal-behavior-continents", &["continent"]);
+        let mut rows: Vec<Vec<Value>> = Vec::new();
         for i in 0..(db::ClientContinent::SIZE as usize) {
             let cont: db::ClientContinent = db::ClientContinent::try_from(i as u8).unwrap();
-            let name: String = format!("{:?}", cont);
-            let data = (
-                continent_shifted[i].to_string(),
-                continent_valid[i].to_string(),
-                continent_total[i].to_string(),
-                continent_shifted[i] as f64 / global_total as f64,
-                continent_valid[i] as f64 / global_total as f64,
-                continent_total[i] as f64 / global_total as f64,
-            );
-            writeln!(
-                bw,
-                "{} {} {} {} {:0.3} {:0.3} {:0.3}",
-                name, data.0, data.1, data.2, data.3, data.4, data.5
-            )?;
-            key2data.insert(name, data);
+            rows.push(make_row(
+                vec![format!("{:?}", cont)],
+                continent_shifted[i],
+                continent_valid[i],
+                continent_total[i],
+                global_total,
+            ));
         }
-        writeln!(bw)?;
+        continents.sections.push(rows);
 
+        let mut classes = make_table("temporal-behavior-classes", &["class"]);
+        let mut rows: Vec<Vec<Value>> = Vec::new();
         for i in 0..(TemporalBehavior::SIZE as usize) {
             let behavior: TemporalBehavior = TemporalBehavior::try_from(i as u8).unwrap();
-            let name: String = format!("{:?}", behavior);
-            let data = (
-                self.shifted_bytes[i].iter().sum::<u128>().to_string(),
-                self.valid_bytes[i].iter().sum::<u128>().to_string(),
-                self.total_bytes[i].iter().sum::<u128>().to_string(),
-                self.shifted_bytes[i].iter().sum::<u128>() as f64 / global_total as f64,
-                self.valid_bytes[i].iter().sum::<u128>() as f64 / global_total as f64,
-                self.total_bytes[i].iter().sum::<u128>() as f64 / global_total as f64,
-            );
-            writeln!(
-                bw,
-                "{} {} {} {} {:0.3} {:0.3} {:0.3}",
-                name, data.0, data.1, data.2, data.3, data.4, data.5
-            )?;
-            key2data.insert(name, data);
+            rows.push(make_row(
+                vec![format!("{:?}", behavior)],
+                self.shifted_bytes[i].iter().sum::<u128>(),
+                self.valid_bytes[i].iter().sum::<u128>(),
+                self.total_bytes[i].iter().sum::<u128>(),
+                global_total,
+            ));
         }
-        writeln!(bw)?;
+        classes.sections.push(rows);
 
+        let mut class_continents =
+            make_table("temporal-behavior-class-continents", &["class", "continent"]);
         for i in 0..(TemporalBehavior::SIZE as usize) {
             let behavior: TemporalBehavior = TemporalBehavior::try_from(i as u8).unwrap();
+            let mut rows: Vec<Vec<Value>> = Vec::new();
             for (j, &curr_cont_total) in continent_total.iter().enumerate() {
                 let cont: db::ClientContinent = db::ClientContinent::try_from(j as u8).unwrap();
-                let name: String = format!("{:?}+{:?}", behavior, cont);
-                let data = (
-                    self.shifted_bytes[i][j].to_string(),
-                    self.valid_bytes[i][j].to_string(),
-                    self.total_bytes[i][j].to_string(),
-                    self.shifted_bytes[i][j] as f64 / curr_cont_total as f64,
-                    self.valid_bytes[i][j] as f64 / curr_cont_total as f64,
-                    self.total_bytes[i][j] as f64 / curr_cont_total as f64,
-                );
-                writeln!(
-                    bw,
-                    "{} {} {} {} {:0.3} {:0.3} {:0.3}",
-                    name, data.0, data.1, data.2, data.3, data.4, data.5
-                )?;
-                key2data.insert(name, data);
+                rows.push(make_row(
+                    vec![format!("{:?}", behavior), format!("{:?}", cont)],
+                    self.shifted_bytes[i][j],
+                    self.valid_bytes[i][j],
+                    self.total_bytes[i][j],
+                    curr_cont_total,
+                ));
+            }
+            class_continents.sections.push(rows);
+        }
+
+        TableGroup {
+            text_name: "temporal-behavior-table",
+            pickle_name: Some("temporal-behavior"),
+            tables: vec![continents, classes, class_continents],
+        }
+    }
+
+    /// One row per `PathId`.
+    pub fn path_summary_table(&self) -> TableGroup {
+        TableGroup {
+            text_name: "path-summaries",
+            pickle_name: None,
+            tables: vec![Table {
+                name: "path-summaries",
+                columns: PathSummary::COLUMNS.to_vec(),
+                key_columns: 0,
+                key_separator: " ",
+                sections: vec![self
+                    .sorted_paths()
+                    .into_iter()
+                    .map(|(pid, psum)| psum.row(pid))
+                    .collect()],
+            }],
+        }
+    }
+    /// Bins and bytes per `ParetoOutcome`, or `None` if the summarizer
+    /// does not compare routes on both metrics.
+    pub fn pareto_outcome_table(&self) -> Option<TableGroup> {
+        let mut bins: [u64; PARETO_OUTCOMES.len()] = [0; PARETO_OUTCOMES.len()];
+        let mut bytes: [u128; PARETO_OUTCOMES.len()] = [0; PARETO_OUTCOMES.len()];
+        for psum in self.pathid2summary.values() {
+            for binstats in psum.time2binstats.values() {
+                if let Some(pareto) = &binstats.pareto {
+                    bins[pareto.outcome as usize] += 1;
+                    bytes[pareto.outcome as usize] += u128::from(binstats.bytes);
+                }
+            }
+        }
+        let total_bins: u64 = bins.iter().sum();
+        if total_bins == 0 {
+            return None;
+        }
+        let total_bytes: u128 = bytes.iter().sum();
+        Some(TableGroup {
+            text_name: "pareto-outcomes",
+            pickle_name: None,
+            tables: vec![Table {
+                name: "pareto-outcomes",
+                columns: vec!["outcome", "bins", "bytes", "frac_bins", "frac_bytes"],
+                key_columns: 1,
+                key_separator: " ",
+                sections: vec![PARETO_OUTCOMES
+                    .iter()
+                    .map(|outcome| {
+                        let i = *outcome as usize;
+                        vec![
+                            Value::Str(format!("{:?}", outcome)),
+                            Value::Int(u128::from(bins[i])),
+                            Value::Int(bytes[i]),
+                            Value::Float(bins[i] as f64 / total_bins as f64, 3),
+                            Value::Float(bytes[i] as f64 / total_bytes.max(1) as f64, 3),
+                        ]
+                    })
+                    .collect()],
+            }],
+        })
+    }
+
+    /// Bins and bytes by the number of significantly better alternates,
+    /// or `None` if the summarizer does not test every alternate.
+    pub fn better_alternates_table(&self) -> Option<TableGroup> {
+        let mut bins: [u64; db::TimeBin::MAX_ROUTES] = [0; db::TimeBin::MAX_ROUTES];
+        let mut bytes: [u128; db::TimeBin::MAX_ROUTES] = [0; db::TimeBin::MAX_ROUTES];
+        for psum in self.pathid2summary.values() {
+            for binstats in psum.time2binstats.values() {
+                if let Some(counts) = &binstats.alternates {
+                    bins[counts.better as usize] += 1;
+                    bytes[counts.better as usize] += u128::from(binstats.bytes);
+                }
+            }
+        }
+        let total_bins: u64 = bins.iter().sum();
+        if total_bins == 0 {
+            return None;
+        }
+        let total_bytes: u128 = bytes.iter().sum();
+        Some(TableGroup {
+            text_name: "better-alternates",
+            pickle_name: None,
+            tables: vec![Table {
+                name: "better-alternates",
+                columns: vec!["better_alternates", "bins", "bytes", "frac_bins", "frac_bytes"],
+                key_columns: 1,
+                key_separator: " ",
+                sections: vec![(0..db::TimeBin::MAX_ROUTES)
+                    .map(|i| {
+                        vec![
+                            Value::Int(i as u128),
+                            Value::Int(u128::from(bins[i])),
+                            Value::Int(bytes[i]),
+                            Value::Float(bins[i] as f64 / total_bins as f64, 3),
+                            Value::Float(bytes[i] as f64 / total_bytes.max(1) as f64, 3),
+                        ]
+                    })
+                    .collect()],
+            }],
+        })
+    }
+
+    /// Bytes per `DegradationAttribution` by continent and by primary
+    /// peer type, or `None` if the summarizer does not attribute
+    /// degradation.
+    pub fn attribution_tables(&self) -> Option<TableGroup> {
+        type Counters = [u128; DEGRADATION_ATTRIBUTIONS.len()];
+        let mut continent2bytes: BTreeMap<db::ClientContinent, Counters> = BTreeMap::new();
+        let mut peertype2bytes: BTreeMap<db::PeerType, Counters> = BTreeMap::new();
+        for (pathid, psum) in self.pathid2summary.iter() {
+            for binstats in psum.time2binstats.values() {
+                if let Some(attribution) = binstats.attribution {
+                    let bytes: u128 = u128::from(binstats.bytes);
+                    continent2bytes.entry(pathid.client_continent).or_default()
+                        [attribution as usize] += bytes;
+                    peertype2bytes.entry(binstats.primary_peer_type).or_default()
+                        [attribution as usize] += bytes;
+                }
+            }
+        }
+        if continent2bytes.is_empty() {
+            return None;
+        }
+        let columns = |key: &'static str| {
+            std::iter::once(key)
+                .chain(DEGRADATION_ATTRIBUTIONS.iter().map(|a| a.column()))
+                .collect::<Vec<&'static str>>()
+        };
+        let row = |key: String, counters: &Counters| {
+            std::iter::once(Value::Str(key))
+                .chain(counters.iter().map(|bytes| Value::Int(*bytes)))
+                .collect::<Vec<Value>>()
+        };
+        Some(TableGroup {
+            text_name: "degradation-attribution",
+            pickle_name: None,
+            tables: vec![
+                Table {
+                    name: "degradation-attribution-continents",
+                    columns: columns("continent"),
+                    key_columns: 1,
+                    key_separator: " ",
+                    sections: vec![continent2bytes
+                        .iter()
+                        .map(|(continent, counters)| row(format!("{:?}", continent), counters))
+                        .collect()],
+                },
+                Table {
+                    name: "degradation-attribution-peer-types",
+                    columns: columns("primary_peer_type"),
+                    key_columns: 1,
+                    key_separator: " ",
+                    sections: vec![peertype2bytes
+                        .iter()
+                        .map(|(peer_type, counters)| row(format!("{:?}", peer_type), counters))
+                        .collect()],
+                },
+            ],
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct SavedSummaryRef<'a> {
+    summarizer: &'a str,
+    spec: Option<&'a str>,
+    total_bins: u32,
+    paths: Vec<(&'a db::PathId, &'a PathSummary)>,
+}
+
+#[derive(Deserialize)]
+struct SavedSummary {
+    summarizer: String,
+    #[serde(default)]
+    spec: Option<String>,
+    total_bins: u32,
+    paths: Vec<(db::PathId, PathSummary)>,
+}
+
+impl TransitionMatrix {
+    pub fn add(&mut self, from: TemporalBehavior, to: TemporalBehavior, bytes: u128) {
+        self.paths[from as usize][to as usize] += 1;
+        self.bytes[from as usize][to as usize] += bytes;
+    }
+
+    /// Paths and bytes that changed behavior.
+    pub fn changed(&self) -> (u64, u128) {
+        let mut paths: u64 = 0;
+        let mut bytes: u128 = 0;
+        for i in 0..(TemporalBehavior::SIZE as usize) {
+            for j in 0..(TemporalBehavior::SIZE as usize) {
+                if i != j {
+                    paths += self.paths[i][j];
+                    bytes += self.bytes[i][j];
+                }
             }
-            writeln!(bw)?;
         }
+        (paths, bytes)
+    }
 
-        let mut filepath = path.clone();
-        filepath.push("temporal-behavior.pickle");
+    pub fn total(&self) -> (u64, u128) {
+        (self.paths.iter().flatten().sum(), self.bytes.iter().flatten().sum())
+    }
+
+    /// Writes `{name}.txt` and `{name}.pickle` under `path`.
+    pub fn dump(&self, path: &Path, name: &str) -> Result<(), io::Error> {
+        let mut key2data: BTreeMap<String, (u64, String, f64, f64)> = BTreeMap::new();
+        let (total_paths, total_bytes) = self.total();
+
+        let mut filepath = path.to_path_buf();
+        filepath.push(format!("{}.txt", name));
         let file = fs::OpenOptions::new()
             .read(true)
             .write(true)
@@ -732,17 +1628,25 @@ impl DBSummary {
             .create(true)
             .open(filepath)?;
         let mut bw = io::BufWriter::new(file);
-        serde_pickle::to_writer(&mut bw, &key2data, true).unwrap_or_else(|e| {
-            error!("{}", e);
-            info!("could not dump temporal-behavior table as pickle");
-        });
-
-        Ok(())
-    }
+        for i in 0..(TemporalBehavior::SIZE as usize) {
+            let from: TemporalBehavior = TemporalBehavior::try_from(i as u8).unwrap();
+            for j in 0..(TemporalBehavior::SIZE as usize) {
+                let to: TemporalBehavior = TemporalBehavior::try_from(j as u8).unwrap();
+                let key: String = format!("{:?}+{:?}", from, to);
+                let data = (
+                    self.paths[i][j],
+                    self.bytes[i][j].to_string(),
+                    self.paths[i][j] as f64 / total_paths as f64,
+                    self.bytes[i][j] as f64 / total_bytes as f64,
+                );
+                writeln!(bw, "{} {} {} {:0.3} {:0.3}", key, data.0, data.1, data.2, data.3)?;
+                key2data.insert(key, data);
+            }
+            writeln!(bw)?;
+        }
 
-    fn dump_path_summaries(&self, path: &PathBuf) -> Result<(), io::Error> {
-        let mut filepath = path.clone();
-        filepath.push("path-summaries.txt");
+        let mut filepath = path.to_path_buf();
+        filepath.push(format!("{}.pickle", name));
         let file = fs::OpenOptions::new()
             .read(true)
             .write(true)
@@ -750,16 +1654,23 @@ impl DBSummary {
             .create(true)
             .open(filepath)?;
         let mut bw = io::BufWriter::new(file);
-        for (pid, psum) in &self.pathid2summary {
-            writeln!(bw, "{}", psum.text(&pid))?;
-        }
+        serde_pickle::to_writer(&mut bw, &key2data, true).unwrap_or_else(|e| {
+            error!("{}", e);
+            info!("could not dump {} as pickle", name);
+        });
+
         Ok(())
     }
 }
 
+type CdfDataFn = dyn Fn(f32, f32) -> f32;
+type CdfDiffCiFn = dyn Fn(&RouteDiffs) -> (f32, f32);
+/// Selects the days, since the epoch, a diurnal class considers.
+type DayFilter = fn(u32) -> bool;
+
 struct CdfDataConfig<'a, F, G>
 where
-    F: Fn(&db::RouteInfo, &db::RouteInfo) -> (f32, f32),
+    F: Fn(&RouteDiffs) -> (f32, f32),
     G: Fn(f32, f32) -> f32,
 {
     only_shifted_bins: bool,
@@ -768,23 +1679,15 @@ where
     compute_data: &'a G,
 }
 
-fn compute_cdf_data<F, G>(
-    pathid: &db::PathId,
-    time: u64,
-    bs: &TimeBinStats,
-    db: &db::DB,
-    sum: &dyn TimeBinSummarizer,
-    config: CdfDataConfig<F, G>,
-) -> Option<(f32, f64)>
+fn compute_cdf_data<F, G>(bs: &TimeBinStats, config: CdfDataConfig<F, G>) -> Option<(f32, f64)>
 where
-    F: Fn(&db::RouteInfo, &db::RouteInfo) -> (f32, f32),
+    F: Fn(&RouteDiffs) -> (f32, f32),
     G: Fn(f32, f32) -> f32,
 {
     if config.only_shifted_bins && !bs.is_shifted {
         None
     } else {
-        let (primary, alternate) = sum.get_routes(pathid, time, db);
-        let (diff, interval) = (config.compute_diff_ci)(primary, alternate);
+        let (diff, interval) = (config.compute_diff_ci)(&bs.route_diffs);
         let data: f32 = (config.compute_data)(diff, interval);
         let weight: f64 = if config.weighted {
             bs.bytes as f64
@@ -803,13 +1706,27 @@ impl PathSummary {
         summarizer: &dyn TimeBinSummarizer,
         tempconfig: &TemporalConfig,
     ) -> PathSummary {
-        let mut psum = PathSummary::default();
-        let mut is_shifted = false;
-        for (time, timebin) in time2bin {
-            // This requires that time2bin is a BTreeMap as
-            // computing the number of distinct shift events
-            // requires processing TimeBins in time order.
-            let bytes: u128 = u128::from(timebin.bytes_acked_sum);
+        PathSummary::build_from_bins(pathid, time2bin.iter(), total_bins, summarizer, tempconfig)
+    }
+
+    /// Builds a `PathSummary` over a subset of a path's `TimeBin`s,
+    /// e.g., `time2bin.range(..)` for a time window.  `total_bins` is
+    /// the number of bins in the period covered by `bins`.
+    pub(crate) fn build_from_bins<'a, I>(
+        pathid: &db::PathId,
+        bins: I,
+        total_bins: u32,
+        summarizer: &dyn TimeBinSummarizer,
+        tempconfig: &TemporalConfig,
+    ) -> PathSummary
+    where
+        I: Iterator<Item = (&'a u64, &'a db::TimeBin)>,
+    {
+        let mut psum = PathSummary::default();
+        for (time, timebin) in bins {
+            let bytes: u128 = u128::from(timebin.bytes_acked_sum);
+            psum.existing_bins += 1;
+            psum.existing_bytes += bytes;
             match summarizer.summarize(pathid, timebin) {
                 TimeBinSummary::NoRoute => {
                     psum.noroute_bins += 1;
@@ -819,107 +1736,441 @@ impl PathSummary {
                     psum.wideci_bins += 1;
                     psum.wideci_bytes += bytes;
                 }
-                TimeBinSummary::Valid(binstats) => {
+                TimeBinSummary::Valid(mut binstats) => {
                     psum.valid_bytes += bytes;
-                    let e = psum.day2shifts.entry((time / 86400) as u32);
-                    if binstats.is_shifted {
-                        psum.shifted_bins += 1;
-                        psum.shifted_bytes += bytes;
-                        if !is_shifted {
-                            psum.distinct_shifts += 1;
-                        }
-                        e.and_modify(|e| *e += 1).or_insert(1);
-                    } else {
-                        e.or_insert(0);
-                    }
-                    is_shifted = binstats.is_shifted;
+                    binstats.is_shifted_raw = binstats.is_shifted;
                     psum.time2binstats.insert(*time, binstats);
                 }
             }
         }
-        psum.classify(total_bins, time2bin.len() as u32, tempconfig);
+        psum.classify(total_bins, tempconfig);
         psum
     }
 
-    fn classify(&mut self, total_bins: u32, existing_bins: u32, config: &TemporalConfig) {
-        self.compute_bad_bins(config);
-        let frac_existing = existing_bins as f32 / total_bins as f32;
-        if frac_existing < config.min_frac_existing_bins {
-            self.temporal_behavior = TemporalBehavior::MissingBins;
-            return;
-        }
-        let frac_with_alt = 1.0 - f32::from(self.noroute_bins) / (existing_bins as f32);
-        if frac_with_alt < config.min_frac_bins_with_alternate {
-            self.temporal_behavior = TemporalBehavior::NoRoute;
-            return;
+    /// Applies the hysteresis in `config` to the raw per-bin shift
+    /// decisions and recomputes all shift counters.  This requires
+    /// that time2binstats is a BTreeMap as computing the number of
+    /// distinct shift events requires processing bins in time order.
+    fn compute_shifts(&mut self, config: &TemporalConfig) {
+        let raw: Vec<bool> = self.time2binstats.values().map(|bs| bs.is_shifted_raw).collect();
+        let smoothed: Vec<bool> =
+            smooth_shifts(&raw, config.shift_enter_min_bins, config.shift_exit_min_bins);
+        self.day2shifts.clear();
+        self.distinct_shifts = 0;
+        self.raw_distinct_shifts = 0;
+        self.shifted_bins = 0;
+        self.raw_shifted_bins = 0;
+        self.shifted_bytes = 0;
+        let mut is_shifted = false;
+        let mut is_shifted_raw = false;
+        for ((time, bs), &shifted) in self.time2binstats.iter_mut().zip(smoothed.iter()) {
+            bs.is_shifted = shifted;
+            let e = self.day2shifts.entry((time / 86400) as u32);
+            if bs.is_shifted {
+                self.shifted_bins += 1;
+                self.shifted_bytes += u128::from(bs.bytes);
+                if !is_shifted {
+                    self.distinct_shifts += 1;
+                }
+                e.and_modify(|e| *e += 1).or_insert(1);
+            } else {
+                e.or_insert(0);
+            }
+            if bs.is_shifted_raw {
+                self.raw_shifted_bins += 1;
+                if !is_shifted_raw {
+                    self.raw_distinct_shifts += 1;
+                }
+            }
+            is_shifted = bs.is_shifted;
+            is_shifted_raw = bs.is_shifted_raw;
         }
+    }
+
+    fn classify(&mut self, total_bins: u32, config: &TemporalConfig) {
+        self.compute_shifts(config);
+        self.compute_bad_bins(config);
+        self.diurnal_pvalue = None;
+        self.class_steps.clear();
+        self.temporal_behavior = self.decide_class(total_bins, config);
+    }
+
+    /// Checks the classes in order and returns the first that holds,
+    /// recording each check in `class_steps`.
+    fn decide_class(&mut self, total_bins: u32, config: &TemporalConfig) -> TemporalBehavior {
+        let existing_bins: f32 = self.existing_bins as f32;
         let valid_bins: f32 = self.time2binstats.len() as f32;
-        let frac_valid: f32 = valid_bins / existing_bins as f32;
-        if frac_valid < config.min_frac_valid_bins {
-            self.temporal_behavior = TemporalBehavior::Undersampled;
-            return;
-        }
         let frac_shift: f32 = f32::from(self.shifted_bins) / valid_bins;
-        if frac_shift <= config.uneventful_max_frac_shifted_bins {
-            self.temporal_behavior = TemporalBehavior::Uneventful;
-        } else if frac_shift >= config.continuous_min_frac_shifted_bins {
-            self.temporal_behavior = TemporalBehavior::Continuous;
-        } else if self.bad_bins >= config.diurnal_min_bad_bins {
-            self.temporal_behavior = TemporalBehavior::Diurnal;
-        } else {
-            self.temporal_behavior = TemporalBehavior::Episodic;
+        let thresholds: Vec<(TemporalBehavior, ClassCheck)> = vec![
+            (
+                TemporalBehavior::MissingBins,
+                ClassCheck::new(
+                    "frac_existing_bins",
+                    existing_bins / total_bins as f32,
+                    ClassCmp::Less,
+                    Some("min_frac_existing_bins"),
+                    config.min_frac_existing_bins,
+                ),
+            ),
+            (
+                TemporalBehavior::NoRoute,
+                ClassCheck::new(
+                    "frac_bins_with_alternate",
+                    1.0 - f32::from(self.noroute_bins) / existing_bins,
+                    ClassCmp::Less,
+                    Some("min_frac_bins_with_alternate"),
+                    config.min_frac_bins_with_alternate,
+                ),
+            ),
+            (
+                TemporalBehavior::Undersampled,
+                ClassCheck::new(
+                    "frac_valid_bins",
+                    valid_bins / existing_bins,
+                    ClassCmp::Less,
+                    Some("min_frac_valid_bins"),
+                    config.min_frac_valid_bins,
+                ),
+            ),
+            (
+                TemporalBehavior::Uneventful,
+                ClassCheck::new(
+                    "frac_shifted_bins",
+                    frac_shift,
+                    ClassCmp::LessEq,
+                    Some("uneventful_max_frac_shifted_bins"),
+                    config.uneventful_max_frac_shifted_bins,
+                ),
+            ),
+            (
+                TemporalBehavior::Continuous,
+                ClassCheck::new(
+                    "frac_shifted_bins",
+                    frac_shift,
+                    ClassCmp::GreaterEq,
+                    Some("continuous_min_frac_shifted_bins"),
+                    config.continuous_min_frac_shifted_bins,
+                ),
+            ),
+        ];
+        for (class, check) in thresholds {
+            if self.record_step(class, vec![check]) {
+                return class;
+            }
         }
+
+        let diurnal: [(TemporalBehavior, &str, u16, DayFilter); 3] = [
+            (TemporalBehavior::Diurnal, "bad_bins", self.bad_bins, |_day| true),
+            (TemporalBehavior::WeekdayDiurnal, "weekday_bad_bins", self.weekday_bad_bins, |day| {
+                !is_weekend(day)
+            }),
+            (
+                TemporalBehavior::WeekendDiurnal,
+                "weekend_bad_bins",
+                self.weekend_bad_bins,
+                is_weekend,
+            ),
+        ];
+        for &(class, name, bad_bins, use_day) in diurnal.iter() {
+            let mut checks: Vec<ClassCheck> = vec![ClassCheck::new(
+                name,
+                bad_bins,
+                ClassCmp::GreaterEq,
+                Some("diurnal_min_bad_bins"),
+                config.diurnal_min_bad_bins,
+            )];
+            if checks[0].holds() {
+                if let Some(pvalue) = self.diurnal_pvalue_of(config, &use_day, bad_bins) {
+                    self.diurnal_pvalue = Some(pvalue);
+                    checks.push(ClassCheck::new(
+                        "pvalue",
+                        pvalue,
+                        ClassCmp::LessEq,
+                        Some("diurnal_max_pvalue"),
+                        config.diurnal_max_pvalue,
+                    ));
+                }
+            }
+            if self.record_step(class, checks) {
+                return class;
+            }
+        }
+
+        let weekly: Vec<ClassCheck> = vec![
+            ClassCheck::new(
+                "weekly_min_weeks",
+                config.weekly_min_weeks,
+                ClassCmp::Greater,
+                None,
+                0,
+            ),
+            ClassCheck::new(
+                "days",
+                self.day2shifts.len() as u32,
+                ClassCmp::GreaterEq,
+                Some("7 * weekly_min_weeks"),
+                7 * config.weekly_min_weeks,
+            ),
+            ClassCheck::new(
+                "weekly_autocorr",
+                self.weekly_autocorr,
+                ClassCmp::GreaterEq,
+                Some("weekly_min_autocorr"),
+                config.weekly_min_autocorr,
+            ),
+        ];
+        if self.record_step(TemporalBehavior::Weekly, weekly) {
+            return TemporalBehavior::Weekly;
+        }
+        TemporalBehavior::Episodic
+    }
+
+    fn record_step(&mut self, class: TemporalBehavior, checks: Vec<ClassCheck>) -> bool {
+        let step = ClassStep {
+            class,
+            checks,
+        };
+        let holds: bool = step.holds();
+        self.class_steps.push(step);
+        holds
+    }
+
+    /// The counters `classify` works from, the steps it recorded and
+    /// the resulting class.
+    pub(crate) fn explain_class(&self) -> Vec<String> {
+        let mut lines: Vec<String> = vec![format!(
+            "shifted_bins {} raw_shifted_bins {} distinct_shifts {} bad_bins {} \
+             weekday_bad_bins {} weekend_bad_bins {} weekly_autocorr {:0.3}",
+            self.shifted_bins,
+            self.raw_shifted_bins,
+            self.distinct_shifts,
+            self.bad_bins,
+            self.weekday_bad_bins,
+            self.weekend_bad_bins,
+            self.weekly_autocorr
+        )];
+        lines.extend(self.class_steps.iter().map(ClassStep::to_string));
+        lines.push(format!("class {:?}", self.temporal_behavior));
+        lines
     }
 
     fn compute_bad_bins(&mut self, config: &TemporalConfig) {
         let num_days: u32 = self.day2shifts.len() as u32;
         if num_days < config.min_days {
             self.bad_bins = 0;
+            self.weekday_bad_bins = 0;
+            self.weekend_bad_bins = 0;
+            self.weekly_autocorr = 0.0;
             return;
         }
+        let (bad_bins, bad_bytes) = self.count_bad_bins(config, |_day| true);
+        self.bad_bins = bad_bins as u16;
+        self.bad_bytes = bad_bytes;
+        if !self.has_enough_weeks(config) {
+            self.weekday_bad_bins = 0;
+            self.weekend_bad_bins = 0;
+            self.weekly_autocorr = 0.0;
+            return;
+        }
+        let (weekday_bad_bins, _) = self.count_bad_bins(config, |day| !is_weekend(day));
+        let (weekend_bad_bins, _) = self.count_bad_bins(config, is_weekend);
+        self.weekday_bad_bins = weekday_bad_bins as u16;
+        self.weekend_bad_bins = weekend_bad_bins as u16;
+        self.weekly_autocorr = self.compute_weekly_autocorr(config);
+    }
+
+    /// Counts bad bins considering only days for which `use_day`
+    /// returns true.
+    fn count_bad_bins<F>(&self, config: &TemporalConfig, use_day: F) -> (u32, u128)
+    where
+        F: Fn(u32) -> bool,
+    {
+        let num_days: u32 = self.day2shifts.keys().filter(|&&d| use_day(d)).count() as u32;
+        if num_days == 0 {
+            return (0, 0);
+        }
         let min_shifts: u32 = (config.diurnal_bad_bin_min_prob_shift * num_days as f32) as u32;
         let bins_per_day: usize = (60 * 60 * 24 / config.bin_duration_secs) as usize;
         let mut offset_shift_counts = vec![0u32; bins_per_day];
         let mut offset_bytes = vec![0u64; bins_per_day];
         self.time2binstats.iter().for_each(|(t, bs)| {
-            if bs.is_shifted {
+            if bs.is_shifted && use_day((t / 86400) as u32) {
                 let offset: usize = compute_offset(*t, config.bin_duration_secs);
                 offset_shift_counts[offset] += 1;
                 offset_bytes[offset] += bs.bytes;
             }
         });
-        let (bad_bins, bad_bytes) =
-            compute_bad_bins_traffic(&offset_shift_counts, &offset_bytes, min_shifts);
-        self.bad_bins = bad_bins as u16;
-        self.bad_bytes = bad_bytes;
+        compute_bad_bins_traffic(&offset_shift_counts, &offset_bytes, min_shifts)
     }
 
-    fn text(&self, pid: &db::PathId) -> String {
-        format!(
-            "{} {} {} {} {} {} {} {} {} {} {} {} {:?}",
-            pid.text(),
-            self.distinct_shifts,
-            self.bad_bytes,
-            self.noroute_bytes,
-            self.shifted_bytes,
-            self.valid_bytes,
-            self.wideci_bytes,
-            self.bad_bins,
-            self.noroute_bins,
-            self.shifted_bins,
-            self.time2binstats.len(),
-            self.wideci_bins,
-            self.temporal_behavior
-        )
+    /// Estimates the probability of getting at least `bad_bins` bad
+    /// bins by chance over the days for which `use_day` returns true by
+    /// shuffling the path's shift decisions across its valid bins in
+    /// those days `config.diurnal_permutations` times.  Returns `None`
+    /// if the test is disabled.
+    fn diurnal_pvalue_of(
+        &self,
+        config: &TemporalConfig,
+        use_day: &dyn Fn(u32) -> bool,
+        bad_bins: u16,
+    ) -> Option<f32> {
+        if config.diurnal_permutations == 0 {
+            return None;
+        }
+        let num_days: u32 = self.day2shifts.keys().filter(|&&d| use_day(d)).count() as u32;
+        let min_shifts: u32 = (config.diurnal_bad_bin_min_prob_shift * num_days as f32) as u32;
+        let bins_per_day: usize = (60 * 60 * 24 / config.bin_duration_secs) as usize;
+        let (offsets, mut shifts): (Vec<usize>, Vec<bool>) = self
+            .time2binstats
+            .iter()
+            .filter(|(t, _bs)| use_day((*t / 86400) as u32))
+            .map(|(t, bs)| (compute_offset(*t, config.bin_duration_secs), bs.is_shifted))
+            .unzip();
+        let mut rng = XorShift64::new(PERMUTATION_SEED);
+        let mut offset_shift_counts = vec![0u32; bins_per_day];
+        let mut extreme: u32 = 0;
+        for _ in 0..config.diurnal_permutations {
+            rng.shuffle(&mut shifts);
+            offset_shift_counts.iter_mut().for_each(|c| *c = 0);
+            for (&offset, &shifted) in offsets.iter().zip(shifts.iter()) {
+                if shifted {
+                    offset_shift_counts[offset] += 1;
+                }
+            }
+            let count = offset_shift_counts.iter().filter(|&&c| c >= min_shifts).count();
+            if count >= usize::from(bad_bins) {
+                extreme += 1;
+            }
+        }
+        Some((extreme + 1) as f32 / (config.diurnal_permutations + 1) as f32)
+    }
+
+    fn has_enough_weeks(&self, config: &TemporalConfig) -> bool {
+        config.weekly_min_weeks > 0 && self.day2shifts.len() as u32 >= 7 * config.weekly_min_weeks
+    }
+
+    /// Autocorrelation of the shift series at a lag of one week.  Bins
+    /// without `TimeBinStats` are considered not shifted.
+    fn compute_weekly_autocorr(&self, config: &TemporalConfig) -> f32 {
+        let first: u64 = match self.time2binstats.keys().next() {
+            Some(&t) => t,
+            None => return 0.0,
+        };
+        let last: u64 = *self.time2binstats.keys().next_back().unwrap();
+        let bin_duration_secs = u64::from(config.bin_duration_secs);
+        let nbins: usize = ((last - first) / bin_duration_secs) as usize + 1;
+        let mut series = vec![0.0f64; nbins];
+        for (t, bs) in self.time2binstats.iter() {
+            if bs.is_shifted {
+                series[((t - first) / bin_duration_secs) as usize] = 1.0;
+            }
+        }
+        let lag: usize = (7 * 86400 / bin_duration_secs) as usize;
+        autocorrelation(&series, lag) as f32
+    }
+    const COLUMNS: [&'static str; 17] = [
+        "vip_metro",
+        "bgp_prefix",
+        "continent",
+        "country",
+        "distinct_shifts",
+        "bad_bytes",
+        "noroute_bytes",
+        "shifted_bytes",
+        "valid_bytes",
+        "wideci_bytes",
+        "bad_bins",
+        "noroute_bins",
+        "shifted_bins",
+        "valid_bins",
+        "wideci_bins",
+        "temporal_behavior",
+        "diurnal_pvalue",
+    ];
+    fn row(&self, pid: &db::PathId) -> Vec<Value> {
+        vec![
+            Value::Str(pid.vip_metro.clone()),
+            Value::Str(pid.bgp_ip_prefix.to_string()),
+            Value::Str(format!("{:?}", pid.client_continent)),
+            Value::Str(pid.client_country.iter().collect()),
+            Value::Int(u128::from(self.distinct_shifts)),
+            Value::Int(self.bad_bytes),
+            Value::Int(self.noroute_bytes),
+            Value::Int(self.shifted_bytes),
+            Value::Int(self.valid_bytes),
+            Value::Int(self.wideci_bytes),
+            Value::Int(u128::from(self.bad_bins)),
+            Value::Int(u128::from(self.noroute_bins)),
+            Value::Int(u128::from(self.shifted_bins)),
+            Value::Int(self.time2binstats.len() as u128),
+            Value::Int(u128::from(self.wideci_bins)),
+            Value::Str(format!("{:?}", self.temporal_behavior)),
+            self.diurnal_pvalue.map_or(Value::Null, |p| Value::Float(f64::from(p), 4)),
+        ]
     }
 }
 
-impl Default for TemporalBehavior {
-    fn default() -> Self {
-        TemporalBehavior::Uninitialized
+const PERMUTATION_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Small deterministic PRNG (xorshift64*) so permutation tests are
+/// reproducible across runs.
+struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    fn new(seed: u64) -> XorShift64 {
+        XorShift64 {
+            state: seed.max(1),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Fisher-Yates shuffle.
+    fn shuffle<T>(&mut self, data: &mut [T]) {
+        for i in (1..data.len()).rev() {
+            let j: usize = (self.next_u64() % (i as u64 + 1)) as usize;
+            data.swap(i, j);
+        }
     }
 }
 
+/// Smooths per-bin shift decisions with hysteresis: entering the
+/// shifted state requires `enter_min_bins` consecutive shifted bins and
+/// leaving it requires `exit_min_bins` consecutive non-shifted bins.
+/// Runs too short to switch state take the current state.  A value of
+/// 0 or 1 disables the corresponding rule.
+fn smooth_shifts(raw: &[bool], enter_min_bins: u16, exit_min_bins: u16) -> Vec<bool> {
+    let mut smoothed = vec![false; raw.len()];
+    let mut state = false;
+    let mut pending: usize = 0;
+    for (i, &shifted) in raw.iter().enumerate() {
+        if shifted != state {
+            let min_bins = if state {
+                exit_min_bins
+            } else {
+                enter_min_bins
+            };
+            if i + 1 - pending < usize::from(min_bins.max(1)) {
+                continue;
+            }
+            state = shifted;
+        }
+        smoothed[pending..=i].iter_mut().for_each(|s| *s = state);
+        pending = i + 1;
+    }
+    // Bins in a trailing run too short to switch state keep the
+    // current state.
+    smoothed[pending..].iter_mut().for_each(|s| *s = state);
+    smoothed
+}
+
 fn compute_bad_bins_traffic(
     offset_shift_counts: &[u32],
     offset_traffic: &[u64],
@@ -937,15 +2188,53 @@ fn compute_bad_bins_traffic(
     (bad_bins, bad_traffic)
 }
 
+/// Pearson correlation between `series` and itself shifted by `lag`.
+/// Returns 0 if either side is constant or shorter than two elements.
+fn autocorrelation(series: &[f64], lag: usize) -> f64 {
+    if series.len() < lag + 2 {
+        return 0.0;
+    }
+    let n: usize = series.len() - lag;
+    let xs = &series[..n];
+    let ys = &series[lag..];
+    let xmean: f64 = xs.iter().sum::<f64>() / n as f64;
+    let ymean: f64 = ys.iter().sum::<f64>() / n as f64;
+    let mut cov: f64 = 0.0;
+    let mut xvar: f64 = 0.0;
+    let mut yvar: f64 = 0.0;
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        cov += (x - xmean) * (y - ymean);
+        xvar += (x - xmean) * (x - xmean);
+        yvar += (y - ymean) * (y - ymean);
+    }
+    if xvar == 0.0 || yvar == 0.0 {
+        return 0.0;
+    }
+    cov / (xvar * yvar).sqrt()
+}
+
+/// Whether `day` (days since the Unix epoch, a Thursday) is a Saturday
+/// or Sunday.
+fn is_weekend(day: u32) -> bool {
+    // Day 0 is a Thursday, so days 2 and 3 (mod 7) are the weekend.
+    let weekday: u32 = (day + 3) % 7; // 0 is Monday
+    weekday >= 5
+}
+
 fn compute_offset(time: u64, bin_duration_secs: u32) -> usize {
     ((time % 86400) / u64::from(bin_duration_secs)) as usize
 }
 
 #[cfg(test)]
 mod tests {
+    use std::path::PathBuf;
+
     use super::*;
     use crate::performance::db;
-    use crate::performance::summarizers::opportunity::MinRtt50ImprovementSummarizer;
+    use crate::performance::summarizers::degradation::MinRtt50LowerBoundDegradationSummarizer;
+    use crate::performance::summarizers::opportunity::{
+        HdRatioBootstrapDifferenceImprovementSummarizer, MinRtt50ImprovementSummarizer,
+    };
 
     const BIN_DURATION_SECS: u64 = 900;
     const BINS_IN_WEEK: u32 = 7 * 86400 / BIN_DURATION_SECS as u32;
@@ -958,7 +2247,13 @@ mod tests {
         continuous_min_frac_shifted_bins: 1.0,
         diurnal_min_bad_bins: 96,
         diurnal_bad_bin_min_prob_shift: 1.0,
+        diurnal_permutations: 0,
+        diurnal_max_pvalue: 1.0,
         uneventful_max_frac_shifted_bins: 0.20,
+        weekly_min_weeks: 0,
+        weekly_min_autocorr: 1.0,
+        shift_enter_min_bins: 1,
+        shift_exit_min_bins: 1,
     };
     const DEFAULT_TEMPCONFIG: TemporalConfig = TemporalConfig {
         bin_duration_secs: 900,
@@ -969,7 +2264,13 @@ mod tests {
         continuous_min_frac_shifted_bins: 0.8,
         diurnal_min_bad_bins: 24,
         diurnal_bad_bin_min_prob_shift: 0.8,
+        diurnal_permutations: 0,
+        diurnal_max_pvalue: 1.0,
         uneventful_max_frac_shifted_bins: 0.20,
+        weekly_min_weeks: 0,
+        weekly_min_autocorr: 1.0,
+        shift_enter_min_bins: 1,
+        shift_exit_min_bins: 1,
     };
 
     #[test]
@@ -1226,7 +2527,7 @@ mod tests {
 
         let mut config = DEFAULT_TEMPCONFIG;
         config.min_frac_valid_bins = 0.4;
-        psum.classify(BINS_IN_WEEK, time2bin.len() as u32, &config);
+        psum.classify(BINS_IN_WEEK, &config);
         assert!(psum.temporal_behavior == TemporalBehavior::Continuous);
     }
 
@@ -1247,15 +2548,58 @@ mod tests {
 
         let mut config = DEFAULT_TEMPCONFIG;
         config.diurnal_min_bad_bins = 56; // 0.6 * 96
-        psum.classify(BINS_IN_WEEK, time2bin.len() as u32, &config);
+        psum.classify(BINS_IN_WEEK, &config);
         assert!(psum.temporal_behavior == TemporalBehavior::Episodic);
 
         let mut config = DEFAULT_TEMPCONFIG;
         config.continuous_min_frac_shifted_bins = 0.4;
-        psum.classify(BINS_IN_WEEK, time2bin.len() as u32, &config);
+        psum.classify(BINS_IN_WEEK, &config);
         assert!(psum.temporal_behavior == TemporalBehavior::Continuous);
     }
 
+    #[test]
+    fn test_explain_class() {
+        let pathid: db::PathId = db::tests::make_path_id();
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let time2bin = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 51, 50, 1, 55, 50, 1);
+        let mut psum =
+            PathSummary::build(&pathid, &time2bin, BINS_IN_WEEK, &summarizer, &DEFAULT_TEMPCONFIG);
+        for config in [DEFAULT_TEMPCONFIG, {
+            let mut config = DEFAULT_TEMPCONFIG;
+            config.diurnal_min_bad_bins = 56;
+            config
+        }]
+        .iter()
+        {
+            psum.classify(BINS_IN_WEEK, config);
+            let lines: Vec<String> = psum.explain_class();
+            let class: String = format!("{:?}", psum.temporal_behavior);
+            assert!(*lines.last().unwrap() == format!("class {}", class));
+            let decisions: Vec<&String> = lines.iter().filter(|l| l.contains(": -> ")).collect();
+            assert!(decisions.len() <= 1);
+            if let Some(line) = decisions.first() {
+                assert!(line.ends_with(&format!("-> {}", class)));
+            }
+        }
+
+        // The explanation shows the p-value of the test classify ran.
+        let mut config = DEFAULT_TEMPCONFIG;
+        config.diurnal_permutations = 199;
+        config.diurnal_max_pvalue = 0.05;
+        psum.classify(BINS_IN_WEEK, &config);
+        let step: &ClassStep = psum.class_steps.last().unwrap();
+        assert!(step.class == TemporalBehavior::Diurnal && step.holds());
+        assert!(step.checks[1].value == f64::from(psum.diurnal_pvalue.unwrap()));
+        let lines: Vec<String> = psum.explain_class();
+        assert!(lines[lines.len() - 2]
+            .ends_with("and pvalue 0.0050 <= diurnal_max_pvalue 0.0500: -> Diurnal"));
+    }
+
     #[test]
     fn test_diurnal_min_prob_shift() {
         let _pathid: db::PathId = db::tests::make_path_id();
@@ -1284,8 +2628,217 @@ mod tests {
 
         let mut config = DEFAULT_TEMPCONFIG;
         config.diurnal_bad_bin_min_prob_shift = 0.5;
-        psum.classify(2 * BINS_IN_WEEK, time2bin.len() as u32, &config);
+        psum.classify(2 * BINS_IN_WEEK, &config);
+        assert!(psum.temporal_behavior == TemporalBehavior::Diurnal);
+    }
+
+    #[test]
+    fn test_smooth_shifts() {
+        let raw = [false, true, false, true, true, true, false, true, false, false, true];
+        assert!(smooth_shifts(&raw, 1, 1) == raw.to_vec());
+        assert!(smooth_shifts(&raw, 0, 0) == raw.to_vec());
+        assert!(
+            smooth_shifts(&raw, 3, 1)
+                == vec![false, false, false, true, true, true, false, false, false, false, false]
+        );
+        assert!(
+            smooth_shifts(&raw, 1, 2)
+                == vec![false, true, true, true, true, true, true, true, false, false, true]
+        );
+        assert!(
+            smooth_shifts(&raw, 3, 3)
+                == vec![false, false, false, true, true, true, true, true, true, true, true]
+        );
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let _pathid: db::PathId = db::tests::make_path_id();
+
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        // Odd bins are shifted, even bins are not.
+        let time2bin = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 51, 50, 1, 55, 50, 1);
+        let nbins = time2bin.len();
+        let mut psum =
+            PathSummary::build(&_pathid, &time2bin, BINS_IN_WEEK, &summarizer, &DEFAULT_TEMPCONFIG);
+        assert!(psum.distinct_shifts == (nbins / 2) as u16);
+        assert!(psum.raw_distinct_shifts == psum.distinct_shifts);
+        assert!(psum.shifted_bins == (nbins / 2) as u16);
+        assert!(psum.raw_shifted_bins == psum.shifted_bins);
+        assert!(psum.time2binstats.values().all(|bs| bs.is_shifted == bs.is_shifted_raw));
+        assert!(psum.temporal_behavior == TemporalBehavior::Diurnal);
+
+        let mut config = DEFAULT_TEMPCONFIG;
+        config.shift_exit_min_bins = 2;
+        psum.classify(BINS_IN_WEEK, &config);
+        assert!(psum.distinct_shifts == 1);
+        assert!(psum.raw_distinct_shifts == (nbins / 2) as u16);
+        assert!(psum.shifted_bins == (nbins - 1) as u16);
+        assert!(psum.raw_shifted_bins == (nbins / 2) as u16);
+        assert!(psum.shifted_bytes == psum.valid_bytes - u128::from(db::TimeBin::MOCK_TOTAL_BYTES));
+        assert!(psum.temporal_behavior == TemporalBehavior::Continuous);
+
+        let mut config = DEFAULT_TEMPCONFIG;
+        config.shift_enter_min_bins = 2;
+        psum.classify(BINS_IN_WEEK, &config);
+        assert!(psum.distinct_shifts == 0);
+        assert!(psum.shifted_bins == 0);
+        assert!(psum.shifted_bytes == 0);
+        assert!(psum.raw_shifted_bins == (nbins / 2) as u16);
+        assert!(psum.temporal_behavior == TemporalBehavior::Uneventful);
+
+        psum.classify(BINS_IN_WEEK, &DEFAULT_TEMPCONFIG);
+        assert!(psum.distinct_shifts == (nbins / 2) as u16);
+        assert!(psum.temporal_behavior == TemporalBehavior::Diurnal);
+    }
+
+    fn mock_weeks_minrtt_p50<F>(weeks: u64, shifted: F) -> BTreeMap<u64, db::TimeBin>
+    where
+        F: Fn(u64) -> bool,
+    {
+        let mut time2bin: BTreeMap<u64, db::TimeBin> = BTreeMap::new();
+        for time in (0..weeks * 7 * 86400).step_by(BIN_DURATION_SECS as usize) {
+            let pri_minrtt_p50 = if shifted(time) {
+                55
+            } else {
+                51
+            };
+            time2bin.insert(time, db::TimeBin::mock_minrtt_p50(time, pri_minrtt_p50, 50, 1));
+        }
+        time2bin
+    }
+
+    #[test]
+    fn test_weekly_classes() {
+        let _pathid: db::PathId = db::tests::make_path_id();
+
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let mut config = DEFAULT_TEMPCONFIG;
+        config.weekly_min_weeks = 2;
+        config.weekly_min_autocorr = 0.5;
+        let weekend = |t: u64| is_weekend((t / 86400) as u32);
+        let business_hours = |t: u64| (9 * 3600..17 * 3600).contains(&(t % 86400));
+
+        let time2bin = mock_weeks_minrtt_p50(2, |t| !weekend(t) && business_hours(t));
+        let nbins = time2bin.len() as u32;
+        let mut psum = PathSummary::build(&_pathid, &time2bin, nbins, &summarizer, &config);
+        assert!(psum.bad_bins == 0);
+        assert!(psum.weekday_bad_bins == 32);
+        assert!(psum.temporal_behavior == TemporalBehavior::WeekdayDiurnal);
+        psum.classify(nbins, &DEFAULT_TEMPCONFIG);
+        assert!(psum.temporal_behavior == TemporalBehavior::Episodic);
+        let mut config3 = config;
+        config3.weekly_min_weeks = 3;
+        psum.classify(nbins, &config3);
+        assert!(psum.temporal_behavior == TemporalBehavior::Episodic);
+
+        let time2bin = mock_weeks_minrtt_p50(2, weekend);
+        let psum = PathSummary::build(&_pathid, &time2bin, nbins, &summarizer, &config);
+        assert!(psum.weekday_bad_bins == 0);
+        assert!(psum.weekend_bad_bins == 96);
+        assert!(psum.temporal_behavior == TemporalBehavior::WeekendDiurnal);
+
+        // Shifted all day on Mondays and Tuesdays.
+        let time2bin = mock_weeks_minrtt_p50(2, |t| (t / 86400 + 3) % 7 < 2);
+        let psum = PathSummary::build(&_pathid, &time2bin, nbins, &summarizer, &config);
+        assert!(psum.weekday_bad_bins == 0);
+        assert!(psum.weekend_bad_bins == 0);
+        assert!((psum.weekly_autocorr - 1.0).abs() < 1e-6);
+        assert!(psum.temporal_behavior == TemporalBehavior::Weekly);
+
+        // Shifted during the first four days only.
+        let time2bin = mock_weeks_minrtt_p50(2, |t| t < 4 * 86400);
+        let psum = PathSummary::build(&_pathid, &time2bin, nbins, &summarizer, &config);
+        assert!(psum.weekly_autocorr < 0.5);
+        assert!(psum.temporal_behavior == TemporalBehavior::Episodic);
+    }
+
+    #[test]
+    fn test_autocorrelation() {
+        let series: Vec<f64> = (0..20)
+            .map(|i| {
+                if i % 4 < 2 {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        assert!((autocorrelation(&series, 4) - 1.0).abs() < 1e-9);
+        assert!((autocorrelation(&series, 2) + 1.0).abs() < 1e-9);
+        assert!(autocorrelation(&series, 19) == 0.0);
+        assert!(autocorrelation(&[1.0; 10], 2) == 0.0);
+        assert!(!is_weekend(0)); // Thursday
+        assert!(is_weekend(2));
+        assert!(is_weekend(3));
+        assert!(!is_weekend(4));
+    }
+
+    #[test]
+    fn test_diurnal_permutation_test() {
+        let _pathid: db::PathId = db::tests::make_path_id();
+
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let mut config = DEFAULT_TEMPCONFIG;
+        config.diurnal_permutations = 199;
+        config.diurnal_max_pvalue = 0.05;
+
+        // Odd bins are shifted every day, which is unlikely by chance.
+        let time2bin = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 51, 50, 1, 55, 50, 1);
+        let mut psum =
+            PathSummary::build(&_pathid, &time2bin, BINS_IN_WEEK, &summarizer, &DEFAULT_TEMPCONFIG);
+        assert!(psum.diurnal_pvalue.is_none());
+        assert!(psum.row(&_pathid).last() == Some(&Value::Null));
+        psum.classify(BINS_IN_WEEK, &config);
+        assert!(psum.diurnal_pvalue == Some(1.0 / 200.0));
+        assert!(psum.temporal_behavior == TemporalBehavior::Diurnal);
+
+        // Most bins are shifted, so many bad bins arise by chance.
+        let time2bin = mock_weeks_minrtt_p50(1, |t| (t / BIN_DURATION_SECS) % 10 < 7);
+        let mut psum =
+            PathSummary::build(&_pathid, &time2bin, BINS_IN_WEEK, &summarizer, &DEFAULT_TEMPCONFIG);
         assert!(psum.temporal_behavior == TemporalBehavior::Diurnal);
+        psum.classify(BINS_IN_WEEK, &config);
+        assert!(psum.diurnal_pvalue.unwrap() > 0.05);
+        assert!(psum.temporal_behavior == TemporalBehavior::Episodic);
+
+        // Weekday and weekend bad bins are tested over their own days.
+        config.weekly_min_weeks = 2;
+        config.weekly_min_autocorr = 1.0;
+        let weekend = |t: u64| is_weekend((t / 86400) as u32);
+        let business_hours = |t: u64| (9 * 3600..17 * 3600).contains(&(t % 86400));
+        let time2bin = mock_weeks_minrtt_p50(2, |t| !weekend(t) && business_hours(t));
+        let nbins = time2bin.len() as u32;
+        let psum = PathSummary::build(&_pathid, &time2bin, nbins, &summarizer, &config);
+        assert!(psum.diurnal_pvalue.unwrap() <= 0.05);
+        assert!(psum.temporal_behavior == TemporalBehavior::WeekdayDiurnal);
+
+        // Most weekday bins are shifted, so many weekday bad bins arise
+        // by chance.
+        let time2bin =
+            mock_weeks_minrtt_p50(2, |t| !weekend(t) && (t / BIN_DURATION_SECS) % 10 < 7);
+        let mut psum = PathSummary::build(&_pathid, &time2bin, nbins, &summarizer, &config);
+        assert!(psum.weekday_bad_bins >= config.diurnal_min_bad_bins);
+        assert!(psum.diurnal_pvalue.unwrap() > 0.05);
+        assert!(psum.temporal_behavior == TemporalBehavior::Episodic);
+        config.diurnal_permutations = 0;
+        psum.classify(nbins, &config);
+        assert!(psum.temporal_behavior == TemporalBehavior::WeekdayDiurnal);
     }
 
     #[test]
@@ -1306,7 +2859,7 @@ mod tests {
 
         let mut config = DEFAULT_TEMPCONFIG;
         config.min_frac_valid_bins = 0.5;
-        psum.classify(BINS_IN_WEEK, time2bin.len() as u32, &config);
+        psum.classify(BINS_IN_WEEK, &config);
         assert!(psum.temporal_behavior == TemporalBehavior::Continuous);
 
         let time2bin = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 51, 50, 100, 51, 50, 1);
@@ -1316,7 +2869,7 @@ mod tests {
 
         let mut config = DEFAULT_TEMPCONFIG;
         config.min_frac_valid_bins = 0.5;
-        psum.classify(BINS_IN_WEEK, time2bin.len() as u32, &config);
+        psum.classify(BINS_IN_WEEK, &config);
         assert!(psum.temporal_behavior == TemporalBehavior::Uneventful);
     }
 
@@ -1364,7 +2917,7 @@ mod tests {
         let mut config = DEFAULT_TEMPCONFIG;
         config.continuous_min_frac_shifted_bins = 0.5;
         config.min_frac_valid_bins = 0.4;
-        dbsum.reclassify(&database, &config);
+        dbsum.reclassify(&config);
         assert!(dbsum.pathid2summary.len() == 2);
         assert!(dbsum.pathid2summary[&pid1].temporal_behavior == TemporalBehavior::Continuous);
         assert!(dbsum.pathid2summary[&pid2].temporal_behavior == TemporalBehavior::Continuous);
@@ -1382,6 +2935,231 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_db_summary_merge() {
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let time2bin1 = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 51, 50, 1, 55, 50, 1);
+        let pid1: db::PathId = db::tests::make_path_id();
+        let mut time2bin2 =
+            db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 55, 50, 1, 55, 50, 1);
+        let half: u64 = *time2bin2.keys().nth(time2bin2.len() / 2).unwrap();
+        time2bin2.split_off(&half);
+        let pid2 = db::PathId {
+            vip_metro: "mia".to_string(),
+            bgp_ip_prefix: "2.0.0.0/24".parse().unwrap(),
+            client_continent: db::ClientContinent::Unknown,
+            client_country: ['B', 'R'],
+        };
+
+        let mut database: db::DB = db::DB::default();
+        database.insert(pid1.clone(), time2bin1.clone());
+        database.insert(pid2.clone(), time2bin2.clone());
+        let dbsum: DBSummary = DBSummary::build(&database, &summarizer, &DEFAULT_TEMPCONFIG);
+        assert!(dbsum.pathid2summary[&pid2].temporal_behavior == TemporalBehavior::MissingBins);
+
+        let mut shard1: db::DB = db::DB::default();
+        shard1.insert(pid1, time2bin1);
+        let mut shard2: db::DB = db::DB::default();
+        shard2.insert(pid2.clone(), time2bin2);
+        let mut merged: DBSummary = DBSummary::build(&shard1, &summarizer, &DEFAULT_TEMPCONFIG);
+        let dbsum2: DBSummary = DBSummary::build(&shard2, &summarizer, &DEFAULT_TEMPCONFIG);
+        assert!(dbsum2.pathid2summary[&pid2].temporal_behavior != TemporalBehavior::MissingBins);
+        merged.merge(dbsum2).unwrap();
+        assert!(merged.total_bins == dbsum.total_bins);
+        let mut other: DBSummary = DBSummary::build(&shard2, &summarizer, &DEFAULT_TEMPCONFIG);
+        other.summarizer = "other".to_string();
+        match merged.merge(other).unwrap_err() {
+            MergeError::Summarizer {
+                summarizer,
+                other,
+                ..
+            } => assert!(summarizer == dbsum.summarizer && other == "other"),
+            err => panic!("{}", err),
+        }
+        let mut respec: DBSummary = DBSummary::build(&shard2, &summarizer, &DEFAULT_TEMPCONFIG);
+        respec.spec = Some("minrtt50-opp:thresh=5.001".to_string());
+        merged.spec = Some("minrtt50-opp:thresh=5.002".to_string());
+        match merged.merge(respec).unwrap_err() {
+            MergeError::Summarizer {
+                other,
+                other_spec,
+                ..
+            } => {
+                assert!(other == dbsum.summarizer);
+                assert!(other_spec.as_deref() == Some("minrtt50-opp:thresh=5.001"));
+            }
+            err => panic!("{}", err),
+        }
+        merged.spec = None;
+        let overlapping: DBSummary = DBSummary::build(&shard2, &summarizer, &DEFAULT_TEMPCONFIG);
+        let err: MergeError = merged.merge(overlapping).unwrap_err();
+        assert!(
+            err == MergeError::OverlappingPaths {
+                path: pid2.clone(),
+                count: 1
+            }
+        );
+        merged.reclassify(&DEFAULT_TEMPCONFIG);
+        assert!(merged.pathid2summary == dbsum.pathid2summary);
+        let tables: TableGroup = dbsum.temporal_tables();
+        let merged_tables: TableGroup = merged.temporal_tables();
+        for (table, merged_table) in tables.tables.iter().zip(merged_tables.tables.iter()) {
+            // Fractions are NaN for empty continents, compare as text.
+            assert!(format!("{:?}", table.sections) == format!("{:?}", merged_table.sections));
+        }
+    }
+
+    #[test]
+    fn test_db_summary_baseline_routes() {
+        let mut database: db::DB = db::DB::default();
+        let time2bin = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 50, 1, 60, 50, 1);
+        let pid: db::PathId = db::tests::make_path_id();
+        assert!(database.insert(pid.clone(), time2bin).is_none());
+        let summarizer =
+            MinRtt50LowerBoundDegradationSummarizer::new(0.0, 5.0, 10.0, 10.0, &database);
+        let dbsum: DBSummary = DBSummary::build(&database, &summarizer, &DEFAULT_TEMPCONFIG);
+        let time2binstats = &dbsum.pathid2summary[&pid].time2binstats;
+        let binstats: &TimeBinStats = &time2binstats[&BIN_DURATION_SECS];
+        assert!(
+            binstats.selection
+                == Some(RouteSelection::Baseline {
+                    primary: 0
+                })
+        );
+        // The primary route is 10ms worse than its baseline
+        assert!((binstats.route_diffs.minrtt50.0 - 10.0).abs() < 1e-6);
+        assert!((binstats.route_diffs.minrtt50.0 - binstats.diff_ci).abs() < 1e-6);
+        let bin: &db::TimeBin = &database.pathid2info[&pid].time2bin[&BIN_DURATION_SECS];
+        match summarizer.get_routes(&pid, bin, binstats.selection.unwrap()) {
+            Some(SelectedRoutes::Baseline {
+                baseline,
+                primary,
+            }) => {
+                assert!(baseline.minrtt_ms_p50 == 50);
+                assert!(primary.minrtt_ms_p50 == 60);
+            }
+            _ => unreachable!(),
+        }
+        assert!(RouteSelection::Baseline {
+            primary: 0
+        }
+        .resolve(bin)
+        .is_none());
+        assert!(summarizer
+            .get_routes(
+                &pid,
+                bin,
+                RouteSelection::Baseline {
+                    primary: 5
+                }
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_db_summary_save_load() -> Result<(), Box<dyn std::error::Error>> {
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let mut database: db::DB = db::DB::default();
+        let time2bin = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 51, 50, 1, 55, 50, 1);
+        let pid: db::PathId = db::tests::make_path_id();
+        assert!(database.insert(pid.clone(), time2bin).is_none());
+        let mut dbsum: DBSummary = DBSummary::build(&database, &summarizer, &DEFAULT_TEMPCONFIG);
+        assert!(dbsum.pathid2summary[&pid].existing_bytes == database.total_traffic);
+        let binstats: &TimeBinStats =
+            dbsum.pathid2summary[&pid].time2binstats.values().next().unwrap();
+        assert!(binstats.route_diffs.minrtt50.0 == -binstats.diff_ci);
+        assert!(
+            binstats.selection
+                == Some(RouteSelection::Alternate {
+                    primary: 0,
+                    alternate: 1
+                })
+        );
+
+        let mut file = std::env::temp_dir();
+        file.push(format!("fbperf-test-save-load-{}.json.gz", std::process::id()));
+        dbsum.spec = Some("minrtt50-opp:thresh=5.125".to_string());
+        dbsum.save(&file)?;
+        let mut loaded: DBSummary = DBSummary::load(&file)?;
+        fs::remove_file(&file)?;
+        assert!(loaded.summarizer == summarizer.prefix());
+        assert!(loaded.spec == dbsum.spec);
+        assert!(loaded.total_bins == database.total_bins);
+        assert!(loaded.pathid2summary == dbsum.pathid2summary);
+        assert!(loaded.total_bytes == dbsum.total_bytes);
+
+        let mut config = DEFAULT_TEMPCONFIG;
+        config.continuous_min_frac_shifted_bins = 0.5;
+        dbsum.reclassify(&config);
+        loaded.reclassify(&config);
+        assert!(loaded.pathid2summary[&pid].temporal_behavior == TemporalBehavior::Continuous);
+        assert!(loaded.pathid2summary == dbsum.pathid2summary);
+        assert!(loaded.valid_bytes == dbsum.valid_bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_db_summary_asymmetric_ci() -> Result<(), Box<dyn std::error::Error>> {
+        let summarizer = HdRatioBootstrapDifferenceImprovementSummarizer {
+            hdratio_boot_min_improv: 0.2,
+            max_hdratio_boot_diff_ci_fullwidth: 0.15,
+            compare_lower_bound: true,
+        };
+        // The bootstrapped CI [0.22, 0.35] of the difference 0.25 is
+        // skewed towards larger improvements.
+        let mut database: db::DB = db::DB::default();
+        let time2bin: BTreeMap<u64, db::TimeBin> = (0..8)
+            .map(|i| {
+                let time: u64 = i * BIN_DURATION_SECS;
+                (time, db::TimeBin::mock_hdratio_boot(time, 0.70, 0.95, 0.22, 0.35))
+            })
+            .collect();
+        let pid: db::PathId = db::tests::make_path_id();
+        assert!(database.insert(pid.clone(), time2bin).is_none());
+        let dbsum: DBSummary = DBSummary::build(&database, &summarizer, &DEFAULT_TEMPCONFIG);
+        let check = |binstats: &TimeBinStats| {
+            assert!((binstats.diff_ci - 0.25).abs() < 1e-6);
+            assert!((binstats.diff_ci_lb - 0.22).abs() < 1e-6);
+            assert!((binstats.diff_ci_ub - 0.35).abs() < 1e-6);
+            assert!(binstats.is_shifted);
+        };
+        dbsum.pathid2summary[&pid].time2binstats.values().for_each(check);
+
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("fbperf-test-asymmetric-ci-{}", std::process::id()));
+        let file: PathBuf = dir.join("summary.json.gz");
+        fs::create_dir_all(&dir)?;
+        dbsum.save(&file)?;
+        let loaded: DBSummary = DBSummary::load(&file)?;
+        assert!(loaded.pathid2summary == dbsum.pathid2summary);
+        loaded.pathid2summary[&pid].time2binstats.values().for_each(check);
+
+        loaded.dump_cdfs(&dir)?;
+        let values = |name: &str| -> Vec<f32> {
+            fs::read_to_string(dir.join(name))
+                .unwrap()
+                .lines()
+                .map(|line| line.split(' ').next().unwrap().parse().unwrap())
+                .collect()
+        };
+        let lbs: Vec<f32> = values("main_lb_weighted_false_table_false.cdf");
+        let ubs: Vec<f32> = values("main_ub_weighted_false_table_false.cdf");
+        fs::remove_dir_all(&dir)?;
+        assert!(!lbs.is_empty() && lbs.iter().all(|lb| (lb - 0.22).abs() < 1e-6));
+        assert!(!ubs.is_empty() && ubs.iter().all(|ub| (ub - 0.35).abs() < 1e-6));
+        Ok(())
+    }
+
     #[test]
     fn test_compute_offset() {
         for bin_duration_secs in (300..=1200).step_by(300) {
@@ -0,0 +1,451 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::performance::db;
+use crate::performance::perfstats::{DBSummary, PathSummary, TemporalBehavior};
+use crate::performance::sink::{OutputFormat, Table, TableGroup, Value};
+
+/// A `PathSummary` quantity, named as in `path-summaries.csv`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quantity {
+    DistinctShifts,
+    BadBytes,
+    ExistingBytes,
+    NorouteBytes,
+    ShiftedBytes,
+    ValidBytes,
+    WideciBytes,
+    BadBins,
+    ExistingBins,
+    NorouteBins,
+    ShiftedBins,
+    ValidBins,
+    WideciBins,
+}
+
+const QUANTITIES: [(&str, Quantity); 13] = [
+    ("distinct_shifts", Quantity::DistinctShifts),
+    ("bad_bytes", Quantity::BadBytes),
+    ("existing_bytes", Quantity::ExistingBytes),
+    ("noroute_bytes", Quantity::NorouteBytes),
+    ("shifted_bytes", Quantity::ShiftedBytes),
+    ("valid_bytes", Quantity::ValidBytes),
+    ("wideci_bytes", Quantity::WideciBytes),
+    ("bad_bins", Quantity::BadBins),
+    ("existing_bins", Quantity::ExistingBins),
+    ("noroute_bins", Quantity::NorouteBins),
+    ("shifted_bins", Quantity::ShiftedBins),
+    ("valid_bins", Quantity::ValidBins),
+    ("wideci_bins", Quantity::WideciBins),
+];
+
+/// Ranks paths by `numerator / denominator`, or by `numerator` alone,
+/// highest first.  Paths with a zero denominator rank last.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RankMetric {
+    pub numerator: Quantity,
+    pub denominator: Option<Quantity>,
+}
+
+/// The ranking metric for each temporal class that is reported and the
+/// number of paths with the most traffic ranked in each class.
+#[derive(Clone, Debug)]
+pub struct RankConfig {
+    pub class2metric: Vec<(TemporalBehavior, RankMetric)>,
+    pub top: usize,
+}
+
+/// Paths of each class in `RankConfig`, highest ranked first.
+pub struct RankedPaths {
+    pub config: RankConfig,
+    pub class2paths: Vec<(TemporalBehavior, Vec<RankedPath>)>,
+}
+
+pub struct RankedPath {
+    pub pathid: Arc<db::PathId>,
+    pub value: f64,
+    pub existing_bytes: u128,
+    pub shifted_bytes: u128,
+    /// Peer types of the primary and alternate routes carrying the
+    /// most bytes over shifted bins; `None` without shifted bins.
+    pub primary_peer_type: Option<db::PeerType>,
+    pub alternate_peer_type: Option<db::PeerType>,
+}
+
+impl Quantity {
+    fn value(self, psum: &PathSummary) -> f64 {
+        match self {
+            Quantity::DistinctShifts => f64::from(psum.distinct_shifts),
+            Quantity::BadBytes => psum.bad_bytes as f64,
+            Quantity::ExistingBytes => psum.existing_bytes as f64,
+            Quantity::NorouteBytes => psum.noroute_bytes as f64,
+            Quantity::ShiftedBytes => psum.shifted_bytes as f64,
+            Quantity::ValidBytes => psum.valid_bytes as f64,
+            Quantity::WideciBytes => psum.wideci_bytes as f64,
+            Quantity::BadBins => f64::from(psum.bad_bins),
+            Quantity::ExistingBins => f64::from(psum.existing_bins),
+            Quantity::NorouteBins => f64::from(psum.noroute_bins),
+            Quantity::ShiftedBins => f64::from(psum.shifted_bins),
+            Quantity::ValidBins => psum.time2binstats.len() as f64,
+            Quantity::WideciBins => f64::from(psum.wideci_bins),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        QUANTITIES.iter().find(|(_, q)| *q == self).unwrap().0
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Quantity, String> {
+        QUANTITIES.iter().find(|(name, _)| *name == s).map(|(_, q)| *q).ok_or_else(|| {
+            let names: Vec<&str> = QUANTITIES.iter().map(|(name, _)| *name).collect();
+            format!("unknown quantity {} ({})", s, names.join(", "))
+        })
+    }
+}
+
+impl RankMetric {
+    pub fn value(&self, psum: &PathSummary) -> f64 {
+        let numerator: f64 = self.numerator.value(psum);
+        match self.denominator {
+            None => numerator,
+            Some(denominator) => {
+                let denominator: f64 = denominator.value(psum);
+                if denominator == 0.0 {
+                    f64::NEG_INFINITY
+                } else {
+                    numerator / denominator
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for RankMetric {
+    type Err = String;
+    /// Parses `numerator` or `numerator/denominator`, e.g.,
+    /// `shifted_bins/valid_bins`.
+    fn from_str(s: &str) -> Result<RankMetric, String> {
+        let mut parts = s.splitn(2, '/');
+        let numerator: Quantity = parts.next().unwrap().parse()?;
+        let denominator: Option<Quantity> = match parts.next() {
+            Some(text) => Some(text.parse()?),
+            None => None,
+        };
+        Ok(RankMetric {
+            numerator,
+            denominator,
+        })
+    }
+}
+
+impl fmt::Display for RankMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.denominator {
+            None => write!(f, "{}", self.numerator.name()),
+            Some(denominator) => write!(f, "{}/{}", self.numerator.name(), denominator.name()),
+        }
+    }
+}
+
+impl Default for RankConfig {
+    /// Continuous paths by the fraction of valid bins that are shifted,
+    /// diurnal paths by the fraction of shifted bytes in bad bins, and
+    /// episodic paths by the number of shifted bins per shift.
+    fn default() -> RankConfig {
+        RankConfig {
+            class2metric: vec![
+                (
+                    TemporalBehavior::Continuous,
+                    RankMetric {
+                        numerator: Quantity::ShiftedBins,
+                        denominator: Some(Quantity::ValidBins),
+                    },
+                ),
+                (
+                    TemporalBehavior::Diurnal,
+                    RankMetric {
+                        numerator: Quantity::BadBytes,
+                        denominator: Some(Quantity::ShiftedBytes),
+                    },
+                ),
+                (
+                    TemporalBehavior::Episodic,
+                    RankMetric {
+                        numerator: Quantity::ShiftedBins,
+                        denominator: Some(Quantity::DistinctShifts),
+                    },
+                ),
+            ],
+            top: 100,
+        }
+    }
+}
+
+impl RankConfig {
+    /// Sets the metric of a class from `Class=metric`, e.g.,
+    /// `Continuous=shifted_bytes`.
+    pub fn set(&mut self, spec: &str) -> Result<(), String> {
+        let mut parts = spec.splitn(2, '=');
+        let class: TemporalBehavior = parts.next().unwrap().parse()?;
+        let metric: RankMetric =
+            parts.next().ok_or_else(|| format!("expected Class=metric, got {}", spec))?.parse()?;
+        match self.class2metric.iter_mut().find(|(c, _)| *c == class) {
+            Some(entry) => entry.1 = metric,
+            None => self.class2metric.push((class, metric)),
+        }
+        Ok(())
+    }
+}
+
+impl RankedPaths {
+    pub fn build(dbsum: &DBSummary, config: &RankConfig) -> RankedPaths {
+        let mut class2paths: Vec<(TemporalBehavior, Vec<RankedPath>)> = Vec::new();
+        for (class, metric) in config.class2metric.iter() {
+            let mut candidates: Vec<(&Arc<db::PathId>, &PathSummary)> = dbsum
+                .pathid2summary
+                .iter()
+                .filter(|(_pid, psum)| psum.temporal_behavior == *class)
+                .collect();
+            candidates.sort_by(|(pid1, psum1), (pid2, psum2)| {
+                psum2.existing_bytes.cmp(&psum1.existing_bytes).then_with(|| pid1.cmp(pid2))
+            });
+            candidates.truncate(config.top);
+            let mut paths: Vec<RankedPath> = candidates
+                .into_iter()
+                .map(|(pid, psum)| RankedPath::build(pid, psum, metric))
+                .collect();
+            paths.sort_by(|p1, p2| {
+                p2.value
+                    .partial_cmp(&p1.value)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| p2.existing_bytes.cmp(&p1.existing_bytes))
+                    .then_with(|| p1.pathid.cmp(&p2.pathid))
+            });
+            class2paths.push((*class, paths));
+        }
+        RankedPaths {
+            config: config.clone(),
+            class2paths,
+        }
+    }
+
+    pub fn dump(&self, path: &Path, formats: &[OutputFormat]) -> Result<(), io::Error> {
+        std::fs::create_dir_all(path)?;
+        for format in formats.iter() {
+            format.sink().write(path, &self.table())?;
+        }
+        Ok(())
+    }
+
+    const COLUMNS: [&'static str; 12] = [
+        "rank",
+        "vip_metro",
+        "bgp_prefix",
+        "continent",
+        "country",
+        "temporal_behavior",
+        "metric",
+        "value",
+        "existing_bytes",
+        "shifted_bytes",
+        "primary_peer_type",
+        "alternate_peer_type",
+    ];
+
+    /// One table per class, written to `ranked-paths-<class>` by sinks
+    /// with one file per table.
+    pub fn table(&self) -> TableGroup {
+        let peer_type = |pt: Option<db::PeerType>| match pt {
+            Some(pt) => Value::Str(format!("{:?}", pt)),
+            None => Value::Null,
+        };
+        let tables: Vec<Table> = self
+            .class2paths
+            .iter()
+            .zip(self.config.class2metric.iter())
+            .map(|((class, paths), (_, metric))| Table {
+                name: table_name(*class),
+                columns: RankedPaths::COLUMNS.to_vec(),
+                key_columns: 0,
+                key_separator: " ",
+                sections: vec![paths
+                    .iter()
+                    .enumerate()
+                    .map(|(i, rp)| {
+                        vec![
+                            Value::Int(i as u128 + 1),
+                            Value::Str(rp.pathid.vip_metro.clone()),
+                            Value::Str(rp.pathid.bgp_ip_prefix.to_string()),
+                            Value::Str(format!("{:?}", rp.pathid.client_continent)),
+                            Value::Str(rp.pathid.client_country.iter().collect()),
+                            Value::Str(format!("{:?}", class)),
+                            Value::Str(metric.to_string()),
+                            if rp.value.is_finite() {
+                                Value::Float(rp.value, 4)
+                            } else {
+                                Value::Null
+                            },
+                            Value::Int(rp.existing_bytes),
+                            Value::Int(rp.shifted_bytes),
+                            peer_type(rp.primary_peer_type),
+                            peer_type(rp.alternate_peer_type),
+                        ]
+                    })
+                    .collect()],
+            })
+            .collect();
+        TableGroup {
+            text_name: "ranked-paths",
+            pickle_name: None,
+            tables,
+        }
+    }
+}
+
+impl RankedPath {
+    fn build(pathid: &Arc<db::PathId>, psum: &PathSummary, metric: &RankMetric) -> RankedPath {
+        let mut primary2bytes: BTreeMap<db::PeerType, u128> = BTreeMap::new();
+        let mut alternate2bytes: BTreeMap<db::PeerType, u128> = BTreeMap::new();
+        for bs in psum.time2binstats.values().filter(|bs| bs.is_shifted) {
+            *primary2bytes.entry(bs.primary_peer_type).or_insert(0) += u128::from(bs.bytes);
+            *alternate2bytes.entry(bs.alternate_peer_type).or_insert(0) += u128::from(bs.bytes);
+        }
+        RankedPath {
+            pathid: Arc::clone(pathid),
+            value: metric.value(psum),
+            existing_bytes: psum.existing_bytes,
+            shifted_bytes: psum.shifted_bytes,
+            primary_peer_type: dominant(&primary2bytes),
+            alternate_peer_type: dominant(&alternate2bytes),
+        }
+    }
+}
+
+/// The key with the most bytes, the first one on ties.
+fn dominant(type2bytes: &BTreeMap<db::PeerType, u128>) -> Option<db::PeerType> {
+    type2bytes.iter().rev().max_by_key(|(_pt, bytes)| **bytes).map(|(pt, _bytes)| *pt)
+}
+
+fn table_name(class: TemporalBehavior) -> &'static str {
+    match class {
+        TemporalBehavior::Uneventful => "ranked-paths-uneventful",
+        TemporalBehavior::Continuous => "ranked-paths-continuous",
+        TemporalBehavior::Diurnal => "ranked-paths-diurnal",
+        TemporalBehavior::Episodic => "ranked-paths-episodic",
+        TemporalBehavior::Undersampled => "ranked-paths-undersampled",
+        TemporalBehavior::NoRoute => "ranked-paths-noroute",
+        TemporalBehavior::MissingBins => "ranked-paths-missingbins",
+        TemporalBehavior::WeekdayDiurnal => "ranked-paths-weekdaydiurnal",
+        TemporalBehavior::WeekendDiurnal => "ranked-paths-weekenddiurnal",
+        TemporalBehavior::Weekly => "ranked-paths-weekly",
+        TemporalBehavior::Uninitialized | TemporalBehavior::SIZE => "ranked-paths-uninitialized",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::performance::perfstats::TemporalConfig;
+    use crate::performance::summarizers::opportunity::MinRtt50ImprovementSummarizer;
+
+    #[test]
+    fn test_rank_metric_parse() {
+        let metric: RankMetric = "shifted_bins/valid_bins".parse().unwrap();
+        assert!(metric == RankConfig::default().class2metric[0].1);
+        assert!(metric.to_string() == "shifted_bins/valid_bins");
+        let metric: RankMetric = "shifted_bytes".parse().unwrap();
+        assert!(metric.denominator.is_none());
+        assert!("shifted_bins/bogus".parse::<RankMetric>().is_err());
+
+        let mut config = RankConfig::default();
+        config.set("Continuous=shifted_bytes").unwrap();
+        config.set("Weekly=bad_bins").unwrap();
+        assert!(config.class2metric.len() == 4);
+        assert!(config.class2metric[0].1 == metric);
+        assert!(config.set("Continuous").is_err());
+        assert!(config.set("Bogus=bad_bins").is_err());
+    }
+
+    #[test]
+    fn test_ranked_paths() {
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let tempconfig = TemporalConfig {
+            bin_duration_secs: 900,
+            min_days: 1,
+            min_frac_existing_bins: 0.0,
+            min_frac_bins_with_alternate: 0.0,
+            min_frac_valid_bins: 0.0,
+            continuous_min_frac_shifted_bins: 0.5,
+            diurnal_min_bad_bins: 100,
+            diurnal_bad_bin_min_prob_shift: 0.5,
+            diurnal_permutations: 0,
+            diurnal_max_pvalue: 1.0,
+            uneventful_max_frac_shifted_bins: 0.0,
+            weekly_min_weeks: 0,
+            weekly_min_autocorr: 1.0,
+            shift_enter_min_bins: 1,
+            shift_exit_min_bins: 1,
+        };
+        // Paths are shifted in the first `shifted` of `nbins` bins.
+        let mut database = db::DB::default();
+        for (metro, nbins, shifted) in [("gru", 8, 4), ("gig", 8, 8), ("mia", 4, 4)].iter() {
+            let mut pid: db::PathId = db::tests::make_path_id();
+            pid.vip_metro = metro.to_string();
+            let mut time2bin: BTreeMap<u64, db::TimeBin> = BTreeMap::new();
+            for i in 0..*nbins {
+                let pri_minrtt_p50 = if i < *shifted {
+                    60
+                } else {
+                    50
+                };
+                let time: u64 = i * 900;
+                time2bin.insert(time, db::TimeBin::mock_minrtt_p50(time, pri_minrtt_p50, 50, 1));
+            }
+            database.insert(pid, time2bin);
+        }
+        let dbsum = DBSummary::build(&database, &summarizer, &tempconfig);
+
+        let metros = |ranked: &RankedPaths| -> Vec<String> {
+            ranked.class2paths[0].1.iter().map(|rp| rp.pathid.vip_metro.clone()).collect()
+        };
+        let mut config = RankConfig::default();
+        let ranked = RankedPaths::build(&dbsum, &config);
+        assert!(ranked.class2paths[0].0 == TemporalBehavior::Continuous);
+        assert!(metros(&ranked) == vec!["gig", "mia", "gru"]);
+        let top = &ranked.class2paths[0].1[0];
+        assert!(top.value == 1.0);
+        assert!(top.primary_peer_type.is_some());
+
+        config.set("Continuous=shifted_bytes").unwrap();
+        let ranked = RankedPaths::build(&dbsum, &config);
+        assert!(metros(&ranked) == vec!["gig", "gru", "mia"]);
+
+        // The cutoff keeps the paths with the most traffic.
+        config.top = 2;
+        let ranked = RankedPaths::build(&dbsum, &config);
+        assert!(metros(&ranked) == vec!["gig", "gru"]);
+
+        let group = ranked.table();
+        assert!(group.tables.len() == 3);
+        assert!(group.tables[0].name == "ranked-paths-continuous");
+        let first: &Vec<Value> = group.tables[0].rows().next().unwrap();
+        assert!(first[0] == Value::Int(1));
+        assert!(first[1] == Value::Str("gig".to_string()));
+        assert!(first[6] == Value::Str("shifted_bytes".to_string()));
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use log::{error, info};
+
+use crate::performance::db;
+use crate::performance::perfstats::{
+    DBSummary, TemporalBehavior, TemporalConfig, TransitionMatrix,
+};
+use crate::performance::sweep::{self, ConfigError};
+
+/// Class transitions between a reference `TemporalConfig` and each
+/// config in a grid, all applied to the same `DBSummary`.
+pub struct SensitivityReport {
+    pub reference: TemporalConfig,
+    pub entries: Vec<SensitivityEntry>,
+}
+
+pub struct SensitivityEntry {
+    pub tempconfig: TemporalConfig,
+    /// Transitions from the behavior under the reference config to the
+    /// behavior under `tempconfig`; bytes are the path's total traffic.
+    pub transitions: TransitionMatrix,
+}
+
+impl SensitivityReport {
+    /// Reclassifies `dbsum` under `reference` and every config in
+    /// `grid`; `dbsum` is left classified under `reference`.  Configs
+    /// in `grid` must use the same `bin_duration_secs` as `reference`.
+    pub fn build(
+        dbsum: &mut DBSummary,
+        reference: &TemporalConfig,
+        grid: &[TemporalConfig],
+    ) -> SensitivityReport {
+        dbsum.reclassify(reference);
+        let pathid2reference: HashMap<Arc<db::PathId>, TemporalBehavior> = dbsum
+            .pathid2summary
+            .iter()
+            .map(|(pid, psum)| (Arc::clone(pid), psum.temporal_behavior))
+            .collect();
+        let mut entries: Vec<SensitivityEntry> = Vec::with_capacity(grid.len());
+        for tempconfig in grid {
+            assert!(tempconfig.bin_duration_secs == reference.bin_duration_secs);
+            dbsum.reclassify(tempconfig);
+            let mut transitions = TransitionMatrix::default();
+            for (pid, psum) in dbsum.pathid2summary.iter() {
+                transitions.add(pathid2reference[pid], psum.temporal_behavior, psum.existing_bytes);
+            }
+            entries.push(SensitivityEntry {
+                tempconfig: *tempconfig,
+                transitions,
+            });
+        }
+        dbsum.reclassify(reference);
+        SensitivityReport {
+            reference: *reference,
+            entries,
+        }
+    }
+
+    /// Writes one transition matrix per config plus a summary with one
+    /// line per config, both identified by the thresholds that differ
+    /// from the reference; `TemporalConfig::prefix` rounds values and
+    /// omits disabled settings, so it can name two configs alike.
+    pub fn dump(&self, path: &Path) -> Result<(), io::Error> {
+        std::fs::create_dir_all(path)?;
+        self.reference.dump(path)?;
+        let mut key2data: BTreeMap<String, (u64, u64, String, String, f64)> = BTreeMap::new();
+
+        let mut filepath = path.to_path_buf();
+        filepath.push("sensitivity-summary.txt");
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(filepath)?;
+        let mut bw = io::BufWriter::new(file);
+        for entry in self.entries.iter() {
+            let diff: Vec<String> = entry.tempconfig.diff(&self.reference);
+            let name: String = if diff.is_empty() {
+                "reference".to_string()
+            } else {
+                diff.join(",")
+            };
+            let (changed_paths, changed_bytes) = entry.transitions.changed();
+            let (total_paths, total_bytes) = entry.transitions.total();
+            let data = (
+                changed_paths,
+                total_paths,
+                changed_bytes.to_string(),
+                total_bytes.to_string(),
+                changed_bytes as f64 / total_bytes as f64,
+            );
+            writeln!(bw, "{} {} {} {} {} {:0.3}", name, data.0, data.1, data.2, data.3, data.4)?;
+            entry.transitions.dump(path, &format!("transitions--{}", name))?;
+            key2data.insert(name, data);
+        }
+
+        let mut filepath = path.to_path_buf();
+        filepath.push("sensitivity-summary.pickle");
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(filepath)?;
+        let mut bw = io::BufWriter::new(file);
+        serde_pickle::to_writer(&mut bw, &key2data, true).unwrap_or_else(|e| {
+            error!("{}", e);
+            info!("could not dump sensitivity summary as pickle");
+        });
+
+        Ok(())
+    }
+}
+
+/// Values of one `TemporalConfig` parameter to try in a one-at-a-time
+/// grid, e.g., `diurnal_min_bad_bins=1,4,8` on the command line or
+/// `diurnal_min_bad_bins = [1, 4, 8]` in the `[sensitivity]` table of a
+/// sweep configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensitivityRange {
+    pub name: String,
+    pub values: Vec<toml::Value>,
+}
+
+/// Ranges swept when none are given.
+const DEFAULT_RANGES: [&str; 5] = [
+    "min_frac_valid_bins=0.4,0.6,0.8",
+    "continuous_min_frac_shifted_bins=0.5,0.75,0.9",
+    "diurnal_min_bad_bins=1,4,8,16",
+    "diurnal_bad_bin_min_prob_shift=0.3,0.5,0.8",
+    "uneventful_max_frac_shifted_bins=0.0,0.01,0.05",
+];
+
+impl SensitivityRange {
+    pub fn parse(text: &str) -> Result<SensitivityRange, ConfigError> {
+        let (name, values) = match text.find('=') {
+            Some(i) => (&text[..i], &text[i + 1..]),
+            None => return Err(ConfigError::new(text, "expected parameter=value,value,...")),
+        };
+        let values: Vec<toml::Value> = values
+            .split(',')
+            .filter(|v| !v.is_empty())
+            .map(|v| sweep::parse_tempconfig_value(name, v))
+            .collect::<Result<Vec<toml::Value>, ConfigError>>()?;
+        SensitivityRange::new(name, values)
+    }
+
+    pub(crate) fn new(
+        name: &str,
+        values: Vec<toml::Value>,
+    ) -> Result<SensitivityRange, ConfigError> {
+        if name == "bin_duration_secs" {
+            return Err(ConfigError::new(name, "cannot vary in a sensitivity grid"));
+        }
+        if values.is_empty() {
+            return Err(ConfigError::new(name, "empty list of values"));
+        }
+        Ok(SensitivityRange {
+            name: name.to_string(),
+            values,
+        })
+    }
+
+    pub fn defaults() -> Vec<SensitivityRange> {
+        DEFAULT_RANGES.iter().map(|text| SensitivityRange::parse(text).unwrap()).collect()
+    }
+}
+
+/// Builds a one-at-a-time grid around `reference`: each config sets a
+/// single parameter to one of the values in its range.  Configs equal
+/// to `reference` are skipped.
+pub fn build_one_at_a_time_grid(
+    reference: &TemporalConfig,
+    ranges: &[SensitivityRange],
+) -> Result<Vec<TemporalConfig>, ConfigError> {
+    let mut grid: Vec<TemporalConfig> = Vec::new();
+    for range in ranges.iter() {
+        for value in range.values.iter() {
+            grid.push(sweep::set_tempconfig_param(reference, &range.name, value)?);
+        }
+    }
+    grid.retain(|cfg| !cfg.diff(reference).is_empty());
+    Ok(grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::summarizers::opportunity::MinRtt50ImprovementSummarizer;
+    use std::path::PathBuf;
+
+    const BIN_DURATION_SECS: u64 = 900;
+    const TEMPCONFIG: TemporalConfig = TemporalConfig {
+        bin_duration_secs: 900,
+        min_days: 7,
+        min_frac_existing_bins: 1.0,
+        min_frac_bins_with_alternate: 1.0,
+        min_frac_valid_bins: 0.8,
+        continuous_min_frac_shifted_bins: 0.8,
+        diurnal_min_bad_bins: 24,
+        diurnal_bad_bin_min_prob_shift: 0.8,
+        diurnal_permutations: 0,
+        diurnal_max_pvalue: 1.0,
+        uneventful_max_frac_shifted_bins: 0.20,
+        weekly_min_weeks: 0,
+        weekly_min_autocorr: 1.0,
+        shift_enter_min_bins: 1,
+        shift_exit_min_bins: 1,
+    };
+
+    #[test]
+    fn test_sensitivity_report() {
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let mut database: db::DB = db::DB::default();
+        let time2bin1 = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 51, 50, 1, 55, 50, 1);
+        let nbins: u64 = time2bin1.len() as u64;
+        let pid1: db::PathId = db::tests::make_path_id();
+        let time2bin2 = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 55, 50, 1, 55, 50, 1);
+        let pid2 = db::PathId {
+            vip_metro: "gru".to_string(),
+            bgp_ip_prefix: "2.0.0.0/24".parse().unwrap(),
+            client_continent: db::ClientContinent::Unknown,
+            client_country: ['B', 'R'],
+        };
+        assert!(database.insert(pid1.clone(), time2bin1).is_none());
+        assert!(database.insert(pid2.clone(), time2bin2).is_none());
+        let mut dbsum: DBSummary = DBSummary::build(&database, &summarizer, &TEMPCONFIG);
+
+        let grid = vec![
+            TEMPCONFIG,
+            TemporalConfig {
+                continuous_min_frac_shifted_bins: 0.4,
+                ..TEMPCONFIG
+            },
+            TemporalConfig {
+                diurnal_min_bad_bins: 56,
+                ..TEMPCONFIG
+            },
+        ];
+        let report = SensitivityReport::build(&mut dbsum, &TEMPCONFIG, &grid);
+        assert!(report.entries.len() == 3);
+        assert!(dbsum.pathid2summary[&pid1].temporal_behavior == TemporalBehavior::Diurnal);
+
+        let path_bytes = u128::from(nbins * db::TimeBin::MOCK_TOTAL_BYTES);
+        let diurnal = TemporalBehavior::Diurnal as usize;
+        let continuous = TemporalBehavior::Continuous as usize;
+        let episodic = TemporalBehavior::Episodic as usize;
+
+        assert!(report.entries[0].transitions.changed() == (0, 0));
+        assert!(report.entries[0].transitions.total() == (2, 2 * path_bytes));
+        assert!(report.entries[0].transitions.paths[diurnal][diurnal] == 1);
+        assert!(report.entries[0].transitions.paths[continuous][continuous] == 1);
+
+        assert!(report.entries[1].transitions.changed() == (1, path_bytes));
+        assert!(report.entries[1].transitions.paths[diurnal][continuous] == 1);
+
+        assert!(report.entries[2].transitions.changed() == (1, path_bytes));
+        assert!(report.entries[2].transitions.bytes[diurnal][episodic] == path_bytes);
+        assert!(
+            report.entries[2].tempconfig.diff(&TEMPCONFIG)
+                == vec!["diurnal_min_bad_bins=56".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_dump_names_configs_apart() -> Result<(), Box<dyn std::error::Error>> {
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let mut database: db::DB = db::DB::default();
+        let time2bin = db::TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 51, 50, 1, 55, 50, 1);
+        assert!(database.insert(db::tests::make_path_id(), time2bin).is_none());
+        let mut dbsum: DBSummary = DBSummary::build(&database, &summarizer, &TEMPCONFIG);
+
+        // The p-value is unused without permutations and 0.801 rounds
+        // to 0.80, so all three configs have the same prefix.
+        let grid = vec![
+            TEMPCONFIG,
+            TemporalConfig {
+                diurnal_max_pvalue: 0.5,
+                ..TEMPCONFIG
+            },
+            TemporalConfig {
+                min_frac_valid_bins: 0.801,
+                ..TEMPCONFIG
+            },
+        ];
+        assert!(grid.iter().all(|cfg| cfg.prefix() == TEMPCONFIG.prefix()));
+        let report = SensitivityReport::build(&mut dbsum, &TEMPCONFIG, &grid);
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("fbperf-test-sensitivity-{}", std::process::id()));
+        report.dump(&dir)?;
+        for name in ["reference", "diurnal_max_pvalue=0.5", "min_frac_valid_bins=0.801"].iter() {
+            assert!(dir.join(format!("transitions--{}.txt", name)).exists(), "{}", name);
+        }
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_one_at_a_time_grid() {
+        let grid = build_one_at_a_time_grid(&TEMPCONFIG, &SensitivityRange::defaults()).unwrap();
+        assert!(grid.len() == 14);
+        assert!(grid.iter().all(|cfg| cfg.diff(&TEMPCONFIG).len() == 1));
+
+        let ranges: Vec<SensitivityRange> = ["diurnal_min_bad_bins=12,24,48", "min_days=3"]
+            .iter()
+            .map(|text| SensitivityRange::parse(text).unwrap())
+            .collect();
+        let grid = build_one_at_a_time_grid(&TEMPCONFIG, &ranges).unwrap();
+        let diffs: Vec<Vec<String>> = grid.iter().map(|cfg| cfg.diff(&TEMPCONFIG)).collect();
+        assert!(
+            diffs
+                == vec![
+                    vec!["diurnal_min_bad_bins=12".to_string()],
+                    vec!["diurnal_min_bad_bins=48".to_string()],
+                    vec!["min_days=3".to_string()],
+                ]
+        );
+
+        let err = |text: &str| SensitivityRange::parse(text).unwrap_err().key;
+        assert!(err("diurnal_min_bad_bins") == "diurnal_min_bad_bins");
+        assert!(err("diurnal_min_bad_bins=1.5") == "diurnal_min_bad_bins");
+        assert!(err("min_frac_valid_bins=") == "min_frac_valid_bins");
+        assert!(err("bin_duration_secs=300") == "bin_duration_secs");
+        assert!(err("unknown=1") == "unknown");
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use log::{error, info};
+use serde_pickle::{HashableValue, Value as PickleValue};
+
+/// A table cell.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(u128),
+    /// A value and the number of decimals in the text format.
+    Float(f64, usize),
+    Null,
+}
+
+/// A table with named columns.
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    /// File name, without extension, for formats that write one table
+    /// per file.
+    pub name: &'static str,
+    pub columns: Vec<&'static str>,
+    /// Number of leading columns that identify a row.  The text and
+    /// pickle formats join them with `key_separator`.
+    pub key_columns: usize,
+    pub key_separator: &'static str,
+    /// Rows, in order; the text format ends each section with a blank
+    /// line.
+    pub sections: Vec<Vec<Vec<Value>>>,
+}
+
+/// Tables that the text and pickle formats write together to a single
+/// file, in order.
+#[derive(Clone, Debug, Default)]
+pub struct TableGroup {
+    pub text_name: &'static str,
+    /// The pickle format skips groups without a name.
+    pub pickle_name: Option<&'static str>,
+    pub tables: Vec<Table>,
+}
+
+/// Writes `TableGroup`s into an output directory.
+pub trait Sink {
+    fn write(&self, dir: &Path, group: &TableGroup) -> Result<(), io::Error>;
+}
+
+/// Space-separated text without headers.
+pub struct TextSink;
+/// A dictionary from row key to a tuple with the remaining columns.
+/// Integers are stored as strings as they may not fit in 64 bits.
+pub struct PickleSink;
+/// One CSV file with a header per table.
+pub struct CsvSink;
+/// One JSON object per line and one file per table.
+pub struct JsonLinesSink;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Pickle,
+    Csv,
+    JsonLines,
+}
+
+impl OutputFormat {
+    pub fn sink(&self) -> Box<dyn Sink> {
+        match self {
+            OutputFormat::Text => Box::new(TextSink),
+            OutputFormat::Pickle => Box::new(PickleSink),
+            OutputFormat::Csv => Box::new(CsvSink),
+            OutputFormat::JsonLines => Box::new(JsonLinesSink),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<OutputFormat, String> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "pickle" => Ok(OutputFormat::Pickle),
+            "csv" => Ok(OutputFormat::Csv),
+            "jsonl" => Ok(OutputFormat::JsonLines),
+            _ => Err(format!("unknown output format {} (text, pickle, csv, jsonl)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x, decimals) => write!(f, "{:0.*}", decimals, x),
+            Value::Null => write!(f, "NULL"),
+        }
+    }
+}
+
+impl Value {
+    fn pickle(&self) -> PickleValue {
+        match self {
+            Value::Str(s) => PickleValue::String(s.clone()),
+            Value::Int(i) => PickleValue::String(i.to_string()),
+            Value::Float(x, _) => PickleValue::F64(*x),
+            Value::Null => PickleValue::None,
+        }
+    }
+
+    fn json(&self) -> serde_json::Value {
+        match self {
+            Value::Str(s) => serde_json::Value::String(s.clone()),
+            Value::Int(i) => match u64::try_from(*i) {
+                Ok(i) => serde_json::Value::from(i),
+                Err(_) => serde_json::Value::String(i.to_string()),
+            },
+            Value::Float(x, _) => serde_json::Value::from(*x),
+            Value::Null => serde_json::Value::Null,
+        }
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Value::Float(x, _) => x.to_string(),
+            Value::Null => String::new(),
+            value => value.to_string(),
+        }
+    }
+}
+
+impl Table {
+    pub fn rows(&self) -> impl Iterator<Item = &Vec<Value>> {
+        self.sections.iter().flatten()
+    }
+
+    fn key(&self, row: &[Value]) -> String {
+        let keys: Vec<String> = row[..self.key_columns].iter().map(|v| v.to_string()).collect();
+        keys.join(self.key_separator)
+    }
+}
+
+impl Sink for TextSink {
+    fn write(&self, dir: &Path, group: &TableGroup) -> Result<(), io::Error> {
+        let mut bw = create(dir, &format!("{}.txt", group.text_name))?;
+        for table in group.tables.iter() {
+            let multiple: bool = table.sections.len() > 1;
+            for section in table.sections.iter() {
+                for row in section.iter() {
+                    let mut fields: Vec<String> = Vec::with_capacity(row.len());
+                    if table.key_columns > 0 {
+                        fields.push(table.key(row));
+                    }
+                    fields.extend(row[table.key_columns..].iter().map(|v| v.to_string()));
+                    writeln!(bw, "{}", fields.join(" "))?;
+                }
+                if multiple || group.tables.len() > 1 {
+                    writeln!(bw)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Sink for PickleSink {
+    fn write(&self, dir: &Path, group: &TableGroup) -> Result<(), io::Error> {
+        let name: &str = match group.pickle_name {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let mut key2data: BTreeMap<HashableValue, PickleValue> = BTreeMap::new();
+        for table in group.tables.iter() {
+            for row in table.rows() {
+                let data: Vec<PickleValue> =
+                    row[table.key_columns..].iter().map(Value::pickle).collect();
+                key2data.insert(HashableValue::String(table.key(row)), PickleValue::Tuple(data));
+            }
+        }
+        let mut bw = create(dir, &format!("{}.pickle", name))?;
+        serde_pickle::value_to_writer(&mut bw, &PickleValue::Dict(key2data), true).unwrap_or_else(
+            |e| {
+                error!("{}", e);
+                info!("could not dump {} as pickle", name);
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Sink for CsvSink {
+    fn write(&self, dir: &Path, group: &TableGroup) -> Result<(), io::Error> {
+        for table in group.tables.iter() {
+            let bw = create(dir, &format!("{}.csv", table.name))?;
+            let mut wtr = csv::Writer::from_writer(bw);
+            wtr.write_record(&table.columns)?;
+            for row in table.rows() {
+                wtr.write_record(row.iter().map(Value::csv))?;
+            }
+            wtr.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Sink for JsonLinesSink {
+    fn write(&self, dir: &Path, group: &TableGroup) -> Result<(), io::Error> {
+        for table in group.tables.iter() {
+            let mut bw = create(dir, &format!("{}.jsonl", table.name))?;
+            for row in table.rows() {
+                let object: serde_json::Map<String, serde_json::Value> = table
+                    .columns
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(column, value)| (column.to_string(), value.json()))
+                    .collect();
+                serde_json::to_writer(&mut bw, &object)?;
+                writeln!(bw)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn create(dir: &Path, filename: &str) -> Result<io::BufWriter<fs::File>, io::Error> {
+    let mut filepath = dir.to_path_buf();
+    filepath.push(filename);
+    let file =
+        fs::OpenOptions::new().read(true).write(true).truncate(true).create(true).open(filepath)?;
+    Ok(io::BufWriter::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_group() -> TableGroup {
+        let row = |name: &str, cont: &str, bytes: u128, frac: f64| {
+            vec![
+                Value::Str(name.to_string()),
+                Value::Str(cont.to_string()),
+                Value::Int(bytes),
+                Value::Float(frac, 3),
+            ]
+        };
+        TableGroup {
+            text_name: "table",
+            pickle_name: Some("table"),
+            tables: vec![Table {
+                name: "class-continent",
+                columns: vec!["class", "continent", "bytes", "frac"],
+                key_columns: 2,
+                key_separator: "+",
+                sections: vec![
+                    vec![row("Diurnal", "AF", 10, 0.25), row("Diurnal", "SA", 30, 0.75)],
+                    vec![row("Episodic", "AF", 0, 0.0)],
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_sinks() -> Result<(), Box<dyn std::error::Error>> {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("fbperf-test-sinks-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let group = make_group();
+        for format in ["text", "pickle", "csv", "jsonl"].iter() {
+            format.parse::<OutputFormat>()?.sink().write(&dir, &group)?;
+        }
+        assert!("xml".parse::<OutputFormat>().is_err());
+
+        let text = fs::read_to_string(dir.join("table.txt"))?;
+        assert!(text == "Diurnal+AF 10 0.250\nDiurnal+SA 30 0.750\n\nEpisodic+AF 0 0.000\n\n");
+
+        let csv = fs::read_to_string(dir.join("class-continent.csv"))?;
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0] == "class,continent,bytes,frac");
+        assert!(lines[1] == "Diurnal,AF,10,0.25");
+        assert!(lines.len() == 4);
+
+        let jsonl = fs::read_to_string(dir.join("class-continent.jsonl"))?;
+        let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap())?;
+        assert!(first["continent"] == "AF");
+        assert!(first["bytes"] == 10);
+
+        let file = fs::File::open(dir.join("table.pickle"))?;
+        let key2data: BTreeMap<String, (String, f64)> =
+            serde_pickle::from_reader(io::BufReader::new(file))?;
+        assert!(key2data["Diurnal+SA"] == ("30".to_string(), 0.75));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
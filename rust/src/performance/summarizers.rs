@@ -1,3 +1,5 @@
+pub mod combinators;
 pub mod degradation;
 pub mod opportunity;
+pub mod registry;
 pub mod relationships;
@@ -0,0 +1,405 @@
+//! Summarizers built from other summarizers.
+//!
+//! Wrappers take any `TimeBinSummarizer` and compose its `prefix()`, so
+//! a new analysis can be written as, e.g., an opportunity summarizer
+//! restricted to one continent instead of a copy of the opportunity
+//! summarizer with an extra check.
+//!
+//! The registry exposes them as the `filter-*`, `and`, `or` and
+//! `inverted` types, whose summarizer parameters are other specs, e.g.,
+//! `filter-continent:inner=[minrtt50-opp:...],continent=SA`.
+//!
+//! Filters drop bins after the inner summarizer made its decision; they
+//! do not change which routes the inner summarizer compares.  This is
+//! why the relationship summarizers remain their own types instead of
+//! compositions: they compare the primary route against the first
+//! valid alternate whose peer type is in a bitmask, while opportunity
+//! summarizers compare it against the best alternate of any peer type,
+//! and `minrtt50-opp` also requires HD-ratio not to get worse.  An
+//! alternate peer type filter over `minrtt50-opp` therefore drops bins
+//! whose best alternate has another peer type, rather than comparing
+//! the primary route against the best route of the wanted peer type.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::performance::db;
+use crate::performance::perfstats::{
+    RouteSelection, SelectedRoutes, TimeBinStats, TimeBinSummarizer, TimeBinSummary,
+};
+
+/// Which bins a `FilteredSummarizer` keeps.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    /// Primary routes whose peer type is set in the bitmask, as in the
+    /// relationship summarizers.
+    PrimaryPeerTypes(u32),
+    /// Alternate routes whose peer type is set in the bitmask.
+    AlternatePeerTypes(u32),
+    Continent(db::ClientContinent),
+    Country([char; 2]),
+    /// Paths whose BGP prefix length is within the inclusive range.
+    PrefixLength {
+        min: u8,
+        max: u8,
+    },
+}
+
+impl Filter {
+    fn matches(&self, pathid: &db::PathId, stats: &TimeBinStats) -> bool {
+        match self {
+            Filter::PrimaryPeerTypes(bitmask) => {
+                bitmask & (1 << stats.primary_peer_type as u8) != 0
+            }
+            Filter::AlternatePeerTypes(bitmask) => {
+                bitmask & (1 << stats.alternate_peer_type as u8) != 0
+            }
+            Filter::Continent(continent) => pathid.client_continent == *continent,
+            Filter::Country(country) => pathid.client_country == *country,
+            Filter::PrefixLength {
+                min,
+                max,
+            } => (*min..=*max).contains(&pathid.bgp_ip_prefix.prefix_len()),
+        }
+    }
+
+    fn suffix(&self) -> String {
+        match self {
+            Filter::PrimaryPeerTypes(bitmask) => format!("--filter-primary-{}", bitmask),
+            Filter::AlternatePeerTypes(bitmask) => format!("--filter-alternate-{}", bitmask),
+            Filter::Continent(continent) => format!("--filter-continent-{:?}", continent),
+            Filter::Country(country) => {
+                format!("--filter-country-{}{}", country[0], country[1])
+            }
+            Filter::PrefixLength {
+                min,
+                max,
+            } => format!("--filter-prefix-len-{}-{}", min, max),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Filter::PrimaryPeerTypes(bitmask) => write!(f, "primary peer type bitmask {}", bitmask),
+            Filter::AlternatePeerTypes(bitmask) => {
+                write!(f, "alternate peer type bitmask {}", bitmask)
+            }
+            Filter::Continent(continent) => write!(f, "continent {:?}", continent),
+            Filter::Country(country) => write!(f, "country {}{}", country[0], country[1]),
+            Filter::PrefixLength {
+                min,
+                max,
+            } => write!(f, "prefix length {}..={}", min, max),
+        }
+    }
+}
+
+/// Keeps the bins of `inner` that match every filter; other bins are
+/// `NoRoute`.
+pub struct FilteredSummarizer {
+    inner: Arc<dyn TimeBinSummarizer>,
+    filters: Vec<Filter>,
+}
+
+impl FilteredSummarizer {
+    pub fn new(inner: Arc<dyn TimeBinSummarizer>, filters: Vec<Filter>) -> Self {
+        Self {
+            inner,
+            filters,
+        }
+    }
+
+    fn rejected_by(&self, pathid: &db::PathId, stats: &TimeBinStats) -> Option<&Filter> {
+        self.filters.iter().find(|filter| !filter.matches(pathid, stats))
+    }
+}
+
+impl TimeBinSummarizer for FilteredSummarizer {
+    fn summarize(&self, pathid: &db::PathId, bin: &db::TimeBin) -> TimeBinSummary {
+        match self.inner.summarize(pathid, bin) {
+            TimeBinSummary::Valid(stats) if self.rejected_by(pathid, &stats).is_some() => {
+                TimeBinSummary::NoRoute
+            }
+            summary => summary,
+        }
+    }
+    fn get_routes<'s: 'd, 'd>(
+        &'s self,
+        pathid: &db::PathId,
+        bin: &'d db::TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        self.inner.get_routes(pathid, bin, selection)
+    }
+    fn prefix(&self) -> String {
+        let suffixes: Vec<String> = self.filters.iter().map(Filter::suffix).collect();
+        format!("{}{}", self.inner.prefix(), suffixes.concat())
+    }
+    fn explain(&self, pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let mut lines: Vec<String> = self.inner.explain(pathid, bin);
+        if let TimeBinSummary::Valid(stats) = self.inner.summarize(pathid, bin) {
+            match self.rejected_by(pathid, &stats) {
+                Some(filter) => lines.push(format!("filter {}: rejected: NoRoute", filter)),
+                None => lines.push("filters: all match".to_string()),
+            }
+        }
+        lines
+    }
+}
+
+/// How a `CombinedSummarizer` joins the shift decisions of its two
+/// summarizers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Combination {
+    And,
+    Or,
+}
+
+impl Combination {
+    fn apply(self, left: bool, right: bool) -> bool {
+        match self {
+            Combination::And => left && right,
+            Combination::Or => left || right,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Combination::And => "and",
+            Combination::Or => "or",
+        }
+    }
+}
+
+/// Joins the `is_shifted` decisions of two summarizers on the same bin.
+///
+/// A bin is only valid if it is valid for both summarizers; otherwise
+/// the left summary is returned if it is not valid, else the right one.
+/// Statistics and routes of valid bins come from `left`.
+pub struct CombinedSummarizer {
+    left: Arc<dyn TimeBinSummarizer>,
+    right: Arc<dyn TimeBinSummarizer>,
+    combination: Combination,
+}
+
+impl CombinedSummarizer {
+    pub fn new(
+        left: Arc<dyn TimeBinSummarizer>,
+        right: Arc<dyn TimeBinSummarizer>,
+        combination: Combination,
+    ) -> Self {
+        Self {
+            left,
+            right,
+            combination,
+        }
+    }
+}
+
+impl TimeBinSummarizer for CombinedSummarizer {
+    fn summarize(&self, pathid: &db::PathId, bin: &db::TimeBin) -> TimeBinSummary {
+        match (self.left.summarize(pathid, bin), self.right.summarize(pathid, bin)) {
+            (TimeBinSummary::Valid(mut left), TimeBinSummary::Valid(right)) => {
+                left.is_shifted = self.combination.apply(left.is_shifted, right.is_shifted);
+                TimeBinSummary::Valid(left)
+            }
+            (TimeBinSummary::Valid(_), right) => right,
+            (left, _) => left,
+        }
+    }
+    fn get_routes<'s: 'd, 'd>(
+        &'s self,
+        pathid: &db::PathId,
+        bin: &'d db::TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        self.left.get_routes(pathid, bin, selection)
+    }
+    /// `--end` closes the combination, so filters wrapping it and
+    /// filters wrapping `right` have different prefixes.
+    fn prefix(&self) -> String {
+        format!("{}--{}--{}--end", self.left.prefix(), self.combination.name(), self.right.prefix())
+    }
+    fn explain(&self, pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let mut lines: Vec<String> = vec![format!("left: {}", self.left.prefix())];
+        lines.extend(self.left.explain(pathid, bin));
+        lines.push(format!("right: {}", self.right.prefix()));
+        lines.extend(self.right.explain(pathid, bin));
+        lines.push(format!(
+            "{} of left {} and right {}",
+            self.combination.name(),
+            self.left.summarize(pathid, bin).describe(),
+            self.right.summarize(pathid, bin).describe()
+        ));
+        lines
+    }
+}
+
+/// Swaps the routes `inner` compares, e.g., turning an opportunity
+/// summarizer into one that finds bins where the alternate is worse
+/// than the primary.
+///
+/// The difference is negated and a bin is shifted when the lower bound
+/// of the negated difference exceeds `min_diff`; the inner decision is
+/// ignored.  The recorded primary and alternate routes are swapped;
+/// baselines are kept.  Statistics only meaningful in the inner
+/// direction (Pareto outcomes, alternate counts, attribution) are
+/// dropped.
+pub struct InvertedSummarizer {
+    inner: Arc<dyn TimeBinSummarizer>,
+    min_diff: f32,
+}
+
+impl InvertedSummarizer {
+    pub fn new(inner: Arc<dyn TimeBinSummarizer>, min_diff: f32) -> Self {
+        Self {
+            inner,
+            min_diff,
+        }
+    }
+}
+
+impl TimeBinSummarizer for InvertedSummarizer {
+    fn summarize(&self, pathid: &db::PathId, bin: &db::TimeBin) -> TimeBinSummary {
+        match self.inner.summarize(pathid, bin) {
+            TimeBinSummary::Valid(stats) => TimeBinSummary::Valid(TimeBinStats {
+                diff_ci: -stats.diff_ci,
+                diff_ci_lb: -stats.diff_ci_ub,
+                diff_ci_ub: -stats.diff_ci_lb,
+                is_shifted: -stats.diff_ci_ub > self.min_diff,
+                primary_peer_type: stats.alternate_peer_type,
+                alternate_peer_type: stats.primary_peer_type,
+                selection: stats.selection.map(RouteSelection::swapped),
+                pareto: None,
+                alternates: None,
+                attribution: None,
+                ..stats
+            }),
+            summary => summary,
+        }
+    }
+    fn get_routes<'s: 'd, 'd>(
+        &'s self,
+        pathid: &db::PathId,
+        bin: &'d db::TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        self.inner.get_routes(pathid, bin, selection)
+    }
+    fn prefix(&self) -> String {
+        format!("{}--inverted--diff-thresh-{:0.2}", self.inner.prefix(), self.min_diff)
+    }
+    fn explain(&self, pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let mut lines: Vec<String> = self.inner.explain(pathid, bin);
+        if let TimeBinSummary::Valid(stats) = self.summarize(pathid, bin) {
+            lines.push(format!(
+                "inverted diff lower bound {:0.3} > {:0.3}: {}",
+                stats.diff_ci_lb, self.min_diff, stats.is_shifted
+            ));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::performance::summarizers::opportunity::MinRtt50ImprovementSummarizer;
+
+    fn minrtt50_opp(min_improv: f32) -> Arc<dyn TimeBinSummarizer> {
+        Arc::new(MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: min_improv,
+            max_minrtt50_diff_ci_halfwidth: 10.0,
+            max_hdratio50_diff_ci_halfwidth: 0.1,
+            compare_lower_bound: true,
+        })
+    }
+
+    fn is_shifted(summary: TimeBinSummary) -> Option<bool> {
+        match summary {
+            TimeBinSummary::Valid(stats) => Some(stats.is_shifted),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_filtered_summarizer() {
+        let pid = db::tests::make_path_id();
+        // ci_halfwidth = 2 * (2**2 + 2**2).sqrt() = 5.66
+        let timebin = db::TimeBin::mock_minrtt_p50(0, 60, 40, 4);
+
+        let keep = FilteredSummarizer::new(
+            minrtt50_opp(5.0),
+            vec![
+                Filter::Continent(pid.client_continent),
+                Filter::PrimaryPeerTypes(1 << db::PeerType::PeeringPrivate as u8),
+                Filter::AlternatePeerTypes(1 << db::PeerType::Transit as u8),
+            ],
+        );
+        assert!(is_shifted(keep.summarize(&pid, &timebin)) == Some(true));
+
+        let drop = FilteredSummarizer::new(
+            minrtt50_opp(5.0),
+            vec![Filter::AlternatePeerTypes(1 << db::PeerType::PeeringPaid as u8)],
+        );
+        assert!(drop.summarize(&pid, &timebin) == TimeBinSummary::NoRoute);
+        assert!(drop.prefix() == format!("{}--filter-alternate-4", minrtt50_opp(5.0).prefix()));
+
+        let prefix_len: u8 = pid.bgp_ip_prefix.prefix_len();
+        let drop = FilteredSummarizer::new(
+            minrtt50_opp(5.0),
+            vec![Filter::PrefixLength {
+                min: prefix_len + 1,
+                max: 128,
+            }],
+        );
+        assert!(drop.summarize(&pid, &timebin) == TimeBinSummary::NoRoute);
+    }
+
+    #[test]
+    fn test_combined_summarizer() {
+        let pid = db::tests::make_path_id();
+        // diff 20, ci_halfwidth 5.66, lower bound 14.34
+        let timebin = db::TimeBin::mock_minrtt_p50(0, 60, 40, 4);
+
+        let and = CombinedSummarizer::new(minrtt50_opp(5.0), minrtt50_opp(20.0), Combination::And);
+        assert!(is_shifted(and.summarize(&pid, &timebin)) == Some(false));
+        let or = CombinedSummarizer::new(minrtt50_opp(5.0), minrtt50_opp(20.0), Combination::Or);
+        assert!(is_shifted(or.summarize(&pid, &timebin)) == Some(true));
+        assert!(
+            or.prefix()
+                == format!(
+                    "{}--or--{}--end",
+                    minrtt50_opp(5.0).prefix(),
+                    minrtt50_opp(20.0).prefix()
+                )
+        );
+
+        let timebin = db::TimeBin::mock_minrtt_p50(0, 60, 40, 100);
+        assert!(or.summarize(&pid, &timebin) == TimeBinSummary::WideConfidenceInterval);
+    }
+
+    #[test]
+    fn test_inverted_summarizer() {
+        let pid = db::tests::make_path_id();
+        let inverted = InvertedSummarizer::new(minrtt50_opp(5.0), 5.0);
+
+        // The alternate is 20ms worse than the primary.
+        let timebin = db::TimeBin::mock_minrtt_p50(0, 40, 60, 4);
+        assert!(is_shifted(minrtt50_opp(5.0).summarize(&pid, &timebin)) == Some(false));
+        match inverted.summarize(&pid, &timebin) {
+            TimeBinSummary::Valid(stats) => {
+                assert!(stats.is_shifted);
+                assert!((stats.diff_ci - 20.0).abs() < 1e-6);
+                assert!(stats.primary_peer_type == db::PeerType::Transit);
+            }
+            _ => unreachable!(),
+        }
+
+        let timebin = db::TimeBin::mock_minrtt_p50(0, 60, 40, 4);
+        assert!(is_shifted(inverted.summarize(&pid, &timebin)) == Some(false));
+    }
+}
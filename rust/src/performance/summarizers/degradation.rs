@@ -1,10 +1,162 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::sync::Arc;
 
 use log::info;
 
-use crate::performance::db::{PathId, RouteInfo, TimeBin, DB};
-use crate::performance::perfstats::{TimeBinStats, TimeBinSummarizer, TimeBinSummary};
+use crate::performance::db::{MinRttPercentile, PathId, RouteInfo, TimeBin, DB};
+use crate::performance::explain::{
+    check_shift, check_width, describe_route, Metric, Selection, ANY_PEER_TYPE,
+};
+use crate::performance::perfstats::{
+    DegradationAttribution, RouteSelection, SelectedRoutes, TimeBinStats, TimeBinSummarizer,
+    TimeBinSummary,
+};
+
+/// Which bins a degradation summarizer chooses the baseline of a bin
+/// from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BaselineStrategy {
+    /// All bins of the path; one baseline per path.
+    Global,
+    /// Bins in the trailing window of this many days, up to and
+    /// including the bin compared against the baseline.
+    Trailing(u32),
+    /// Bins at the same time of day as the bin compared against the
+    /// baseline, over that day and this many previous days.
+    TimeOfDay(u32),
+    /// Bins whose primary route has the same `px_nexthops` as that of
+    /// the bin compared against the baseline.
+    Nexthops,
+}
+
+impl BaselineStrategy {
+    const SECONDS_PER_DAY: u64 = 86400;
+
+    /// Bins sharing a key share a baseline.
+    fn key(self, time: u64, primary: &RouteInfo) -> u64 {
+        match self {
+            BaselineStrategy::Global => 0,
+            BaselineStrategy::Trailing(_) | BaselineStrategy::TimeOfDay(_) => time,
+            BaselineStrategy::Nexthops => primary.px_nexthops,
+        }
+    }
+
+    /// The baseline for each key of a path's bins among the routes in
+    /// `valid` (keyed by bin time) that the strategy allows: the route
+    /// at `index(len)` of the allowed routes sorted stably by
+    /// `compare`.  Keys without allowed routes are left out.
+    fn baselines<C, I>(
+        self,
+        time2bin: &BTreeMap<u64, TimeBin>,
+        valid: &BTreeMap<u64, RouteInfo>,
+        compare: C,
+        index: I,
+    ) -> HashMap<u64, Box<RouteInfo>>
+    where
+        C: Fn(&RouteInfo, &RouteInfo) -> Ordering,
+        I: Fn(usize) -> usize,
+    {
+        let mut key2routes: HashMap<u64, Vec<RouteInfo>> = HashMap::new();
+        match self {
+            BaselineStrategy::Global => {
+                if !valid.is_empty() {
+                    key2routes.insert(0, valid.values().copied().collect());
+                }
+            }
+            BaselineStrategy::Nexthops => {
+                for rtinfo in valid.values() {
+                    key2routes.entry(rtinfo.px_nexthops).or_default().push(*rtinfo);
+                }
+            }
+            BaselineStrategy::Trailing(days) => {
+                return Self::trailing_baselines(days, time2bin, valid, compare, index);
+            }
+            BaselineStrategy::TimeOfDay(days) => {
+                for &time in time2bin.keys() {
+                    let routes: Vec<RouteInfo> = (0..=u64::from(days))
+                        .filter_map(|day| time.checked_sub(day * BaselineStrategy::SECONDS_PER_DAY))
+                        .filter_map(|t| valid.get(&t).copied())
+                        .collect();
+                    if !routes.is_empty() {
+                        key2routes.insert(time, routes);
+                    }
+                }
+            }
+        }
+        key2routes
+            .into_iter()
+            .map(|(key, mut routes)| {
+                routes.sort_by(&compare);
+                let i: usize = index(routes.len());
+                (key, Box::new(routes[i]))
+            })
+            .collect()
+    }
+
+    /// `baselines` for `Trailing(days)`.  The window slides over the
+    /// bins in time order, keeping its routes sorted, so each bin costs
+    /// a binary search per route entering or leaving the window rather
+    /// than a sort of the whole window.
+    fn trailing_baselines<C, I>(
+        days: u32,
+        time2bin: &BTreeMap<u64, TimeBin>,
+        valid: &BTreeMap<u64, RouteInfo>,
+        compare: C,
+        index: I,
+    ) -> HashMap<u64, Box<RouteInfo>>
+    where
+        C: Fn(&RouteInfo, &RouteInfo) -> Ordering,
+        I: Fn(usize) -> usize,
+    {
+        let window: u64 = u64::from(days) * BaselineStrategy::SECONDS_PER_DAY;
+        let position = |routes: &[(RouteInfo, u64)], rtinfo: &RouteInfo, time: u64| {
+            routes.binary_search_by(|(other, t)| compare(other, rtinfo).then(t.cmp(&time)))
+        };
+        let mut routes: Vec<(RouteInfo, u64)> = Vec::new();
+        let mut entering = valid.iter().peekable();
+        let mut leaving = valid.iter().peekable();
+        let mut time2baseroute: HashMap<u64, Box<RouteInfo>> = HashMap::new();
+        for &time in time2bin.keys() {
+            let start: u64 = (time + 1).saturating_sub(window);
+            while let Some((&t, rtinfo)) = entering.next_if(|(&t, _)| t <= time) {
+                let i: usize = position(&routes, rtinfo, t).unwrap_err();
+                routes.insert(i, (*rtinfo, t));
+            }
+            while let Some((&t, rtinfo)) = leaving.next_if(|(&t, _)| t < start) {
+                let i: usize = position(&routes, rtinfo, t).unwrap();
+                routes.remove(i);
+            }
+            if !routes.is_empty() {
+                time2baseroute.insert(time, Box::new(routes[index(routes.len())].0));
+            }
+        }
+        time2baseroute
+    }
+
+    /// Appended to summarizer prefixes; empty for `Global` so prefixes
+    /// of existing outputs do not change.
+    fn suffix(self) -> String {
+        match self {
+            BaselineStrategy::Global => String::new(),
+            BaselineStrategy::Trailing(days) => format!("--trailing-days-{}", days),
+            BaselineStrategy::TimeOfDay(days) => format!("--time-of-day-days-{}", days),
+            BaselineStrategy::Nexthops => "--per-nexthops".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for BaselineStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BaselineStrategy::Global => write!(f, "global"),
+            BaselineStrategy::Trailing(days) => write!(f, "trailing {} days", days),
+            BaselineStrategy::TimeOfDay(days) => write!(f, "same time of day over {} days", days),
+            BaselineStrategy::Nexthops => write!(f, "per primary px_nexthops"),
+        }
+    }
+}
 
 /// Summarize MinRTT degradation over time comparing primary routes.
 ///
@@ -13,6 +165,8 @@ use crate::performance::perfstats::{TimeBinStats, TimeBinSummarizer, TimeBinSumm
 /// dataset. Thresholds below control filters applied to the algorithm
 /// to find the best `TimeBin`.
 pub struct MinRtt50LowerBoundDegradationSummarizer {
+    /// The percentile of the valid `TimeBin`s chosen as the baseline.
+    baseline_percentile: f32,
     /// The minimum difference between the best `TimeBin` and other
     /// `TimeBin`s considered degradation.
     min_diff_degradation: f32,
@@ -32,10 +186,14 @@ pub struct MinRtt50LowerBoundDegradationSummarizer {
     /// comparisons having large CI halfwidths (and filtering due to the
     /// CI halfwidth threshold).
     max_minrtt50_ci_halfwidth: f32,
+    /// How the bins a baseline is chosen from depend on the bin
+    /// compared against it.
+    strategy: BaselineStrategy,
     /// This stores the primary `RouteInfo` for the best `TimeBin` for
-    /// each `PathId`, chosen based on the thresholds above. `PathId`s
-    /// without a valid best `TimeBin` are not included in the mapping.
-    pathid2baseroute: HashMap<Arc<PathId>, Box<RouteInfo>>,
+    /// each `PathId` and `BaselineStrategy::key`, chosen based on the
+    /// thresholds above. `PathId`s without a valid best `TimeBin` are
+    /// not included in the mapping.
+    pathid2baseroute: HashMap<Arc<PathId>, HashMap<u64, Box<RouteInfo>>>,
 }
 
 /// Summarize HD-ratio P50 degradation over time comparing primary routes.
@@ -45,6 +203,8 @@ pub struct MinRtt50LowerBoundDegradationSummarizer {
 /// dataset. Thresholds below control filters applied to the algorithm
 /// to find the best `TimeBin`.
 pub struct HdRatio50LowerBoundDegradationSummarizer {
+    /// The percentile of the valid `TimeBin`s chosen as the baseline.
+    baseline_percentile: f32,
     /// The minimum difference between the best `TimeBin` and other
     /// `TimeBin`s considered degradation.
     min_diff_degradation: f32,
@@ -64,6 +224,68 @@ pub struct HdRatio50LowerBoundDegradationSummarizer {
     /// comparisons having large CI halfwidths (and filtering due to the
     /// CI halfwidth threshold).
     max_hdratio50_ci_halfwidth: f32,
+    /// How the bins a baseline is chosen from depend on the bin
+    /// compared against it.
+    strategy: BaselineStrategy,
+    /// This stores the primary `RouteInfo` for the best `TimeBin` for
+    /// each `PathId` and `BaselineStrategy::key`, chosen based on the
+    /// thresholds above. `PathId`s without a valid best `TimeBin` are
+    /// not included in the mapping.
+    pathid2baseroute: HashMap<Arc<PathId>, HashMap<u64, Box<RouteInfo>>>,
+}
+
+/// Summarize MinRTT P50 degradation over time, comparing only identical
+/// primary routes.
+///
+/// We consider two primary routes identical if their `RouteInfo`
+/// instances have the same `px_nexthops`.  The baseline is chosen among
+/// bins whose primary route uses the `px_nexthops` of more than
+/// `min_frac_bins_using_bestbin_path` of the valid bins; bins whose
+/// primary route uses other nexthops have no route to compare against.
+pub struct MinRtt50LowerBoundDistinctPathsDegradationSummarizer {
+    /// The percentile of the valid `TimeBin`s chosen as the baseline.
+    baseline_percentile: f32,
+    /// The minimum difference between the best `TimeBin` and other
+    /// `TimeBin`s considered degradation.
+    min_diff_degradation: f32,
+    /// The maximum CI halfwidth of the performance difference between
+    /// the best `TimeBin` and other `TimeBin`s.
+    max_diff_ci_halfwidth: f32,
+    /// During initialization, `TimeBin`s whose primary route's MinRTT
+    /// P50 CI halfwidth is above this threshold will not be considered
+    /// for "best".
+    max_minrtt50_ci_halfwidth: f32,
+    /// If one `px_nexthops` value is used by a fraction of valid bins
+    /// higher than this threshold, the baseline is chosen among its
+    /// bins.  We assume this threshold to be at least 50% to avoid the
+    /// need to break ties among multiple `px_nexthops`.
+    min_frac_bins_using_bestbin_path: f32,
+    /// This stores the primary `RouteInfo` for the best `TimeBin` for
+    /// each `PathId`, chosen based on the thresholds above. `PathId`s
+    /// without a valid best `TimeBin` are not included in the mapping.
+    pathid2baseroute: HashMap<Arc<PathId>, Box<RouteInfo>>,
+}
+
+/// Summarize HD-ratio P50 degradation over time, comparing only
+/// identical primary routes, as
+/// `MinRtt50LowerBoundDistinctPathsDegradationSummarizer`.
+pub struct HdRatio50LowerBoundDistinctPathsDegradationSummarizer {
+    /// The percentile of the valid `TimeBin`s chosen as the baseline.
+    baseline_percentile: f32,
+    /// The minimum difference between the best `TimeBin` and other
+    /// `TimeBin`s considered degradation.
+    min_diff_degradation: f32,
+    /// The maximum CI halfwidth of the performance difference between
+    /// the best `TimeBin` and other `TimeBin`s.
+    max_diff_ci_halfwidth: f32,
+    /// During initialization, `TimeBin`s whose primary route's HD-ratio
+    /// P50 CI halfwidth is above this threshold will not be considered
+    /// for "best".
+    max_hdratio50_ci_halfwidth: f32,
+    /// If one `px_nexthops` value is used by a fraction of valid bins
+    /// higher than this threshold, the baseline is chosen among its
+    /// bins.  Must be at least 50%.
+    min_frac_bins_using_bestbin_path: f32,
     /// This stores the primary `RouteInfo` for the best `TimeBin` for
     /// each `PathId`, chosen based on the thresholds above. `PathId`s
     /// without a valid best `TimeBin` are not included in the mapping.
@@ -78,10 +300,706 @@ impl MinRtt50LowerBoundDegradationSummarizer {
         max_minrtt50_ci_halfwidth: f32,
         db: &DB,
     ) -> Self {
+        Self::with_strategy(
+            baseline_percentile,
+            min_diff_degradation,
+            max_diff_ci_halfwidth,
+            max_minrtt50_ci_halfwidth,
+            BaselineStrategy::Global,
+            db,
+        )
+    }
+
+    pub fn with_strategy(
+        baseline_percentile: f32,
+        min_diff_degradation: f32,
+        max_diff_ci_halfwidth: f32,
+        max_minrtt50_ci_halfwidth: f32,
+        strategy: BaselineStrategy,
+        db: &DB,
+    ) -> Self {
+        assert!(!matches!(
+            strategy,
+            BaselineStrategy::Trailing(0) | BaselineStrategy::TimeOfDay(0)
+        ));
+        let mut sum = Self {
+            baseline_percentile,
+            min_diff_degradation,
+            max_diff_ci_halfwidth,
+            max_minrtt50_ci_halfwidth,
+            strategy,
+            pathid2baseroute: HashMap::new(),
+        };
+        for (pathid, pinfo) in &db.pathid2info {
+            let mut valid: BTreeMap<u64, RouteInfo> = BTreeMap::new();
+            for (time, timebin) in pinfo.time2bin.iter() {
+                match timebin.get_primary_route_minrtt() {
+                    None => continue,
+                    Some(primary) => {
+                        if f32::from(primary.minrtt_ms_p50_ci_halfwidth)
+                            >= max_minrtt50_ci_halfwidth
+                        {
+                            continue;
+                        }
+                        valid.insert(*time, **primary);
+                    }
+                }
+            }
+            let baseroutes = strategy.baselines(
+                &pinfo.time2bin,
+                &valid,
+                RouteInfo::compare_median_minrtt,
+                |len| len - 1 - ((len - 1) as f32 * baseline_percentile).round() as usize,
+            );
+            if baseroutes.is_empty() {
+                continue;
+            }
+            sum.pathid2baseroute.insert(Arc::clone(pathid), baseroutes);
+        }
+        info!(
+            "MinRtt50LowerBoundDegradationSummarizer paths in={} out={}",
+            db.pathid2info.len(),
+            sum.pathid2baseroute.len()
+        );
+        sum
+    }
+}
+
+impl TimeBinSummarizer for MinRtt50LowerBoundDegradationSummarizer {
+    fn summarize(&self, pathid: &PathId, bin: &TimeBin) -> TimeBinSummary {
+        match (self.pathid2baseroute.get(pathid), bin.get_primary_route_minrtt()) {
+            (None, _) => TimeBinSummary::WideConfidenceInterval,
+            (_, None) => TimeBinSummary::NoRoute,
+            (Some(key2baseroute), Some(primary)) => {
+                match key2baseroute.get(&self.strategy.key(bin.time_bucket, primary)) {
+                    None => TimeBinSummary::WideConfidenceInterval,
+                    Some(bestroute) => {
+                        let (diff, halfwidth) =
+                            RouteInfo::minrtt_median_diff_ci(primary, bestroute);
+                        if halfwidth > self.max_diff_ci_halfwidth {
+                            TimeBinSummary::WideConfidenceInterval
+                        } else {
+                            TimeBinSummary::Valid(TimeBinStats {
+                                diff_ci: diff,
+                                diff_ci_lb: diff - halfwidth,
+                                diff_ci_ub: diff + halfwidth,
+                                is_shifted: diff - halfwidth > self.min_diff_degradation,
+                                primary_peer_type: primary.peer_type,
+                                alternate_peer_type: bestroute.peer_type,
+                                selection: RouteSelection::baseline(bin, primary),
+                                bitmask: 0,
+                                bytes: bin.bytes_acked_sum,
+                                ..Default::default()
+                            })
+                        }
+                    }
+                }
+            }
+        }
+    }
+    fn get_routes<'s: 'd, 'd>(
+        &'s self,
+        pathid: &PathId,
+        bin: &'d TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        let primary: &RouteInfo = selection.primary_route(bin)?;
+        let baseline: &RouteInfo =
+            self.pathid2baseroute.get(pathid)?.get(&self.strategy.key(bin.time_bucket, primary))?;
+        Some(SelectedRoutes::Baseline {
+            baseline,
+            primary,
+        })
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "minrtt50--deg--bound-true--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}--baseline-{:0.2}{}",
+            self.min_diff_degradation,
+            self.max_diff_ci_halfwidth,
+            self.max_minrtt50_ci_halfwidth,
+            self.baseline_percentile,
+            self.strategy.suffix(),
+        )
+    }
+    fn explain(&self, pathid: &PathId, bin: &TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_minrtt().as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "baseline: primary route of the baseline percentile bin",
+            metric: Metric::MinRtt,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: None,
+            primary,
+            alternate: None,
+        }
+        .describe(bin);
+        let key2baseroute = match self.pathid2baseroute.get(pathid) {
+            Some(key2baseroute) => key2baseroute,
+            None => {
+                lines.push(format!(
+                    "no baseline: no bin with primary MinRTT P50 CI halfwidth <= {:0.3}: WideConfidenceInterval",
+                    self.max_minrtt50_ci_halfwidth
+                ));
+                return lines;
+            }
+        };
+        let primary: &RouteInfo = match primary {
+            Some(primary) => primary,
+            None => return lines,
+        };
+        let baseroute: &RouteInfo =
+            match key2baseroute.get(&self.strategy.key(bin.time_bucket, primary)) {
+                Some(baseroute) => baseroute,
+                None => {
+                    lines.push(format!(
+                        "no {} baseline for this bin: WideConfidenceInterval",
+                        self.strategy
+                    ));
+                    return lines;
+                }
+            };
+        lines.push(format!(
+            "baseline percentile {:0.2} {} {}",
+            self.baseline_percentile,
+            self.strategy,
+            describe_route(baseroute)
+        ));
+        let (diff, halfwidth) = RouteInfo::minrtt_median_diff_ci(primary, baseroute);
+        lines.push(check_width(
+            "MinRTT P50 diff CI halfwidth",
+            halfwidth,
+            self.max_diff_ci_halfwidth,
+        ));
+        lines.push(check_shift(
+            "MinRTT P50 diff lower bound",
+            diff - halfwidth,
+            ">",
+            self.min_diff_degradation,
+            diff - halfwidth > self.min_diff_degradation,
+        ));
+        lines
+    }
+}
+
+impl HdRatio50LowerBoundDegradationSummarizer {
+    pub fn new(
+        baseline_percentile: f32,
+        min_diff_degradation: f32,
+        max_diff_ci_halfwidth: f32,
+        max_hdratio50_ci_halfwidth: f32,
+        db: &DB,
+    ) -> Self {
+        Self::with_strategy(
+            baseline_percentile,
+            min_diff_degradation,
+            max_diff_ci_halfwidth,
+            max_hdratio50_ci_halfwidth,
+            BaselineStrategy::Global,
+            db,
+        )
+    }
+
+    pub fn with_strategy(
+        baseline_percentile: f32,
+        min_diff_degradation: f32,
+        max_diff_ci_halfwidth: f32,
+        max_hdratio50_ci_halfwidth: f32,
+        strategy: BaselineStrategy,
+        db: &DB,
+    ) -> Self {
+        assert!(!matches!(
+            strategy,
+            BaselineStrategy::Trailing(0) | BaselineStrategy::TimeOfDay(0)
+        ));
+        let mut sum = Self {
+            baseline_percentile,
+            min_diff_degradation,
+            max_diff_ci_halfwidth,
+            max_hdratio50_ci_halfwidth,
+            strategy,
+            pathid2baseroute: HashMap::new(),
+        };
+        for (pathid, pinfo) in &db.pathid2info {
+            let mut valid: BTreeMap<u64, RouteInfo> = BTreeMap::new();
+            for (time, timebin) in pinfo.time2bin.iter() {
+                match timebin.get_primary_route_hdratio() {
+                    None => continue,
+                    Some(primary) => {
+                        if primary.hdratio_p50_ci_halfwidth >= max_hdratio50_ci_halfwidth {
+                            continue;
+                        }
+                        valid.insert(*time, **primary);
+                    }
+                }
+            }
+            let baseroutes = strategy.baselines(
+                &pinfo.time2bin,
+                &valid,
+                RouteInfo::compare_median_hdratio,
+                |len| ((len - 1) as f32 * baseline_percentile).round() as usize,
+            );
+            if baseroutes.is_empty() {
+                continue;
+            }
+            sum.pathid2baseroute.insert(Arc::clone(pathid), baseroutes);
+        }
+        info!(
+            "HdRatio50LowerBoundDegradationSummarizer paths in={} out={}",
+            db.pathid2info.len(),
+            sum.pathid2baseroute.len()
+        );
+        sum
+    }
+}
+
+impl TimeBinSummarizer for HdRatio50LowerBoundDegradationSummarizer {
+    fn summarize(&self, pathid: &PathId, bin: &TimeBin) -> TimeBinSummary {
+        match (self.pathid2baseroute.get(pathid), bin.get_primary_route_hdratio()) {
+            (None, _) => TimeBinSummary::WideConfidenceInterval,
+            (_, None) => TimeBinSummary::NoRoute,
+            (Some(key2baseroute), Some(primary)) => {
+                match key2baseroute.get(&self.strategy.key(bin.time_bucket, primary)) {
+                    None => TimeBinSummary::WideConfidenceInterval,
+                    Some(bestroute) => {
+                        let (diff, halfwidth) =
+                            RouteInfo::hdratio_median_diff_ci(bestroute, primary);
+                        if halfwidth > self.max_diff_ci_halfwidth {
+                            TimeBinSummary::WideConfidenceInterval
+                        } else {
+                            TimeBinSummary::Valid(TimeBinStats {
+                                bytes: bin.bytes_acked_sum,
+                                diff_ci: diff,
+                                diff_ci_lb: diff - halfwidth,
+                                diff_ci_ub: diff + halfwidth,
+                                primary_peer_type: primary.peer_type,
+                                alternate_peer_type: bestroute.peer_type,
+                                selection: RouteSelection::baseline(bin, primary),
+                                bitmask: 0,
+                                is_shifted: diff - halfwidth > self.min_diff_degradation,
+                                ..Default::default()
+                            })
+                        }
+                    }
+                }
+            }
+        }
+    }
+    fn get_routes<'s: 'd, 'd>(
+        &'s self,
+        pathid: &PathId,
+        bin: &'d TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        let primary: &RouteInfo = selection.primary_route(bin)?;
+        let baseline: &RouteInfo =
+            self.pathid2baseroute.get(pathid)?.get(&self.strategy.key(bin.time_bucket, primary))?;
+        Some(SelectedRoutes::Baseline {
+            baseline,
+            primary,
+        })
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "hdratio50--deg--bound-true--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}--baseline-{:0.2}{}",
+            self.min_diff_degradation,
+            self.max_diff_ci_halfwidth,
+            self.max_hdratio50_ci_halfwidth,
+            self.baseline_percentile,
+            self.strategy.suffix(),
+        )
+    }
+    fn explain(&self, pathid: &PathId, bin: &TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_hdratio().as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "baseline: primary route of the baseline percentile bin",
+            metric: Metric::HdRatio,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: None,
+            primary,
+            alternate: None,
+        }
+        .describe(bin);
+        let key2baseroute = match self.pathid2baseroute.get(pathid) {
+            Some(key2baseroute) => key2baseroute,
+            None => {
+                lines.push(format!(
+                    "no baseline: no bin with primary HD-ratio P50 CI halfwidth <= {:0.3}: WideConfidenceInterval",
+                    self.max_hdratio50_ci_halfwidth
+                ));
+                return lines;
+            }
+        };
+        let primary: &RouteInfo = match primary {
+            Some(primary) => primary,
+            None => return lines,
+        };
+        let baseroute: &RouteInfo =
+            match key2baseroute.get(&self.strategy.key(bin.time_bucket, primary)) {
+                Some(baseroute) => baseroute,
+                None => {
+                    lines.push(format!(
+                        "no {} baseline for this bin: WideConfidenceInterval",
+                        self.strategy
+                    ));
+                    return lines;
+                }
+            };
+        lines.push(format!(
+            "baseline percentile {:0.2} {} {}",
+            self.baseline_percentile,
+            self.strategy,
+            describe_route(baseroute)
+        ));
+        let (diff, halfwidth) = RouteInfo::hdratio_median_diff_ci(baseroute, primary);
+        lines.push(check_width(
+            "HD-ratio P50 diff CI halfwidth",
+            halfwidth,
+            self.max_diff_ci_halfwidth,
+        ));
+        lines.push(check_shift(
+            "HD-ratio P50 diff lower bound",
+            diff - halfwidth,
+            ">",
+            self.min_diff_degradation,
+            diff - halfwidth > self.min_diff_degradation,
+        ));
+        lines
+    }
+}
+
+/// As `MinRtt50LowerBoundDegradationSummarizer` on another MinRTT
+/// percentile, with a global baseline.  Primary routes without the
+/// percentile are not valid.
+pub struct MinRttPercentileLowerBoundDegradationSummarizer {
+    percentile: MinRttPercentile,
+    baseline_percentile: f32,
+    min_diff_degradation: f32,
+    max_diff_ci_halfwidth: f32,
+    /// As `max_minrtt50_ci_halfwidth` on the CI of `percentile`.
+    max_ci_halfwidth: f32,
+    pathid2baseroute: HashMap<Arc<PathId>, Box<RouteInfo>>,
+}
+
+impl MinRttPercentileLowerBoundDegradationSummarizer {
+    pub fn new(
+        percentile: MinRttPercentile,
+        baseline_percentile: f32,
+        min_diff_degradation: f32,
+        max_diff_ci_halfwidth: f32,
+        max_ci_halfwidth: f32,
+        db: &DB,
+    ) -> Self {
+        let mut sum = Self {
+            percentile,
+            baseline_percentile,
+            min_diff_degradation,
+            max_diff_ci_halfwidth,
+            max_ci_halfwidth,
+            pathid2baseroute: HashMap::new(),
+        };
+        for (pathid, pinfo) in &db.pathid2info {
+            let mut routes: Vec<RouteInfo> = Vec::new();
+            for timebin in pinfo.time2bin.values() {
+                if let Some(primary) = sum.primary(timebin) {
+                    let (_, halfwidth) = primary.minrtt_percentile(percentile).unwrap();
+                    if f32::from(halfwidth) < max_ci_halfwidth {
+                        routes.push(*primary);
+                    }
+                }
+            }
+            if routes.is_empty() {
+                continue;
+            }
+            routes.sort_by(|rt1, rt2| RouteInfo::compare_minrtt_percentile(rt1, rt2, percentile));
+            let i: usize = ((routes.len() - 1) as f32 * baseline_percentile).round() as usize;
+            let baseroute: RouteInfo = routes[routes.len() - 1 - i];
+            sum.pathid2baseroute.insert(Arc::clone(pathid), Box::new(baseroute));
+        }
+        info!(
+            "MinRttPercentileLowerBoundDegradationSummarizer P{} paths in={} out={}",
+            percentile.value(),
+            db.pathid2info.len(),
+            sum.pathid2baseroute.len()
+        );
+        sum
+    }
+
+    fn primary<'b>(&self, bin: &'b TimeBin) -> Option<&'b RouteInfo> {
+        let percentile = self.percentile;
+        bin.get_primary_route(|rtinfo| RouteInfo::minrtt_percentile_valid(rtinfo, percentile))
+            .as_deref()
+    }
+}
+
+impl TimeBinSummarizer for MinRttPercentileLowerBoundDegradationSummarizer {
+    fn summarize(&self, pathid: &PathId, bin: &TimeBin) -> TimeBinSummary {
+        match (self.pathid2baseroute.get(pathid), self.primary(bin)) {
+            (None, _) => TimeBinSummary::WideConfidenceInterval,
+            (_, None) => TimeBinSummary::NoRoute,
+            (Some(bestroute), Some(primary)) => {
+                let (diff, halfwidth) =
+                    RouteInfo::minrtt_percentile_diff_ci(primary, bestroute, self.percentile);
+                if halfwidth > self.max_diff_ci_halfwidth {
+                    TimeBinSummary::WideConfidenceInterval
+                } else {
+                    TimeBinSummary::Valid(TimeBinStats {
+                        diff_ci: diff,
+                        diff_ci_lb: diff - halfwidth,
+                        diff_ci_ub: diff + halfwidth,
+                        is_shifted: diff - halfwidth > self.min_diff_degradation,
+                        primary_peer_type: primary.peer_type,
+                        alternate_peer_type: bestroute.peer_type,
+                        selection: RouteSelection::baseline(bin, primary),
+                        bitmask: 0,
+                        bytes: bin.bytes_acked_sum,
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+    }
+    fn get_routes<'s: 'd, 'd>(
+        &'s self,
+        pathid: &PathId,
+        bin: &'d TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        Some(SelectedRoutes::Baseline {
+            baseline: self.pathid2baseroute.get(pathid)?,
+            primary: selection.primary_route(bin)?,
+        })
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "minrtt-p{}--deg--bound-true--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}--baseline-{:0.2}",
+            self.percentile.value(),
+            self.min_diff_degradation,
+            self.max_diff_ci_halfwidth,
+            self.max_ci_halfwidth,
+            self.baseline_percentile,
+        )
+    }
+    fn explain(&self, pathid: &PathId, bin: &TimeBin) -> Vec<String> {
+        let primary = self.primary(bin);
+        let mut lines: Vec<String> = Selection {
+            rule: "baseline: primary route of the baseline percentile bin",
+            metric: Metric::MinRtt,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: None,
+            primary,
+            alternate: None,
+        }
+        .describe(bin);
+        let name: String = format!("MinRTT P{}", self.percentile.value());
+        let baseroute: &RouteInfo = match self.pathid2baseroute.get(pathid) {
+            Some(baseroute) => baseroute,
+            None => {
+                lines.push(format!(
+                    "no baseline: no bin with primary {} CI halfwidth <= {:0.3}: WideConfidenceInterval",
+                    name, self.max_ci_halfwidth
+                ));
+                return lines;
+            }
+        };
+        lines.push(format!(
+            "baseline percentile {:0.2} {}",
+            self.baseline_percentile,
+            describe_route(baseroute)
+        ));
+        let primary: &RouteInfo = match primary {
+            Some(primary) => primary,
+            None => return lines,
+        };
+        let (diff, halfwidth) =
+            RouteInfo::minrtt_percentile_diff_ci(primary, baseroute, self.percentile);
+        lines.push(check_width(
+            &format!("{} diff CI halfwidth", name),
+            halfwidth,
+            self.max_diff_ci_halfwidth,
+        ));
+        lines.push(check_shift(
+            &format!("{} diff lower bound", name),
+            diff - halfwidth,
+            ">",
+            self.min_diff_degradation,
+            diff - halfwidth > self.min_diff_degradation,
+        ));
+        lines
+    }
+}
+
+/// As `HdRatio50LowerBoundDegradationSummarizer` on mean HD-ratio, with
+/// a global baseline and Welch CIs of the difference.
+pub struct HdRatioMeanLowerBoundDegradationSummarizer {
+    baseline_percentile: f32,
+    min_diff_degradation: f32,
+    max_diff_ci_halfwidth: f32,
+    /// As `max_hdratio50_ci_halfwidth` on the CI of the mean.
+    max_hdratio_ci_halfwidth: f32,
+    pathid2baseroute: HashMap<Arc<PathId>, Box<RouteInfo>>,
+}
+
+impl HdRatioMeanLowerBoundDegradationSummarizer {
+    const METRIC: DegradationMetric = DegradationMetric::HdRatioMean;
+
+    pub fn new(
+        baseline_percentile: f32,
+        min_diff_degradation: f32,
+        max_diff_ci_halfwidth: f32,
+        max_hdratio_ci_halfwidth: f32,
+        db: &DB,
+    ) -> Self {
+        let mut sum = Self {
+            baseline_percentile,
+            min_diff_degradation,
+            max_diff_ci_halfwidth,
+            max_hdratio_ci_halfwidth,
+            pathid2baseroute: HashMap::new(),
+        };
+        for (pathid, pinfo) in &db.pathid2info {
+            let routes: Vec<RouteInfo> = pinfo
+                .time2bin
+                .values()
+                .filter_map(|timebin| timebin.get_primary_route_hdratio().as_deref())
+                .filter(|primary| Self::METRIC.ci_halfwidth(primary) < max_hdratio_ci_halfwidth)
+                .copied()
+                .collect();
+            if routes.is_empty() {
+                continue;
+            }
+            let baseroute: RouteInfo = Self::METRIC.pick(routes, baseline_percentile);
+            sum.pathid2baseroute.insert(Arc::clone(pathid), Box::new(baseroute));
+        }
+        info!(
+            "HdRatioMeanLowerBoundDegradationSummarizer paths in={} out={}",
+            db.pathid2info.len(),
+            sum.pathid2baseroute.len()
+        );
+        sum
+    }
+}
+
+impl TimeBinSummarizer for HdRatioMeanLowerBoundDegradationSummarizer {
+    fn summarize(&self, pathid: &PathId, bin: &TimeBin) -> TimeBinSummary {
+        match (self.pathid2baseroute.get(pathid), bin.get_primary_route_hdratio()) {
+            (None, _) => TimeBinSummary::WideConfidenceInterval,
+            (_, None) => TimeBinSummary::NoRoute,
+            (Some(bestroute), Some(primary)) => {
+                let (diff, halfwidth) = Self::METRIC.diff_ci(primary, bestroute);
+                if halfwidth > self.max_diff_ci_halfwidth {
+                    TimeBinSummary::WideConfidenceInterval
+                } else {
+                    TimeBinSummary::Valid(TimeBinStats {
+                        bytes: bin.bytes_acked_sum,
+                        diff_ci: diff,
+                        diff_ci_lb: diff - halfwidth,
+                        diff_ci_ub: diff + halfwidth,
+                        primary_peer_type: primary.peer_type,
+                        alternate_peer_type: bestroute.peer_type,
+                        selection: RouteSelection::baseline(bin, primary),
+                        bitmask: 0,
+                        is_shifted: diff - halfwidth > self.min_diff_degradation,
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+    }
+    fn get_routes<'s: 'd, 'd>(
+        &'s self,
+        pathid: &PathId,
+        bin: &'d TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        Some(SelectedRoutes::Baseline {
+            baseline: self.pathid2baseroute.get(pathid)?,
+            primary: selection.primary_route(bin)?,
+        })
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "hdratio--deg--bound-true--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}--baseline-{:0.2}",
+            self.min_diff_degradation,
+            self.max_diff_ci_halfwidth,
+            self.max_hdratio_ci_halfwidth,
+            self.baseline_percentile,
+        )
+    }
+    fn explain(&self, pathid: &PathId, bin: &TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_hdratio().as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "baseline: primary route of the baseline percentile bin",
+            metric: Metric::HdRatio,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: None,
+            primary,
+            alternate: None,
+        }
+        .describe(bin);
+        let baseroute: &RouteInfo = match self.pathid2baseroute.get(pathid) {
+            Some(baseroute) => baseroute,
+            None => {
+                lines.push(format!(
+                    "no baseline: no bin with primary HD-ratio mean CI halfwidth <= {:0.3}: WideConfidenceInterval",
+                    self.max_hdratio_ci_halfwidth
+                ));
+                return lines;
+            }
+        };
+        lines.push(format!(
+            "baseline percentile {:0.2} {}",
+            self.baseline_percentile,
+            describe_route(baseroute)
+        ));
+        let primary: &RouteInfo = match primary {
+            Some(primary) => primary,
+            None => return lines,
+        };
+        let (diff, halfwidth) = Self::METRIC.diff_ci(primary, baseroute);
+        lines.push(check_width(
+            "HD-ratio mean diff CI halfwidth",
+            halfwidth,
+            self.max_diff_ci_halfwidth,
+        ));
+        lines.push(check_shift(
+            "HD-ratio mean diff lower bound",
+            diff - halfwidth,
+            ">",
+            self.min_diff_degradation,
+            diff - halfwidth > self.min_diff_degradation,
+        ));
+        lines
+    }
+}
+
+/// The `px_nexthops` used by the primary route of more than `min_frac`
+/// of `valid`, if any.
+fn dominant_nexthops(valid: &[RouteInfo], min_frac: f32) -> Option<u64> {
+    let mut id2cnt: HashMap<u64, u32> = HashMap::new();
+    for rtinfo in valid.iter() {
+        *id2cnt.entry(rtinfo.px_nexthops).or_insert(0) += 1;
+    }
+    id2cnt
+        .into_iter()
+        .find(|(_, cnt)| (*cnt as f32) / (valid.len() as f32) > min_frac)
+        .map(|(id, _)| id)
+}
+
+impl MinRtt50LowerBoundDistinctPathsDegradationSummarizer {
+    pub fn new(
+        baseline_percentile: f32,
+        min_diff_degradation: f32,
+        max_diff_ci_halfwidth: f32,
+        max_minrtt50_ci_halfwidth: f32,
+        min_frac_bins_using_bestbin_path: f32,
+        db: &DB,
+    ) -> Self {
+        assert!(min_frac_bins_using_bestbin_path >= 0.5);
         let mut sum = Self {
+            baseline_percentile,
             min_diff_degradation,
             max_diff_ci_halfwidth,
             max_minrtt50_ci_halfwidth,
+            min_frac_bins_using_bestbin_path,
             pathid2baseroute: HashMap::new(),
         };
         for (pathid, pinfo) in &db.pathid2info {
@@ -99,15 +1017,17 @@ impl MinRtt50LowerBoundDegradationSummarizer {
                     }
                 }
             }
-            if valid.is_empty() {
-                continue;
-            }
+            let nexthops: u64 = match dominant_nexthops(&valid, min_frac_bins_using_bestbin_path) {
+                None => continue,
+                Some(nexthops) => nexthops,
+            };
+            valid.retain(|rtinfo| rtinfo.px_nexthops == nexthops);
             valid.sort_by(RouteInfo::compare_median_minrtt);
             let i: usize = ((valid.len() - 1) as f32 * baseline_percentile).round() as usize;
-            sum.pathid2baseroute.insert(Arc::clone(&pathid), Box::new(valid[valid.len() - 1 - i]));
+            sum.pathid2baseroute.insert(Arc::clone(pathid), Box::new(valid[valid.len() - 1 - i]));
         }
         info!(
-            "MinRtt50LowerBoundDegradationSummarizer paths in={} out={}",
+            "MinRtt50LowerBoundDistinctPathsDegradationSummarizer paths in={} out={}",
             db.pathid2info.len(),
             sum.pathid2baseroute.len()
         );
@@ -115,11 +1035,14 @@ impl MinRtt50LowerBoundDegradationSummarizer {
     }
 }
 
-impl TimeBinSummarizer for MinRtt50LowerBoundDegradationSummarizer {
+impl TimeBinSummarizer for MinRtt50LowerBoundDistinctPathsDegradationSummarizer {
     fn summarize(&self, pathid: &PathId, bin: &TimeBin) -> TimeBinSummary {
         match (self.pathid2baseroute.get(pathid), bin.get_primary_route_minrtt()) {
             (None, _) => TimeBinSummary::WideConfidenceInterval,
             (_, None) => TimeBinSummary::NoRoute,
+            (Some(bestroute), Some(primary)) if primary.px_nexthops != bestroute.px_nexthops => {
+                TimeBinSummary::NoRoute
+            }
             (Some(bestroute), Some(primary)) => {
                 let (diff, halfwidth) = RouteInfo::minrtt_median_diff_ci(primary, bestroute);
                 if halfwidth > self.max_diff_ci_halfwidth {
@@ -127,12 +1050,15 @@ impl TimeBinSummarizer for MinRtt50LowerBoundDegradationSummarizer {
                 } else {
                     TimeBinSummary::Valid(TimeBinStats {
                         diff_ci: diff,
-                        diff_ci_halfwidth: halfwidth,
+                        diff_ci_lb: diff - halfwidth,
+                        diff_ci_ub: diff + halfwidth,
                         is_shifted: diff - halfwidth > self.min_diff_degradation,
                         primary_peer_type: primary.peer_type,
                         alternate_peer_type: bestroute.peer_type,
+                        selection: RouteSelection::baseline(bin, primary),
                         bitmask: 0,
                         bytes: bin.bytes_acked_sum,
+                        ..Default::default()
                     })
                 }
             }
@@ -141,105 +1067,571 @@ impl TimeBinSummarizer for MinRtt50LowerBoundDegradationSummarizer {
     fn get_routes<'s: 'd, 'd>(
         &'s self,
         pathid: &PathId,
-        time: u64,
-        db: &'d DB,
-    ) -> (&'d RouteInfo, &'d RouteInfo) {
-        (
-            &self.pathid2baseroute[pathid],
-            &db.pathid2info[pathid].time2bin[&time].get_primary_route_minrtt().as_ref().unwrap(),
+        bin: &'d TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        let primary: &RouteInfo = selection.primary_route(bin)?;
+        let baseline: &RouteInfo = self.pathid2baseroute.get(pathid)?;
+        Some(SelectedRoutes::Baseline {
+            baseline,
+            primary,
+        })
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "minrtt50--deg-distinct--bound-true--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}--baseline-{:0.2}--min-frac-{:0.2}",
+            self.min_diff_degradation,
+            self.max_diff_ci_halfwidth,
+            self.max_minrtt50_ci_halfwidth,
+            self.baseline_percentile,
+            self.min_frac_bins_using_bestbin_path,
         )
     }
+    fn explain(&self, pathid: &PathId, bin: &TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_minrtt().as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "baseline: primary route of the baseline percentile bin using the dominant nexthops",
+            metric: Metric::MinRtt,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: None,
+            primary,
+            alternate: None,
+        }
+        .describe(bin);
+        let baseroute: &RouteInfo = match self.pathid2baseroute.get(pathid) {
+            Some(baseroute) => baseroute,
+            None => {
+                lines.push(format!(
+                    "no baseline: no nexthops used by > {:0.2} of bins with primary MinRTT P50 CI halfwidth <= {:0.3}: WideConfidenceInterval",
+                    self.min_frac_bins_using_bestbin_path, self.max_minrtt50_ci_halfwidth
+                ));
+                return lines;
+            }
+        };
+        lines.push(format!(
+            "baseline percentile {:0.2} {}",
+            self.baseline_percentile,
+            describe_route(baseroute)
+        ));
+        if let Some(primary) = primary {
+            if primary.px_nexthops != baseroute.px_nexthops {
+                lines.push("primary nexthops differ from the baseline: NoRoute".to_string());
+                return lines;
+            }
+            let (diff, halfwidth) = RouteInfo::minrtt_median_diff_ci(primary, baseroute);
+            lines.push(check_width(
+                "MinRTT P50 diff CI halfwidth",
+                halfwidth,
+                self.max_diff_ci_halfwidth,
+            ));
+            lines.push(check_shift(
+                "MinRTT P50 diff lower bound",
+                diff - halfwidth,
+                ">",
+                self.min_diff_degradation,
+                diff - halfwidth > self.min_diff_degradation,
+            ));
+        }
+        lines
+    }
+}
+
+impl HdRatio50LowerBoundDistinctPathsDegradationSummarizer {
+    pub fn new(
+        baseline_percentile: f32,
+        min_diff_degradation: f32,
+        max_diff_ci_halfwidth: f32,
+        max_hdratio50_ci_halfwidth: f32,
+        min_frac_bins_using_bestbin_path: f32,
+        db: &DB,
+    ) -> Self {
+        assert!(min_frac_bins_using_bestbin_path >= 0.5);
+        let mut sum = Self {
+            baseline_percentile,
+            min_diff_degradation,
+            max_diff_ci_halfwidth,
+            max_hdratio50_ci_halfwidth,
+            min_frac_bins_using_bestbin_path,
+            pathid2baseroute: HashMap::new(),
+        };
+        for (pathid, pinfo) in &db.pathid2info {
+            let mut valid: Vec<RouteInfo> = Vec::default();
+            for timebin in pinfo.time2bin.values() {
+                match timebin.get_primary_route_hdratio() {
+                    None => continue,
+                    Some(primary) => {
+                        if primary.hdratio_p50_ci_halfwidth >= max_hdratio50_ci_halfwidth {
+                            continue;
+                        }
+                        valid.push(**primary);
+                    }
+                }
+            }
+            let nexthops: u64 = match dominant_nexthops(&valid, min_frac_bins_using_bestbin_path) {
+                None => continue,
+                Some(nexthops) => nexthops,
+            };
+            valid.retain(|rtinfo| rtinfo.px_nexthops == nexthops);
+            valid.sort_by(RouteInfo::compare_median_hdratio);
+            let i: usize = ((valid.len() - 1) as f32 * baseline_percentile).round() as usize;
+            sum.pathid2baseroute.insert(Arc::clone(pathid), Box::new(valid[i]));
+        }
+        info!(
+            "HdRatio50LowerBoundDistinctPathsDegradationSummarizer paths in={} out={}",
+            db.pathid2info.len(),
+            sum.pathid2baseroute.len()
+        );
+        sum
+    }
+}
+
+impl TimeBinSummarizer for HdRatio50LowerBoundDistinctPathsDegradationSummarizer {
+    fn summarize(&self, pathid: &PathId, bin: &TimeBin) -> TimeBinSummary {
+        match (self.pathid2baseroute.get(pathid), bin.get_primary_route_hdratio()) {
+            (None, _) => TimeBinSummary::WideConfidenceInterval,
+            (_, None) => TimeBinSummary::NoRoute,
+            (Some(bestroute), Some(primary)) if primary.px_nexthops != bestroute.px_nexthops => {
+                TimeBinSummary::NoRoute
+            }
+            (Some(bestroute), Some(primary)) => {
+                let (diff, halfwidth) = RouteInfo::hdratio_median_diff_ci(bestroute, primary);
+                if halfwidth > self.max_diff_ci_halfwidth {
+                    TimeBinSummary::WideConfidenceInterval
+                } else {
+                    TimeBinSummary::Valid(TimeBinStats {
+                        bytes: bin.bytes_acked_sum,
+                        diff_ci: diff,
+                        diff_ci_lb: diff - halfwidth,
+                        diff_ci_ub: diff + halfwidth,
+                        primary_peer_type: primary.peer_type,
+                        alternate_peer_type: bestroute.peer_type,
+                        selection: RouteSelection::baseline(bin, primary),
+                        bitmask: 0,
+                        is_shifted: diff - halfwidth > self.min_diff_degradation,
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+    }
+    fn get_routes<'s: 'd, 'd>(
+        &'s self,
+        pathid: &PathId,
+        bin: &'d TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        let primary: &RouteInfo = selection.primary_route(bin)?;
+        let baseline: &RouteInfo = self.pathid2baseroute.get(pathid)?;
+        Some(SelectedRoutes::Baseline {
+            baseline,
+            primary,
+        })
+    }
     fn prefix(&self) -> String {
         format!(
-            "minrtt50--deg--bound-true--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}",
-            self.min_diff_degradation, self.max_diff_ci_halfwidth, self.max_minrtt50_ci_halfwidth,
+            "hdratio50--deg-distinct--bound-true--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}--baseline-{:0.2}--min-frac-{:0.2}",
+            self.min_diff_degradation,
+            self.max_diff_ci_halfwidth,
+            self.max_hdratio50_ci_halfwidth,
+            self.baseline_percentile,
+            self.min_frac_bins_using_bestbin_path,
         )
     }
+    fn explain(&self, pathid: &PathId, bin: &TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_hdratio().as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "baseline: primary route of the baseline percentile bin using the dominant nexthops",
+            metric: Metric::HdRatio,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: None,
+            primary,
+            alternate: None,
+        }
+        .describe(bin);
+        let baseroute: &RouteInfo = match self.pathid2baseroute.get(pathid) {
+            Some(baseroute) => baseroute,
+            None => {
+                lines.push(format!(
+                    "no baseline: no nexthops used by > {:0.2} of bins with primary HD-ratio P50 CI halfwidth <= {:0.3}: WideConfidenceInterval",
+                    self.min_frac_bins_using_bestbin_path, self.max_hdratio50_ci_halfwidth
+                ));
+                return lines;
+            }
+        };
+        lines.push(format!(
+            "baseline percentile {:0.2} {}",
+            self.baseline_percentile,
+            describe_route(baseroute)
+        ));
+        if let Some(primary) = primary {
+            if primary.px_nexthops != baseroute.px_nexthops {
+                lines.push("primary nexthops differ from the baseline: NoRoute".to_string());
+                return lines;
+            }
+            let (diff, halfwidth) = RouteInfo::hdratio_median_diff_ci(baseroute, primary);
+            lines.push(check_width(
+                "HD-ratio P50 diff CI halfwidth",
+                halfwidth,
+                self.max_diff_ci_halfwidth,
+            ));
+            lines.push(check_shift(
+                "HD-ratio P50 diff lower bound",
+                diff - halfwidth,
+                ">",
+                self.min_diff_degradation,
+                diff - halfwidth > self.min_diff_degradation,
+            ));
+        }
+        lines
+    }
+}
+
+/// The metric a `DegradationAttributionSummarizer` compares routes on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DegradationMetric {
+    MinRtt50,
+    HdRatio50,
+    HdRatioMean,
+}
+
+impl DegradationMetric {
+    fn valid(self, rtinfo: &RouteInfo) -> bool {
+        match self {
+            DegradationMetric::MinRtt50 => RouteInfo::minrtt_valid(rtinfo),
+            DegradationMetric::HdRatio50 | DegradationMetric::HdRatioMean => {
+                RouteInfo::hdratio_valid(rtinfo)
+            }
+        }
+    }
+
+    fn ci_halfwidth(self, rtinfo: &RouteInfo) -> f32 {
+        match self {
+            DegradationMetric::MinRtt50 => f32::from(rtinfo.minrtt_ms_p50_ci_halfwidth),
+            DegradationMetric::HdRatio50 => rtinfo.hdratio_p50_ci_halfwidth,
+            DegradationMetric::HdRatioMean => rtinfo.hdratio_mean_ci_halfwidth(),
+        }
+    }
+
+    /// Degradation of `rtinfo` relative to `baseroute` and its CI
+    /// halfwidth; positive values are worse for both metrics.
+    fn diff_ci(self, rtinfo: &RouteInfo, baseroute: &RouteInfo) -> (f32, f32) {
+        match self {
+            DegradationMetric::MinRtt50 => RouteInfo::minrtt_median_diff_ci(rtinfo, baseroute),
+            DegradationMetric::HdRatio50 => RouteInfo::hdratio_median_diff_ci(baseroute, rtinfo),
+            DegradationMetric::HdRatioMean => RouteInfo::hdratio_mean_diff_ci(baseroute, rtinfo),
+        }
+    }
+
+    /// The route at `percentile` of the metric's values in `routes`,
+    /// as for `baseline_percentile` in the other degradation
+    /// summarizers: percentile 0 is the lowest MinRTT (the best route)
+    /// for `MinRtt50` and the lowest HD-ratio (the worst route) for
+    /// `HdRatio50` and `HdRatioMean`.
+    fn pick(self, mut routes: Vec<RouteInfo>, percentile: f32) -> RouteInfo {
+        let i: usize = ((routes.len() - 1) as f32 * percentile).round() as usize;
+        match self {
+            DegradationMetric::MinRtt50 => {
+                routes.sort_by(RouteInfo::compare_median_minrtt);
+                routes[routes.len() - 1 - i]
+            }
+            DegradationMetric::HdRatio50 => {
+                routes.sort_by(RouteInfo::compare_median_hdratio);
+                routes[i]
+            }
+            DegradationMetric::HdRatioMean => {
+                routes.sort_by(RouteInfo::compare_mean_hdratio);
+                routes[i]
+            }
+        }
+    }
+
+    fn explain_metric(self) -> Metric {
+        match self {
+            DegradationMetric::MinRtt50 => Metric::MinRtt,
+            DegradationMetric::HdRatio50 | DegradationMetric::HdRatioMean => Metric::HdRatio,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            DegradationMetric::MinRtt50 => "MinRTT P50",
+            DegradationMetric::HdRatio50 => "HD-ratio P50",
+            DegradationMetric::HdRatioMean => "HD-ratio mean",
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            DegradationMetric::MinRtt50 => "minrtt50",
+            DegradationMetric::HdRatio50 => "hdratio50",
+            DegradationMetric::HdRatioMean => "hdratio",
+        }
+    }
 }
 
-impl HdRatio50LowerBoundDegradationSummarizer {
+/// Attribute degradation of a bin to the primary route's path or to
+/// all routes of the client.
+///
+/// Every route, primary or alternate, is compared against its own
+/// baseline: the route at the baseline percentile among all bins where
+/// a route with the same `px_nexthops` was valid.  A bin is summarized
+/// as for `MinRtt50LowerBoundDegradationSummarizer`, and bins with at
+/// least one comparable alternate additionally get a
+/// `DegradationAttribution`.  If the primary degrades along with all
+/// alternates the problem is likely common to the client (e.g., its
+/// access network); if an alternate is healthy, the problem is likely
+/// specific to the primary's path.
+pub struct DegradationAttributionSummarizer {
+    metric: DegradationMetric,
+    /// The percentile of the valid routes chosen as the baseline.
+    baseline_percentile: f32,
+    /// The minimum difference between a route and its baseline
+    /// considered degradation.
+    min_diff_degradation: f32,
+    /// The maximum CI halfwidth of the difference between a route and
+    /// its baseline; routes whose comparisons are wider are ignored.
+    max_diff_ci_halfwidth: f32,
+    /// During initialization, routes whose CI halfwidth is above this
+    /// threshold will not be considered for baselines.
+    max_ci_halfwidth: f32,
+    /// The baseline of each `px_nexthops` for each `PathId`.  `PathId`s
+    /// without any valid route are not included in the mapping.
+    pathid2baseroutes: HashMap<Arc<PathId>, HashMap<u64, Box<RouteInfo>>>,
+}
+
+/// A route of a bin compared against its baseline.
+struct RouteComparison<'a> {
+    route: &'a RouteInfo,
+    baseroute: &'a RouteInfo,
+    diff: f32,
+    halfwidth: f32,
+}
+
+impl RouteComparison<'_> {
+    fn degraded(&self, min_diff_degradation: f32) -> bool {
+        self.diff - self.halfwidth > min_diff_degradation
+    }
+}
+
+impl DegradationAttributionSummarizer {
     pub fn new(
+        metric: DegradationMetric,
         baseline_percentile: f32,
         min_diff_degradation: f32,
         max_diff_ci_halfwidth: f32,
-        max_hdratio50_ci_halfwidth: f32,
+        max_ci_halfwidth: f32,
         db: &DB,
     ) -> Self {
-        let mut sum = HdRatio50LowerBoundDegradationSummarizer {
+        let mut sum = Self {
+            metric,
+            baseline_percentile,
             min_diff_degradation,
             max_diff_ci_halfwidth,
-            max_hdratio50_ci_halfwidth,
-            pathid2baseroute: HashMap::new(),
+            max_ci_halfwidth,
+            pathid2baseroutes: HashMap::new(),
         };
         for (pathid, pinfo) in &db.pathid2info {
-            let mut valid: Vec<RouteInfo> = Vec::default();
+            let mut nexthops2routes: HashMap<u64, Vec<RouteInfo>> = HashMap::new();
             for timebin in pinfo.time2bin.values() {
-                match timebin.get_primary_route_hdratio() {
-                    None => continue,
-                    Some(primary) => {
-                        if primary.hdratio_p50_ci_halfwidth >= max_hdratio50_ci_halfwidth {
-                            continue;
-                        }
-                        valid.push(**primary);
+                for rtinfo in timebin.num2route.iter().flatten() {
+                    if metric.valid(rtinfo) && metric.ci_halfwidth(rtinfo) < max_ci_halfwidth {
+                        nexthops2routes.entry(rtinfo.px_nexthops).or_default().push(**rtinfo);
                     }
                 }
             }
-            if valid.is_empty() {
+            if nexthops2routes.is_empty() {
                 continue;
             }
-            valid.sort_by(RouteInfo::compare_median_hdratio);
-            let i: usize = ((valid.len() - 1) as f32 * baseline_percentile).round() as usize;
-            sum.pathid2baseroute.insert(Arc::clone(&pathid), Box::new(valid[i]));
+            let baseroutes = nexthops2routes
+                .into_iter()
+                .map(|(nexthops, routes)| {
+                    (nexthops, Box::new(metric.pick(routes, baseline_percentile)))
+                })
+                .collect();
+            sum.pathid2baseroutes.insert(Arc::clone(pathid), baseroutes);
         }
         info!(
-            "HdRatio50LowerBoundDegradationSummarizer paths in={} out={}",
+            "DegradationAttributionSummarizer metric={:?} paths in={} out={}",
+            metric,
             db.pathid2info.len(),
-            sum.pathid2baseroute.len()
+            sum.pathid2baseroutes.len()
         );
         sum
     }
-}
 
-impl TimeBinSummarizer for HdRatio50LowerBoundDegradationSummarizer {
-    fn summarize(&self, pathid: &PathId, bin: &TimeBin) -> TimeBinSummary {
-        match (self.pathid2baseroute.get(pathid), bin.get_primary_route_hdratio()) {
-            (None, _) => TimeBinSummary::WideConfidenceInterval,
-            (_, None) => TimeBinSummary::NoRoute,
-            (Some(bestroute), Some(primary)) => {
-                let (diff, halfwidth) = RouteInfo::hdratio_median_diff_ci(bestroute, primary);
-                if halfwidth > self.max_diff_ci_halfwidth {
-                    TimeBinSummary::WideConfidenceInterval
-                } else {
-                    TimeBinSummary::Valid(TimeBinStats {
-                        bytes: bin.bytes_acked_sum,
-                        diff_ci: diff,
-                        diff_ci_halfwidth: halfwidth,
-                        primary_peer_type: primary.peer_type,
-                        alternate_peer_type: bestroute.peer_type,
-                        bitmask: 0,
-                        is_shifted: diff - halfwidth > self.min_diff_degradation,
-                    })
-                }
+    fn primary<'b>(&self, bin: &'b TimeBin) -> Option<&'b RouteInfo> {
+        match self.metric {
+            DegradationMetric::MinRtt50 => bin.get_primary_route_minrtt().as_deref(),
+            DegradationMetric::HdRatio50 | DegradationMetric::HdRatioMean => {
+                bin.get_primary_route_hdratio().as_deref()
             }
         }
     }
+
+    fn compare<'a>(
+        &self,
+        baseroutes: &'a HashMap<u64, Box<RouteInfo>>,
+        route: &'a RouteInfo,
+    ) -> Option<RouteComparison<'a>> {
+        let baseroute: &RouteInfo = baseroutes.get(&route.px_nexthops)?;
+        let (diff, halfwidth) = self.metric.diff_ci(route, baseroute);
+        Some(RouteComparison {
+            route,
+            baseroute,
+            diff,
+            halfwidth,
+        })
+    }
+
+    /// Valid alternates of `bin` whose comparison against their
+    /// baseline is narrow enough.
+    fn alternates<'a>(
+        &self,
+        baseroutes: &'a HashMap<u64, Box<RouteInfo>>,
+        bin: &'a TimeBin,
+    ) -> Vec<RouteComparison<'a>> {
+        bin.num2route[1..]
+            .iter()
+            .flatten()
+            .filter(|rtinfo| rtinfo.apm_route_num != 1 && self.metric.valid(rtinfo))
+            .filter_map(|rtinfo| self.compare(baseroutes, rtinfo))
+            .filter(|cmp| cmp.halfwidth <= self.max_diff_ci_halfwidth)
+            .collect()
+    }
+}
+
+impl TimeBinSummarizer for DegradationAttributionSummarizer {
+    fn summarize(&self, pathid: &PathId, bin: &TimeBin) -> TimeBinSummary {
+        let baseroutes = match (self.pathid2baseroutes.get(pathid), self.primary(bin)) {
+            (None, _) => return TimeBinSummary::WideConfidenceInterval,
+            (_, None) => return TimeBinSummary::NoRoute,
+            (Some(baseroutes), Some(_)) => baseroutes,
+        };
+        let primary = match self.compare(baseroutes, self.primary(bin).unwrap()) {
+            Some(cmp) if cmp.halfwidth <= self.max_diff_ci_halfwidth => cmp,
+            _ => return TimeBinSummary::WideConfidenceInterval,
+        };
+        let is_shifted: bool = primary.degraded(self.min_diff_degradation);
+        let alternates = self.alternates(baseroutes, bin);
+        let attribution = if alternates.is_empty() {
+            None
+        } else {
+            let degraded: usize =
+                alternates.iter().filter(|cmp| cmp.degraded(self.min_diff_degradation)).count();
+            Some(DegradationAttribution::classify(is_shifted, degraded, alternates.len()))
+        };
+        TimeBinSummary::Valid(TimeBinStats {
+            diff_ci: primary.diff,
+            diff_ci_lb: primary.diff - primary.halfwidth,
+            diff_ci_ub: primary.diff + primary.halfwidth,
+            is_shifted,
+            primary_peer_type: primary.route.peer_type,
+            selection: RouteSelection::baseline(bin, primary.route),
+            alternate_peer_type: alternates
+                .first()
+                .map_or(primary.route.peer_type, |cmp| cmp.route.peer_type),
+            bitmask: 0,
+            bytes: bin.bytes_acked_sum,
+            attribution,
+            ..Default::default()
+        })
+    }
     fn get_routes<'s: 'd, 'd>(
         &'s self,
         pathid: &PathId,
-        time: u64,
-        db: &'d DB,
-    ) -> (&'d RouteInfo, &'d RouteInfo) {
-        (
-            &self.pathid2baseroute[pathid],
-            &db.pathid2info[pathid].time2bin[&time].get_primary_route_hdratio().as_ref().unwrap(),
-        )
+        bin: &'d TimeBin,
+        selection: RouteSelection,
+    ) -> Option<SelectedRoutes<'d>> {
+        let primary: &RouteInfo = selection.primary_route(bin)?;
+        let baseline: &RouteInfo = self.pathid2baseroutes.get(pathid)?.get(&primary.px_nexthops)?;
+        Some(SelectedRoutes::Baseline {
+            baseline,
+            primary,
+        })
     }
     fn prefix(&self) -> String {
         format!(
-            "hdratio50--deg--bound-true--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}",
-            self.min_diff_degradation, self.max_diff_ci_halfwidth, self.max_hdratio50_ci_halfwidth,
+            "{}--deg-attribution--diff-thresh-{:0.2}--diff-ci-{:0.2}--base-ci-{:0.2}--baseline-{:0.2}",
+            self.metric.prefix(),
+            self.min_diff_degradation,
+            self.max_diff_ci_halfwidth,
+            self.max_ci_halfwidth,
+            self.baseline_percentile,
         )
     }
+    fn explain(&self, pathid: &PathId, bin: &TimeBin) -> Vec<String> {
+        let primary = self.primary(bin);
+        let mut lines: Vec<String> = Selection {
+            rule: "baselines: route of the baseline percentile bin for each px_nexthops",
+            metric: self.metric.explain_metric(),
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: None,
+            primary,
+            alternate: None,
+        }
+        .describe(bin);
+        let baseroutes = match self.pathid2baseroutes.get(pathid) {
+            Some(baseroutes) => baseroutes,
+            None => {
+                lines.push(format!(
+                    "no baseline: no route with {} CI halfwidth <= {:0.3}: WideConfidenceInterval",
+                    self.metric.name(),
+                    self.max_ci_halfwidth
+                ));
+                return lines;
+            }
+        };
+        let primary = match primary {
+            Some(primary) => primary,
+            None => return lines,
+        };
+        let cmp = match self.compare(baseroutes, primary) {
+            Some(cmp) => cmp,
+            None => {
+                lines.push(
+                    "no baseline for the primary nexthops: WideConfidenceInterval".to_string(),
+                );
+                return lines;
+            }
+        };
+        lines.push(format!(
+            "primary baseline percentile {:0.2} {}",
+            self.baseline_percentile,
+            describe_route(cmp.baseroute)
+        ));
+        lines.push(check_width(
+            &format!("{} diff CI halfwidth", self.metric.name()),
+            cmp.halfwidth,
+            self.max_diff_ci_halfwidth,
+        ));
+        lines.push(check_shift(
+            &format!("{} diff lower bound", self.metric.name()),
+            cmp.diff - cmp.halfwidth,
+            ">",
+            self.min_diff_degradation,
+            cmp.degraded(self.min_diff_degradation),
+        ));
+        for alt in self.alternates(baseroutes, bin) {
+            lines.push(format!(
+                "alternate {} baseline {} diff lower bound {:0.3}: {}",
+                describe_route(alt.route),
+                describe_route(alt.baseroute),
+                alt.diff - alt.halfwidth,
+                if alt.degraded(self.min_diff_degradation) {
+                    "degraded"
+                } else {
+                    "healthy"
+                }
+            ));
+        }
+        if let TimeBinSummary::Valid(binstats) = self.summarize(pathid, bin) {
+            match binstats.attribution {
+                Some(attribution) => lines.push(format!("attribution: {:?}", attribution)),
+                None => lines.push("attribution: no comparable alternate".to_string()),
+            }
+        }
+        lines
+    }
 }
 
 #[cfg(test)]
@@ -258,19 +1650,19 @@ mod tests {
         let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 50, 60, 8);
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(0.0, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 50);
 
         let mut db: DB = DB::default();
         let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(0.0, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 50);
 
         let mut db: DB = DB::default();
         let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 12, 60, 51, 8);
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(0.0, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 60);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 60);
 
         let mut db: DB = DB::default();
         let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 12, 60, 51, 12);
@@ -285,7 +1677,7 @@ mod tests {
         time2bin.entry(BIN_DURATION_SECS).and_modify(|e| *e = timebin);
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(0.0, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 50);
 
         let mut db: DB = DB::default();
         let mut time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
@@ -294,7 +1686,80 @@ mod tests {
         time2bin.entry(BIN_DURATION_SECS).and_modify(|e| *e = timebin);
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(0.0, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 40);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 40);
+    }
+
+    #[test]
+    fn test_minrtt_percentile_degradation() {
+        let pid1 = db::tests::make_path_id();
+
+        let mut db: DB = DB::default();
+        let mut time2bin: BTreeMap<u64, TimeBin> = BTreeMap::new();
+        for (i, p25) in [20, 20, 20, 35].iter().enumerate() {
+            let time: u64 = i as u64 * BIN_DURATION_SECS;
+            time2bin.insert(time, TimeBin::mock_minrtt_p25(time, *p25, 20, 4));
+        }
+        // Not a baseline candidate because of its CI, and not valid.
+        time2bin.insert(4 * BIN_DURATION_SECS, TimeBin::mock_minrtt_p25(0, 10, 20, 12));
+        time2bin.insert(5 * BIN_DURATION_SECS, TimeBin::mock_minrtt_p50(0, 10, 20, 4));
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+
+        let sum = MinRttPercentileLowerBoundDegradationSummarizer::new(
+            MinRttPercentile::P25,
+            0.0,
+            5.0,
+            10.0,
+            10.0,
+            &db,
+        );
+        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p25 == Some((20, 4)));
+        assert!(
+            sum.prefix()
+                == "minrtt-p25--deg--bound-true--diff-thresh-5.00--diff-ci-10.00--base-ci-10.00--baseline-0.00"
+        );
+
+        let time2bin = &db.pathid2info[&pid1].time2bin;
+        let summarize = |i: u64| sum.summarize(&pid1, &time2bin[&(i * BIN_DURATION_SECS)]);
+        match (summarize(0), summarize(3)) {
+            (TimeBinSummary::Valid(same), TimeBinSummary::Valid(worse)) => {
+                assert!(!same.is_shifted);
+                assert!(worse.is_shifted);
+                assert!((worse.diff_ci - 15.0).abs() < 1e-6);
+            }
+            _ => unreachable!(),
+        }
+        assert!(summarize(4) == TimeBinSummary::WideConfidenceInterval);
+        assert!(summarize(5) == TimeBinSummary::NoRoute);
+    }
+
+    #[test]
+    fn test_hdratio_mean_degradation() {
+        let pid1 = db::tests::make_path_id();
+
+        let mut db: DB = DB::default();
+        let mut time2bin: BTreeMap<u64, TimeBin> = BTreeMap::new();
+        for (i, hdratio) in [0.9, 0.9, 0.9, 0.6].iter().enumerate() {
+            let time: u64 = i as u64 * BIN_DURATION_SECS;
+            time2bin.insert(time, TimeBin::mock_hdratio_mean(time, *hdratio, 0.9, 0.1));
+        }
+        // Not a baseline candidate because of its variance.
+        time2bin.insert(4 * BIN_DURATION_SECS, TimeBin::mock_hdratio_mean(0, 1.0, 0.9, 2.0));
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+
+        let sum = HdRatioMeanLowerBoundDegradationSummarizer::new(0.9, 0.1, 0.2, 0.1, &db);
+        assert!((sum.pathid2baseroute[&pid1].hdratio - 0.9).abs() < 1e-6);
+
+        let time2bin = &db.pathid2info[&pid1].time2bin;
+        let summarize = |i: u64| sum.summarize(&pid1, &time2bin[&(i * BIN_DURATION_SECS)]);
+        match (summarize(0), summarize(3)) {
+            (TimeBinSummary::Valid(same), TimeBinSummary::Valid(worse)) => {
+                assert!(!same.is_shifted);
+                assert!(worse.is_shifted);
+                assert!((worse.diff_ci - 0.3).abs() < 1e-6);
+            }
+            _ => unreachable!(),
+        }
+        assert!(summarize(4) == TimeBinSummary::WideConfidenceInterval);
     }
 
     #[test]
@@ -313,7 +1778,7 @@ mod tests {
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         assert!(db.total_traffic == u128::from(nbins * TimeBin::MOCK_TOTAL_BYTES));
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(0.0, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 40);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 40);
 
         let mut db: DB = DB::default();
         let mut time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
@@ -326,7 +1791,7 @@ mod tests {
         }
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(0.0, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 40);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 40);
     }
 
     #[test]
@@ -346,7 +1811,7 @@ mod tests {
         for i in 0..nbins {
             let pct: f32 = i as f32 / nbins as f32;
             let sum = MinRtt50LowerBoundDegradationSummarizer::new(pct, 0.0, 10.0, 10.0, &db);
-            let offset: usize = usize::from(sum.pathid2baseroute[&pid1].minrtt_ms_p50 - 50);
+            let offset: usize = usize::from(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 - 50);
             assert!(offset == i || offset == i + 1 || offset == i - 1);
         }
 
@@ -365,7 +1830,7 @@ mod tests {
             // so we have half the number of valid bins:
             let pct: f32 = i as f32 / nbins as f32;
             let sum = MinRtt50LowerBoundDegradationSummarizer::new(pct, 0.0, 10.0, 10.0, &db);
-            let offset: usize = usize::from(sum.pathid2baseroute[&pid1].minrtt_ms_p50 - 50);
+            let offset: usize = usize::from(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 - 50);
             assert!(offset >= std::cmp::max(i, 2) - 2 && offset <= i + 2);
         }
     }
@@ -387,7 +1852,7 @@ mod tests {
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         assert!(db.total_traffic == u128::from(nbins * TimeBin::MOCK_TOTAL_BYTES));
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(pct, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 50);
 
         let mut db: DB = DB::default();
         let mut time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
@@ -400,7 +1865,153 @@ mod tests {
         }
         assert!(db.insert(pid1.clone(), time2bin).is_none());
         let sum = MinRtt50LowerBoundDegradationSummarizer::new(pct, 0.0, 10.0, 10.0, &db);
-        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 40);
+        assert!(sum.pathid2baseroute[&pid1][&0].minrtt_ms_p50 == 40);
+    }
+
+    #[test]
+    fn test_minrtt_degradation_baseline_strategies() {
+        const DAY: u64 = 86400;
+        let pid1 = db::tests::make_path_id();
+        let new = |strategy: BaselineStrategy, db: &DB| {
+            MinRtt50LowerBoundDegradationSummarizer::with_strategy(
+                0.0, 0.0, 10.0, 10.0, strategy, db,
+            )
+        };
+        let is_shifted =
+            |sum: &MinRtt50LowerBoundDegradationSummarizer, db: &DB, time: u64| match sum
+                .summarize(&pid1, &db.pathid2info[&pid1].time2bin[&time])
+            {
+                TimeBinSummary::Valid(binstats) => binstats.is_shifted,
+                _ => unreachable!(),
+            };
+
+        // The primary route moves to other nexthops with higher MinRTT
+        // after three days; bins at midnight of the first two days are
+        // faster.
+        let mut db: DB = DB::default();
+        let mut time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 60, 51, 4, 60, 51, 4);
+        for (time, timebin) in time2bin.iter_mut() {
+            let primary: &mut RouteInfo = timebin.num2route[0].as_mut().unwrap();
+            if *time == 0 || *time == DAY {
+                primary.minrtt_ms_p50 = 40;
+            } else if *time >= 3 * DAY {
+                primary.minrtt_ms_p50 = 80;
+                primary.px_nexthops = 1337;
+            }
+        }
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+
+        let sum = new(BaselineStrategy::Global, &db);
+        assert!(sum.prefix().ends_with("--baseline-0.00"));
+        assert!(is_shifted(&sum, &db, 2 * DAY + BIN_DURATION_SECS));
+        assert!(is_shifted(&sum, &db, 5 * DAY));
+
+        let sum = new(BaselineStrategy::Trailing(1), &db);
+        assert!(sum.prefix().ends_with("--trailing-days-1"));
+        assert!(sum.pathid2baseroute[&pid1][&(2 * DAY)].minrtt_ms_p50 == 60);
+        assert!(sum.pathid2baseroute[&pid1][&(DAY + 900)].minrtt_ms_p50 == 40);
+        assert!(!is_shifted(&sum, &db, 2 * DAY + BIN_DURATION_SECS));
+        assert!(!is_shifted(&sum, &db, 5 * DAY));
+        assert!(is_shifted(&sum, &db, 3 * DAY));
+
+        let sum = new(BaselineStrategy::TimeOfDay(2), &db);
+        assert!(sum.prefix().ends_with("--time-of-day-days-2"));
+        assert!(sum.pathid2baseroute[&pid1][&(2 * DAY)].minrtt_ms_p50 == 40);
+        assert!(sum.pathid2baseroute[&pid1][&(3 * DAY)].minrtt_ms_p50 == 40);
+        assert!(sum.pathid2baseroute[&pid1][&(4 * DAY)].minrtt_ms_p50 == 60);
+        assert!(!is_shifted(&sum, &db, 2 * DAY + BIN_DURATION_SECS));
+
+        let sum = new(BaselineStrategy::Nexthops, &db);
+        assert!(sum.prefix().ends_with("--per-nexthops"));
+        assert!(sum.pathid2baseroute[&pid1][&1337].minrtt_ms_p50 == 80);
+        assert!(sum.pathid2baseroute[&pid1][&1].minrtt_ms_p50 == 40);
+        assert!(!is_shifted(&sum, &db, 5 * DAY));
+        assert!(is_shifted(&sum, &db, 2 * DAY + BIN_DURATION_SECS));
+    }
+
+    #[test]
+    fn test_trailing_baselines_match_sorted_windows() {
+        // MinRTTs repeat so windows have ties; px_nexthops identifies
+        // the bin a baseline route comes from.
+        let mut time2bin: BTreeMap<u64, TimeBin> = BTreeMap::new();
+        let mut valid: BTreeMap<u64, RouteInfo> = BTreeMap::new();
+        for k in 0..(4 * 96) {
+            let time: u64 = k * BIN_DURATION_SECS;
+            time2bin.insert(time, TimeBin::mock_minrtt_p50(time, 40, 51, 8));
+            if k % 5 != 0 {
+                let mut rtinfo = RouteInfo::mock_minrtt_p50(1, 20 + (k * 37 % 13) as u16, 4);
+                rtinfo.px_nexthops = time;
+                valid.insert(time, rtinfo);
+            }
+        }
+        for &days in [1, 2].iter() {
+            for &pct in [0.0, 0.1, 0.5, 1.0].iter() {
+                let index = |len: usize| ((len - 1) as f32 * pct).round() as usize;
+                let baselines = BaselineStrategy::Trailing(days).baselines(
+                    &time2bin,
+                    &valid,
+                    RouteInfo::compare_median_minrtt,
+                    index,
+                );
+                for &time in time2bin.keys() {
+                    let start: u64 = (time + 1).saturating_sub(u64::from(days) * 86400);
+                    let mut routes: Vec<RouteInfo> =
+                        valid.range(start..=time).map(|(_, rt)| *rt).collect();
+                    if routes.is_empty() {
+                        assert!(!baselines.contains_key(&time));
+                        continue;
+                    }
+                    routes.sort_by(RouteInfo::compare_median_minrtt);
+                    let expected: u64 = routes[index(routes.len())].px_nexthops;
+                    assert!(baselines[&time].px_nexthops == expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_trailing_baselines_nan_hdratio() {
+        // A NaN HD-ratio in the window must not break the binary
+        // searches that keep the window sorted.
+        let mut time2bin: BTreeMap<u64, TimeBin> = BTreeMap::new();
+        let mut valid: BTreeMap<u64, RouteInfo> = BTreeMap::new();
+        for k in 0..(2 * 96) {
+            let time: u64 = k * BIN_DURATION_SECS;
+            time2bin.insert(time, TimeBin::mock_hdratio_p50(time, 0.8, 0.9, 0.05));
+            let mut rtinfo = RouteInfo::mock_hdratio_p50(1, 0.5 + (k % 7) as f32 / 20.0, 0.05);
+            if k % 11 == 3 {
+                rtinfo.hdratio_p50 = f32::NAN;
+            }
+            rtinfo.px_nexthops = time;
+            valid.insert(time, rtinfo);
+        }
+        let index = |len: usize| ((len - 1) as f32 * 0.5).round() as usize;
+        let baselines = BaselineStrategy::Trailing(1).baselines(
+            &time2bin,
+            &valid,
+            RouteInfo::compare_median_hdratio,
+            index,
+        );
+        for &time in time2bin.keys() {
+            let start: u64 = (time + 1).saturating_sub(86400);
+            let mut routes: Vec<RouteInfo> = valid.range(start..=time).map(|(_, rt)| *rt).collect();
+            routes.sort_by(RouteInfo::compare_median_hdratio);
+            assert!(baselines[&time].px_nexthops == routes[index(routes.len())].px_nexthops);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_trailing_baselines_zero_days() {
+        let db: DB = DB::default();
+        MinRtt50LowerBoundDegradationSummarizer::with_strategy(
+            0.1,
+            5.0,
+            10.0,
+            20.0,
+            BaselineStrategy::Trailing(0),
+            &db,
+        );
     }
 
     #[test]
@@ -417,9 +2028,9 @@ mod tests {
         assert!(db.total_traffic == u128::from(nbins * TimeBin::MOCK_TOTAL_BYTES));
 
         let sum = HdRatio50LowerBoundDegradationSummarizer::new(1.0, 0.1, 0.1, 0.1, &db);
-        assert!((sum.pathid2baseroute[&pid1].hdratio_p50 - 0.9).abs() < 1e-6);
+        assert!((sum.pathid2baseroute[&pid1][&0].hdratio_p50 - 0.9).abs() < 1e-6);
         let sum = HdRatio50LowerBoundDegradationSummarizer::new(0.9, 0.1, 0.1, 0.1, &db);
-        assert!((sum.pathid2baseroute[&pid1].hdratio_p50 - 0.8).abs() < 1e-6);
+        assert!((sum.pathid2baseroute[&pid1][&0].hdratio_p50 - 0.8).abs() < 1e-6);
 
         let mut db: DB = DB::default();
         let time2bin =
@@ -429,9 +2040,9 @@ mod tests {
         assert!(db.total_traffic == u128::from(nbins * TimeBin::MOCK_TOTAL_BYTES));
 
         let sum = HdRatio50LowerBoundDegradationSummarizer::new(1.0, 0.1, 0.1, 0.1, &db);
-        assert!((sum.pathid2baseroute[&pid1].hdratio_p50 - 0.8).abs() < 1e-6);
+        assert!((sum.pathid2baseroute[&pid1][&0].hdratio_p50 - 0.8).abs() < 1e-6);
         let sum = HdRatio50LowerBoundDegradationSummarizer::new(0.9, 0.1, 0.1, 0.2, &db);
-        assert!((sum.pathid2baseroute[&pid1].hdratio_p50 - 0.9).abs() < 1e-6);
+        assert!((sum.pathid2baseroute[&pid1][&0].hdratio_p50 - 0.9).abs() < 1e-6);
     }
 
     #[test]
@@ -454,7 +2065,7 @@ mod tests {
         for i in 0..nbins {
             let pct: f32 = i as f32 / nbins as f32;
             let sum = HdRatio50LowerBoundDegradationSummarizer::new(pct, 0.0, 0.1, 0.1, &db);
-            let offset = (STEPSIZE * (sum.pathid2baseroute[&pid1].hdratio_p50 - 0.8)) as usize;
+            let offset = (STEPSIZE * (sum.pathid2baseroute[&pid1][&0].hdratio_p50 - 0.8)) as usize;
             assert!(offset >= std::cmp::max(i, 2) - 2 && offset <= i + 1);
         }
 
@@ -475,7 +2086,7 @@ mod tests {
             // so we have half the number of valid bins:
             let pct: f32 = i as f32 / nbins as f32;
             let sum = HdRatio50LowerBoundDegradationSummarizer::new(pct, 0.0, 0.1, 0.1, &db);
-            let offset = (STEPSIZE * (sum.pathid2baseroute[&pid1].hdratio_p50 - 0.8)) as usize;
+            let offset = (STEPSIZE * (sum.pathid2baseroute[&pid1][&0].hdratio_p50 - 0.8)) as usize;
             assert!(offset >= std::cmp::max(i, 3) - 3 && offset <= i + 1);
             // this f32 precision ::facepalm::
         }
@@ -492,7 +2103,7 @@ mod tests {
         assert!(db.pathid2info.len() == 1);
 
         let sum = HdRatio50LowerBoundDegradationSummarizer::new(1.0, 0.0, 0.2, 0.2, &db);
-        assert!((sum.pathid2baseroute[&pid1].hdratio_p50 - 0.95).abs() < 1e-6);
+        assert!((sum.pathid2baseroute[&pid1][&0].hdratio_p50 - 0.95).abs() < 1e-6);
 
         // ci_halfwidth = 2 * ((0.1/2)**2 + (0.1/2)**2).sqrt() = 0.14
         let timebin = TimeBin::mock_hdratio_p50(0, 0.9, 0.8, 0.1);
@@ -500,7 +2111,7 @@ mod tests {
         if let TimeBinSummary::Valid(binstats) = binsum {
             assert!(!binstats.is_shifted);
             assert!((binstats.diff_ci - 0.05).abs() < 1e-6);
-            assert!((binstats.diff_ci_halfwidth - 0.14).abs() < 0.01);
+            assert!((binstats.diff_ci_halfwidth() - 0.14).abs() < 0.01);
         } else {
             unreachable!();
         }
@@ -510,7 +2121,7 @@ mod tests {
         if let TimeBinSummary::Valid(binstats) = binsum {
             assert!(binstats.is_shifted);
             assert!((binstats.diff_ci - 0.15).abs() < 1e-6);
-            assert!((binstats.diff_ci_halfwidth - 0.14).abs() < 0.01);
+            assert!((binstats.diff_ci_halfwidth() - 0.14).abs() < 0.01);
         } else {
             unreachable!();
         }
@@ -520,4 +2131,202 @@ mod tests {
         let binsum = sum.summarize(&pid1, &timebin);
         assert!(binsum == TimeBinSummary::WideConfidenceInterval);
     }
+
+    #[test]
+    fn test_minrtt_degradation_distinct_new_minrtt_ci() {
+        let pid1 = db::tests::make_path_id();
+        let new = |db: &DB| {
+            MinRtt50LowerBoundDistinctPathsDegradationSummarizer::new(0.0, 0.0, 10.0, 10.0, 0.8, db)
+        };
+
+        let mut db: DB = DB::default();
+        let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 50, 60, 8);
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        assert!(new(&db).pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+
+        let mut db: DB = DB::default();
+        let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        assert!(new(&db).pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+
+        let mut db: DB = DB::default();
+        let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 12, 60, 51, 8);
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        assert!(new(&db).pathid2baseroute[&pid1].minrtt_ms_p50 == 60);
+
+        let mut db: DB = DB::default();
+        let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 12, 60, 51, 12);
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        assert!(!new(&db).pathid2baseroute.contains_key(&pid1));
+
+        let mut db: DB = DB::default();
+        let mut time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
+        // Will not be used because CI is 12 and we only allow 10 below.
+        let timebin = TimeBin::mock_minrtt_p50(BIN_DURATION_SECS, 40, 51, 12);
+        time2bin.entry(BIN_DURATION_SECS).and_modify(|e| *e = timebin);
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        assert!(new(&db).pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+
+        let mut db: DB = DB::default();
+        let mut time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
+        // Will be used because CI is 8 and we only allow 10 below.
+        let timebin = TimeBin::mock_minrtt_p50(BIN_DURATION_SECS, 40, 51, 8);
+        time2bin.entry(BIN_DURATION_SECS).and_modify(|e| *e = timebin);
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        assert!(new(&db).pathid2baseroute[&pid1].minrtt_ms_p50 == 40);
+    }
+
+    #[test]
+    fn test_minrtt_degradation_distinct_new_nexthops() {
+        let pid1 = db::tests::make_path_id();
+
+        let mut db: DB = DB::default();
+        let mut time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
+        // Will not be used because px_nexthops shows up only once
+        let mut timebin = TimeBin::mock_minrtt_p50(BIN_DURATION_SECS, 40, 51, 8);
+        timebin.num2route[0].as_mut().unwrap().px_nexthops = 1337;
+        time2bin.entry(BIN_DURATION_SECS).and_modify(|e| *e = timebin);
+        let nbins: u64 = time2bin.len() as u64;
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        assert!(db.total_traffic == u128::from(nbins * TimeBin::MOCK_TOTAL_BYTES));
+        let sum = MinRtt50LowerBoundDistinctPathsDegradationSummarizer::new(
+            0.0, 0.0, 10.0, 10.0, 0.8, &db,
+        );
+        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+
+        let mut db: DB = DB::default();
+        let mut time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 8, 60, 51, 8);
+        // No route will win, even though we pass a min_frac of 0.5
+        for i in (BIN_DURATION_SECS..(7 * 86400)).step_by(2 * BIN_DURATION_SECS as usize) {
+            let mut timebin = TimeBin::mock_minrtt_p50(i, 60, 51, 8);
+            timebin.num2route[0].as_mut().unwrap().px_nexthops = 1337;
+            time2bin.entry(i).and_modify(|e| *e = timebin);
+        }
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        let sum = MinRtt50LowerBoundDistinctPathsDegradationSummarizer::new(
+            0.0, 0.0, 10.0, 10.0, 0.5, &db,
+        );
+        assert!(!sum.pathid2baseroute.contains_key(&pid1));
+    }
+
+    #[test]
+    fn test_minrtt_degradation_distinct_summarize() {
+        let pid1 = db::tests::make_path_id();
+
+        let mut db: DB = DB::default();
+        let time2bin = TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 51, 4, 50, 60, 4);
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        let sum = MinRtt50LowerBoundDistinctPathsDegradationSummarizer::new(
+            0.0, 0.0, 10.0, 10.0, 0.8, &db,
+        );
+        assert!(sum.pathid2baseroute[&pid1].minrtt_ms_p50 == 50);
+
+        // ci_halfwidth = 2 * (2**2 + 2**2).sqrt() = 5.66
+        let timebin = TimeBin::mock_minrtt_p50(0, 51, 51, 4);
+        let binsum = sum.summarize(&pid1, &timebin);
+        if let TimeBinSummary::Valid(binstats) = binsum {
+            assert!(!binstats.is_shifted);
+            assert!((binstats.diff_ci - 1.0).abs() < 1e-6);
+        } else {
+            unreachable!();
+        }
+
+        let mut timebin = TimeBin::mock_minrtt_p50(0, 60, 51, 4);
+        let binsum = sum.summarize(&pid1, &timebin);
+        if let TimeBinSummary::Valid(binstats) = binsum {
+            assert!(binstats.is_shifted);
+            assert!((binstats.diff_ci - 10.0).abs() < 1e-6);
+        } else {
+            unreachable!();
+        }
+
+        timebin.num2route[0].as_mut().unwrap().px_nexthops = 1337;
+        assert!(sum.summarize(&pid1, &timebin) == TimeBinSummary::NoRoute);
+
+        let timebin = TimeBin::mock_minrtt_p50(0, 60, 51, 100);
+        let binsum = sum.summarize(&pid1, &timebin);
+        assert!(binsum == TimeBinSummary::WideConfidenceInterval);
+    }
+
+    #[test]
+    fn test_hdratio50_degradation_distinct() {
+        let pid1 = db::tests::make_path_id();
+
+        let mut db: DB = DB::default();
+        let mut time2bin =
+            TimeBin::mock_week_hdratio_p50(BIN_DURATION_SECS, 0.9, 0.9, 0.1, 0.8, 0.9, 0.1);
+        // The best bin uses other nexthops and is not the baseline
+        let mut timebin = TimeBin::mock_hdratio_p50(BIN_DURATION_SECS, 0.99, 0.9, 0.1);
+        timebin.num2route[0].as_mut().unwrap().px_nexthops = 1337;
+        time2bin.entry(BIN_DURATION_SECS).and_modify(|e| *e = timebin.clone());
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        let sum = HdRatio50LowerBoundDistinctPathsDegradationSummarizer::new(
+            1.0, 0.0, 0.2, 0.2, 0.8, &db,
+        );
+        assert!((sum.pathid2baseroute[&pid1].hdratio_p50 - 0.9).abs() < 1e-6);
+
+        assert!(sum.summarize(&pid1, &timebin) == TimeBinSummary::NoRoute);
+
+        // ci_halfwidth = 2 * ((0.1/2)**2 + (0.1/2)**2).sqrt() = 0.14
+        let timebin = TimeBin::mock_hdratio_p50(0, 0.7, 0.9, 0.1);
+        let binsum = sum.summarize(&pid1, &timebin);
+        if let TimeBinSummary::Valid(binstats) = binsum {
+            assert!(binstats.is_shifted);
+            assert!((binstats.diff_ci - 0.2).abs() < 1e-6);
+        } else {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn test_degradation_attribution() {
+        let pid1 = db::tests::make_path_id();
+
+        // Alternates use different nexthops so they get their own baseline.
+        let with_alternate_nexthops = |mut timebin: TimeBin| {
+            timebin.num2route[1].as_mut().unwrap().px_nexthops = 2;
+            timebin
+        };
+        let mut db: DB = DB::default();
+        let time2bin: BTreeMap<u64, TimeBin> =
+            TimeBin::mock_week_minrtt_p50(BIN_DURATION_SECS, 50, 70, 4, 50, 70, 4)
+                .into_iter()
+                .map(|(time, timebin)| (time, with_alternate_nexthops(timebin)))
+                .collect();
+        assert!(db.insert(pid1.clone(), time2bin).is_none());
+        let sum = DegradationAttributionSummarizer::new(
+            DegradationMetric::MinRtt50,
+            0.0,
+            0.0,
+            10.0,
+            10.0,
+            &db,
+        );
+        assert!(sum.pathid2baseroutes[&pid1][&1].minrtt_ms_p50 == 50);
+        assert!(sum.pathid2baseroutes[&pid1][&2].minrtt_ms_p50 == 70);
+
+        // ci_halfwidth = 2 * (2**2 + 2**2).sqrt() = 5.66
+        let attribution = |pri: u16, alt: u16| {
+            let timebin = with_alternate_nexthops(TimeBin::mock_minrtt_p50(0, pri, alt, 4));
+            match sum.summarize(&pid1, &timebin) {
+                TimeBinSummary::Valid(binstats) => (binstats.is_shifted, binstats.attribution),
+                _ => unreachable!(),
+            }
+        };
+        assert!(attribution(51, 71) == (false, Some(DegradationAttribution::NoDegradation)));
+        assert!(attribution(60, 71) == (true, Some(DegradationAttribution::PrimaryOnly)));
+        assert!(attribution(60, 80) == (true, Some(DegradationAttribution::AllRoutes)));
+        assert!(attribution(51, 80) == (false, Some(DegradationAttribution::AlternateOnly)));
+
+        // Alternates whose comparison is too wide are not considered.
+        let mut timebin = with_alternate_nexthops(TimeBin::mock_minrtt_p50(0, 60, 80, 4));
+        timebin.num2route[1].as_mut().unwrap().minrtt_ms_p50_ci_halfwidth = 100;
+        match sum.summarize(&pid1, &timebin) {
+            TimeBinSummary::Valid(binstats) => assert!(binstats.attribution.is_none()),
+            _ => unreachable!(),
+        }
+
+        let timebin = TimeBin::mock_minrtt_p50(0, 60, 80, 100);
+        assert!(sum.summarize(&pid1, &timebin) == TimeBinSummary::WideConfidenceInterval);
+    }
 }
@@ -1,13 +1,14 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::Path;
 
 use log::{error, info};
 use serde_pickle;
 
 use crate::performance::db;
+use crate::performance::explain::{check_shift, check_width, Metric, Selection, ANY_PEER_TYPE};
 use crate::performance::perfstats;
 use crate::performance::perfstats::TimeBinSummarizer;
 
@@ -19,6 +20,17 @@ pub struct MinRtt50ImprovementSummarizer {
     pub compare_lower_bound: bool,
 }
 
+/// As `MinRtt50ImprovementSummarizer` on another MinRTT percentile,
+/// without the HD-ratio check.  Routes without the percentile are not
+/// valid.
+#[derive(Clone, Copy, Debug)]
+pub struct MinRttPercentileImprovementSummarizer {
+    pub percentile: db::MinRttPercentile,
+    pub minrtt_min_improv: f32,
+    pub max_minrtt_diff_ci_halfwidth: f32,
+    pub compare_lower_bound: bool,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct HdRatio50ImprovementSummarizer {
     pub hdratio50_min_improv: f32,
@@ -26,6 +38,15 @@ pub struct HdRatio50ImprovementSummarizer {
     pub compare_lower_bound: bool,
 }
 
+/// As `HdRatio50ImprovementSummarizer` on mean HD-ratio, choosing the
+/// alternate with the highest mean.
+#[derive(Clone, Copy, Debug)]
+pub struct HdRatioMeanImprovementSummarizer {
+    pub hdratio_min_improv: f32,
+    pub max_hdratio_diff_ci_halfwidth: f32,
+    pub compare_lower_bound: bool,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct HdRatioBootstrapDifferenceImprovementSummarizer {
     pub hdratio_boot_min_improv: f32,
@@ -33,6 +54,63 @@ pub struct HdRatioBootstrapDifferenceImprovementSummarizer {
     pub compare_lower_bound: bool,
 }
 
+/// Compares the primary route against every alternate valid for both
+/// MinRTT and HD-ratio.  Alternates whose P50s are dominated on both
+/// metrics by another alternate are discarded; among the rest, the one
+/// with the best `perfstats::ParetoOutcome` is chosen, breaking ties by
+/// the lower bound of the MinRTT and then HD-ratio improvements.  Bins
+/// are shifted if the chosen alternate is better on both metrics, or on
+/// one without a significant loss on the other.
+#[derive(Clone, Copy, Debug)]
+pub struct MinRttHdRatioParetoSummarizer {
+    pub minrtt50_min_improv: f32,
+    pub hdratio50_min_improv: f32,
+    pub max_minrtt50_diff_ci_halfwidth: f32,
+    pub max_hdratio50_diff_ci_halfwidth: f32,
+}
+
+/// The routes a `MinRttHdRatioParetoSummarizer` compares in a bin.
+enum ParetoChoice<'a> {
+    NoRoute,
+    WideConfidenceInterval,
+    Valid(&'a db::RouteInfo, &'a db::RouteInfo, perfstats::ParetoStats),
+}
+
+/// How `MinRtt50AnyAlternateSummarizer` corrects for testing the
+/// primary route against several alternates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MultipleComparison {
+    Bonferroni,
+    Holm,
+}
+
+/// Tests the primary route against every alternate valid for MinRTT,
+/// instead of only the alternate with the best point estimate, keeping
+/// the family-wise error rate at the level of the unadjusted CIs.  Bins
+/// are shifted if at least one alternate's MinRTT P50 improvement is
+/// significantly above the threshold after the correction.
+#[derive(Clone, Copy, Debug)]
+pub struct MinRtt50AnyAlternateSummarizer {
+    pub minrtt50_min_improv: f32,
+    pub max_minrtt50_diff_ci_halfwidth: f32,
+    pub correction: MultipleComparison,
+}
+
+/// The test of one alternate by a `MinRtt50AnyAlternateSummarizer`.
+struct AlternateTest<'a> {
+    index: usize,
+    alternate: &'a db::RouteInfo,
+    diff: f32,
+    halfwidth: f32,
+    /// Halfwidth of the CI at the corrected confidence level.
+    adjusted_halfwidth: f32,
+    /// One-sided p-value of the improvement being at most the threshold.
+    pvalue: f64,
+    /// Largest p-value considered significant for this alternate.
+    level: f64,
+    better: bool,
+}
+
 impl TimeBinSummarizer for MinRtt50ImprovementSummarizer {
     fn summarize(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> perfstats::TimeBinSummary {
         match (
@@ -63,27 +141,18 @@ impl TimeBinSummarizer for MinRtt50ImprovementSummarizer {
                 perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
                     bytes: bin.bytes_acked_sum,
                     diff_ci: rtt_diff,
-                    diff_ci_halfwidth: rtt_halfwidth,
+                    diff_ci_lb: rtt_diff - rtt_halfwidth,
+                    diff_ci_ub: rtt_diff + rtt_halfwidth,
                     primary_peer_type: primary.peer_type,
                     alternate_peer_type: bestalt.peer_type,
+                    selection: perfstats::RouteSelection::alternate(bin, primary, bestalt),
                     bitmask: compute_bitmask(primary, bestalt),
                     is_shifted,
+                    ..Default::default()
                 })
             }
         }
     }
-    fn get_routes<'s: 'd, 'd>(
-        &'s self,
-        pathid: &db::PathId,
-        time: u64,
-        db: &'d db::DB,
-    ) -> (&'d db::RouteInfo, &'d db::RouteInfo) {
-        let bin = &db.pathid2info[pathid].time2bin[&time];
-        (
-            bin.get_primary_route_minrtt().as_ref().unwrap(),
-            bin.get_best_alternate_minrtt(db::RouteInfo::compare_median_minrtt).as_ref().unwrap(),
-        )
-    }
     fn prefix(&self) -> String {
         format!(
             "minrtt50--opp--bound-{}--diff-thresh-{:0.2}--diff-ci-{:0.2}--hdratio-diff-ci-{:0.2}",
@@ -93,6 +162,151 @@ impl TimeBinSummarizer for MinRtt50ImprovementSummarizer {
             self.max_hdratio50_diff_ci_halfwidth,
         )
     }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_minrtt().as_deref();
+        let bestalt =
+            bin.get_best_alternate_minrtt(db::RouteInfo::compare_median_minrtt).as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "alternate: lowest MinRTT P50 among valid alternates",
+            metric: Metric::MinRtt,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: Some(ANY_PEER_TYPE),
+            primary,
+            alternate: bestalt,
+        }
+        .describe(bin);
+        if let (Some(primary), Some(bestalt)) = (primary, bestalt) {
+            let (hd_diff, hd_halfwidth) = db::RouteInfo::hdratio_median_diff_ci(bestalt, primary);
+            let (rtt_diff, rtt_halfwidth) = db::RouteInfo::minrtt_median_diff_ci(primary, bestalt);
+            lines.push(check_width(
+                "HD-ratio P50 diff CI halfwidth",
+                hd_halfwidth,
+                self.max_hdratio50_diff_ci_halfwidth,
+            ));
+            lines.push(check_width(
+                "MinRTT P50 diff CI halfwidth",
+                rtt_halfwidth,
+                self.max_minrtt50_diff_ci_halfwidth,
+            ));
+            let (name, rtt_limit) = if self.compare_lower_bound {
+                ("MinRTT P50 diff lower bound", rtt_diff - rtt_halfwidth)
+            } else {
+                ("MinRTT P50 diff", rtt_diff)
+            };
+            lines.push(check_shift(
+                name,
+                rtt_limit,
+                ">=",
+                self.minrtt50_min_improv,
+                rtt_limit >= self.minrtt50_min_improv,
+            ));
+            let hd_limit: f32 = hd_diff + hd_halfwidth;
+            lines.push(check_shift(
+                "HD-ratio P50 diff upper bound",
+                hd_limit,
+                ">=",
+                0.0,
+                hd_limit >= 0.0,
+            ));
+        }
+        lines
+    }
+}
+
+impl MinRttPercentileImprovementSummarizer {
+    fn routes<'d>(
+        &self,
+        bin: &'d db::TimeBin,
+    ) -> (Option<&'d db::RouteInfo>, Option<&'d db::RouteInfo>) {
+        let percentile = self.percentile;
+        let valid =
+            |rtinfo: &db::RouteInfo| db::RouteInfo::minrtt_percentile_valid(rtinfo, percentile);
+        (
+            bin.get_primary_route(valid).as_deref(),
+            bin.get_best_alternate(
+                |rt1, rt2| db::RouteInfo::compare_minrtt_percentile(rt1, rt2, percentile),
+                valid,
+            )
+            .as_deref(),
+        )
+    }
+}
+
+impl TimeBinSummarizer for MinRttPercentileImprovementSummarizer {
+    fn summarize(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> perfstats::TimeBinSummary {
+        match self.routes(bin) {
+            (None, _) => perfstats::TimeBinSummary::NoRoute,
+            (_, None) => perfstats::TimeBinSummary::NoRoute,
+            (Some(primary), Some(bestalt)) => {
+                let (diff, halfwidth) =
+                    db::RouteInfo::minrtt_percentile_diff_ci(primary, bestalt, self.percentile);
+                if halfwidth > self.max_minrtt_diff_ci_halfwidth {
+                    return perfstats::TimeBinSummary::WideConfidenceInterval;
+                }
+                let limit: f32 = if self.compare_lower_bound {
+                    diff - halfwidth
+                } else {
+                    diff
+                };
+                perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
+                    bytes: bin.bytes_acked_sum,
+                    diff_ci: diff,
+                    diff_ci_lb: diff - halfwidth,
+                    diff_ci_ub: diff + halfwidth,
+                    primary_peer_type: primary.peer_type,
+                    alternate_peer_type: bestalt.peer_type,
+                    selection: perfstats::RouteSelection::alternate(bin, primary, bestalt),
+                    bitmask: compute_bitmask(primary, bestalt),
+                    is_shifted: limit >= self.minrtt_min_improv,
+                    ..Default::default()
+                })
+            }
+        }
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "minrtt-p{}--opp--bound-{}--diff-thresh-{:0.2}--diff-ci-{:0.2}",
+            self.percentile.value(),
+            self.compare_lower_bound,
+            self.minrtt_min_improv,
+            self.max_minrtt_diff_ci_halfwidth,
+        )
+    }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let (primary, bestalt) = self.routes(bin);
+        let mut lines: Vec<String> = Selection {
+            rule: "alternate: lowest MinRTT percentile among valid alternates",
+            metric: Metric::MinRtt,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: Some(ANY_PEER_TYPE),
+            primary,
+            alternate: bestalt,
+        }
+        .describe(bin);
+        if let (Some(primary), Some(bestalt)) = (primary, bestalt) {
+            let (diff, halfwidth) =
+                db::RouteInfo::minrtt_percentile_diff_ci(primary, bestalt, self.percentile);
+            let name: String = format!("MinRTT P{} diff", self.percentile.value());
+            lines.push(check_width(
+                &format!("{} CI halfwidth", name),
+                halfwidth,
+                self.max_minrtt_diff_ci_halfwidth,
+            ));
+            let (name, limit) = if self.compare_lower_bound {
+                (format!("{} lower bound", name), diff - halfwidth)
+            } else {
+                (name, diff)
+            };
+            lines.push(check_shift(
+                &name,
+                limit,
+                ">=",
+                self.minrtt_min_improv,
+                limit >= self.minrtt_min_improv,
+            ));
+        }
+        lines
+    }
 }
 
 impl TimeBinSummarizer for HdRatio50ImprovementSummarizer {
@@ -116,28 +330,19 @@ impl TimeBinSummarizer for HdRatio50ImprovementSummarizer {
                     perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
                         bytes: bin.bytes_acked_sum,
                         diff_ci: diff,
-                        diff_ci_halfwidth: halfwidth,
+                        diff_ci_lb: diff - halfwidth,
+                        diff_ci_ub: diff + halfwidth,
                         primary_peer_type: primary.peer_type,
                         alternate_peer_type: bestalt.peer_type,
+                        selection: perfstats::RouteSelection::alternate(bin, primary, bestalt),
                         bitmask: compute_bitmask(primary, bestalt),
                         is_shifted: limit >= self.hdratio50_min_improv,
+                        ..Default::default()
                     })
                 }
             }
         }
     }
-    fn get_routes<'s: 'd, 'd>(
-        &'s self,
-        pathid: &db::PathId,
-        time: u64,
-        db: &'d db::DB,
-    ) -> (&'d db::RouteInfo, &'d db::RouteInfo) {
-        let bin = &db.pathid2info[pathid].time2bin[&time];
-        (
-            bin.get_primary_route_hdratio().as_ref().unwrap(),
-            bin.get_best_alternate_hdratio(db::RouteInfo::compare_median_hdratio).as_ref().unwrap(),
-        )
-    }
     fn prefix(&self) -> String {
         format!(
             "hdratio50--opp--bound-{}--diff-thresh-{:0.2}--diff-ci-{:0.2}",
@@ -146,6 +351,118 @@ impl TimeBinSummarizer for HdRatio50ImprovementSummarizer {
             self.max_hdratio50_diff_ci_halfwidth,
         )
     }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_hdratio().as_deref();
+        let bestalt =
+            bin.get_best_alternate_hdratio(db::RouteInfo::compare_median_minrtt).as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "alternate: lowest MinRTT P50 among valid alternates",
+            metric: Metric::HdRatio,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: Some(ANY_PEER_TYPE),
+            primary,
+            alternate: bestalt,
+        }
+        .describe(bin);
+        if let (Some(primary), Some(bestalt)) = (primary, bestalt) {
+            let (diff, halfwidth) = db::RouteInfo::hdratio_median_diff_ci(bestalt, primary);
+            lines.push(check_width(
+                "HD-ratio P50 diff CI halfwidth",
+                halfwidth,
+                self.max_hdratio50_diff_ci_halfwidth,
+            ));
+            let (name, limit) = if self.compare_lower_bound {
+                ("HD-ratio P50 diff lower bound", diff - halfwidth)
+            } else {
+                ("HD-ratio P50 diff", diff)
+            };
+            lines.push(check_shift(
+                name,
+                limit,
+                ">=",
+                self.hdratio50_min_improv,
+                limit >= self.hdratio50_min_improv,
+            ));
+        }
+        lines
+    }
+}
+
+impl TimeBinSummarizer for HdRatioMeanImprovementSummarizer {
+    fn summarize(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> perfstats::TimeBinSummary {
+        match (
+            bin.get_primary_route_hdratio(),
+            bin.get_best_alternate_hdratio(db::RouteInfo::compare_mean_hdratio),
+        ) {
+            (None, _) => perfstats::TimeBinSummary::NoRoute,
+            (_, None) => perfstats::TimeBinSummary::NoRoute,
+            (Some(ref primary), Some(ref bestalt)) => {
+                let (diff, halfwidth) = db::RouteInfo::hdratio_mean_diff_ci(bestalt, primary);
+                if halfwidth > self.max_hdratio_diff_ci_halfwidth {
+                    perfstats::TimeBinSummary::WideConfidenceInterval
+                } else {
+                    let limit: f32 = if self.compare_lower_bound {
+                        diff - halfwidth
+                    } else {
+                        diff
+                    };
+                    perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
+                        bytes: bin.bytes_acked_sum,
+                        diff_ci: diff,
+                        diff_ci_lb: diff - halfwidth,
+                        diff_ci_ub: diff + halfwidth,
+                        primary_peer_type: primary.peer_type,
+                        alternate_peer_type: bestalt.peer_type,
+                        selection: perfstats::RouteSelection::alternate(bin, primary, bestalt),
+                        bitmask: compute_bitmask(primary, bestalt),
+                        is_shifted: limit >= self.hdratio_min_improv,
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "hdratio--opp--bound-{}--diff-thresh-{:0.2}--diff-ci-{:0.2}",
+            self.compare_lower_bound, self.hdratio_min_improv, self.max_hdratio_diff_ci_halfwidth,
+        )
+    }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_hdratio().as_deref();
+        let bestalt =
+            bin.get_best_alternate_hdratio(db::RouteInfo::compare_mean_hdratio).as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "alternate: highest mean HD-ratio among valid alternates",
+            metric: Metric::HdRatio,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: Some(ANY_PEER_TYPE),
+            primary,
+            alternate: bestalt,
+        }
+        .describe(bin);
+        if let (Some(primary), Some(bestalt)) = (primary, bestalt) {
+            let (diff, halfwidth) = db::RouteInfo::hdratio_mean_diff_ci(bestalt, primary);
+            lines.push(check_width(
+                "HD-ratio mean diff CI halfwidth",
+                halfwidth,
+                self.max_hdratio_diff_ci_halfwidth,
+            ));
+            let (name, limit) = if self.compare_lower_bound {
+                ("HD-ratio mean diff lower bound", diff - halfwidth)
+            } else {
+                ("HD-ratio mean diff", diff)
+            };
+            lines.push(check_shift(
+                name,
+                limit,
+                ">=",
+                self.hdratio_min_improv,
+                limit >= self.hdratio_min_improv,
+            ));
+        }
+        lines
+    }
 }
 
 impl TimeBinSummarizer for HdRatioBootstrapDifferenceImprovementSummarizer {
@@ -170,31 +487,19 @@ impl TimeBinSummarizer for HdRatioBootstrapDifferenceImprovementSummarizer {
                     perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
                         bytes: bin.bytes_acked_sum,
                         diff_ci: diff,
-                        // BUG: Next line assumes symmetry; will impact shaded areas in CDFs.
-                        diff_ci_halfwidth: fullwidth / 2.0,
+                        diff_ci_lb: lb,
+                        diff_ci_ub: ub,
                         primary_peer_type: primary.peer_type,
                         alternate_peer_type: bestalt.peer_type,
+                        selection: perfstats::RouteSelection::alternate(bin, primary, bestalt),
                         bitmask: compute_bitmask(primary, bestalt),
                         is_shifted: limit >= self.hdratio_boot_min_improv,
+                        ..Default::default()
                     })
                 }
             }
         }
     }
-    fn get_routes<'s: 'd, 'd>(
-        &'s self,
-        pathid: &db::PathId,
-        time: u64,
-        db: &'d db::DB,
-    ) -> (&'d db::RouteInfo, &'d db::RouteInfo) {
-        let bin = &db.pathid2info[pathid].time2bin[&time];
-        (
-            bin.get_primary_route_hdratio().as_ref().unwrap(),
-            bin.get_best_alternate_hdratio(db::RouteInfo::compare_hdratio_bootstrap)
-                .as_ref()
-                .unwrap(),
-        )
-    }
     fn prefix(&self) -> String {
         format!(
             "hdratioboot--opp--bound-{}--diff-thresh-{:0.2}--diff-ci-{:0.2}",
@@ -203,6 +508,419 @@ impl TimeBinSummarizer for HdRatioBootstrapDifferenceImprovementSummarizer {
             self.max_hdratio_boot_diff_ci_fullwidth,
         )
     }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route_hdratio().as_deref();
+        let bestalt =
+            bin.get_best_alternate_hdratio(db::RouteInfo::compare_hdratio_bootstrap).as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "alternate: highest bootstrapped HD-ratio among valid alternates",
+            metric: Metric::HdRatio,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: Some(ANY_PEER_TYPE),
+            primary,
+            alternate: bestalt,
+        }
+        .describe(bin);
+        if let (Some(primary), Some(bestalt)) = (primary, bestalt) {
+            let (lb, diff, ub) = db::RouteInfo::hdratio_boot_diff_ci(bestalt, primary);
+            lines.push(check_width(
+                "HD-ratio bootstrap diff CI width",
+                ub - lb,
+                self.max_hdratio_boot_diff_ci_fullwidth,
+            ));
+            let (name, limit) = if self.compare_lower_bound {
+                ("HD-ratio bootstrap diff lower bound", lb)
+            } else {
+                ("HD-ratio bootstrap diff", diff)
+            };
+            lines.push(check_shift(
+                name,
+                limit,
+                ">=",
+                self.hdratio_boot_min_improv,
+                limit >= self.hdratio_boot_min_improv,
+            ));
+        }
+        lines
+    }
+}
+
+fn pareto_valid(rtinfo: &db::RouteInfo) -> bool {
+    db::RouteInfo::minrtt_valid(rtinfo) && db::RouteInfo::hdratio_valid(rtinfo)
+}
+
+/// Whether `rt1` has P50s at least as good as `rt2` on both metrics and
+/// strictly better on one.
+fn pareto_dominates(rt1: &db::RouteInfo, rt2: &db::RouteInfo) -> bool {
+    rt1.minrtt_ms_p50 <= rt2.minrtt_ms_p50
+        && rt1.hdratio_p50 >= rt2.hdratio_p50
+        && (rt1.minrtt_ms_p50 < rt2.minrtt_ms_p50 || rt1.hdratio_p50 > rt2.hdratio_p50)
+}
+
+impl MinRttHdRatioParetoSummarizer {
+    /// Returns `None` if either CI is too wide.
+    fn compare(
+        &self,
+        primary: &db::RouteInfo,
+        alternate: &db::RouteInfo,
+    ) -> Option<perfstats::ParetoStats> {
+        let (rtt_diff, rtt_halfwidth) = db::RouteInfo::minrtt_median_diff_ci(primary, alternate);
+        let (hd_diff, hd_halfwidth) = db::RouteInfo::hdratio_median_diff_ci(alternate, primary);
+        if rtt_halfwidth > self.max_minrtt50_diff_ci_halfwidth
+            || hd_halfwidth > self.max_hdratio50_diff_ci_halfwidth
+        {
+            return None;
+        }
+        let rtt_better: bool = rtt_diff - rtt_halfwidth >= self.minrtt50_min_improv;
+        let hd_better: bool = hd_diff - hd_halfwidth >= self.hdratio50_min_improv;
+        let rtt_loss: bool = rtt_diff + rtt_halfwidth < 0.0;
+        let hd_loss: bool = hd_diff + hd_halfwidth < 0.0;
+        let outcome = match (rtt_better, hd_better) {
+            (true, true) => perfstats::ParetoOutcome::BothBetter,
+            (true, false) if !hd_loss => perfstats::ParetoOutcome::OneBetter,
+            (false, true) if !rtt_loss => perfstats::ParetoOutcome::OneBetter,
+            (true, false) | (false, true) => perfstats::ParetoOutcome::TradeOff,
+            (false, false) => perfstats::ParetoOutcome::NoOpportunity,
+        };
+        Some(perfstats::ParetoStats {
+            minrtt50_improv: (rtt_diff, rtt_halfwidth),
+            hdratio50_improv: (hd_diff, hd_halfwidth),
+            outcome,
+        })
+    }
+
+    /// Alternates valid for both metrics, with their index in
+    /// `bin.num2route`.
+    fn alternates(bin: &db::TimeBin) -> Vec<(usize, &db::RouteInfo)> {
+        bin.num2route
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(i, rtopt)| rtopt.as_deref().map(|rtinfo| (i, rtinfo)))
+            .filter(|(_, rtinfo)| rtinfo.apm_route_num != 1 && pareto_valid(rtinfo))
+            .collect()
+    }
+
+    /// Alternates whose CIs against `primary` are narrow enough and
+    /// that are not dominated by another such alternate.  The CI filter
+    /// runs first so that a wide-CI alternate cannot hide the ones it
+    /// dominates.
+    fn frontier<'d>(
+        &self,
+        primary: &db::RouteInfo,
+        alternates: &[(usize, &'d db::RouteInfo)],
+    ) -> Vec<(usize, &'d db::RouteInfo, perfstats::ParetoStats)> {
+        let comparable: Vec<(usize, &db::RouteInfo, perfstats::ParetoStats)> = alternates
+            .iter()
+            .filter_map(|&(i, alt)| self.compare(primary, alt).map(|stats| (i, alt, stats)))
+            .collect();
+        comparable
+            .iter()
+            .filter(|(_, rtinfo, _)| {
+                !comparable.iter().any(|(_, other, _)| pareto_dominates(other, rtinfo))
+            })
+            .copied()
+            .collect()
+    }
+
+    fn choose<'d>(&self, bin: &'d db::TimeBin) -> ParetoChoice<'d> {
+        let primary: &db::RouteInfo = match bin.get_primary_route(pareto_valid).as_deref() {
+            None => return ParetoChoice::NoRoute,
+            Some(primary) => primary,
+        };
+        let alternates = MinRttHdRatioParetoSummarizer::alternates(bin);
+        if alternates.is_empty() {
+            return ParetoChoice::NoRoute;
+        }
+        let lower_bounds = |stats: &perfstats::ParetoStats| {
+            (
+                stats.minrtt50_improv.0 - stats.minrtt50_improv.1,
+                stats.hdratio50_improv.0 - stats.hdratio50_improv.1,
+            )
+        };
+        let best =
+            self.frontier(primary, &alternates).into_iter().max_by(|(_, _, s1), (_, _, s2)| {
+                let (rtt1, hd1) = lower_bounds(s1);
+                let (rtt2, hd2) = lower_bounds(s2);
+                s1.outcome
+                    .cmp(&s2.outcome)
+                    .then_with(|| rtt1.total_cmp(&rtt2))
+                    .then_with(|| hd1.total_cmp(&hd2))
+            });
+        match best {
+            None => ParetoChoice::WideConfidenceInterval,
+            Some((_, alternate, stats)) => ParetoChoice::Valid(primary, alternate, stats),
+        }
+    }
+}
+
+impl TimeBinSummarizer for MinRttHdRatioParetoSummarizer {
+    fn summarize(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> perfstats::TimeBinSummary {
+        match self.choose(bin) {
+            ParetoChoice::NoRoute => perfstats::TimeBinSummary::NoRoute,
+            ParetoChoice::WideConfidenceInterval => {
+                perfstats::TimeBinSummary::WideConfidenceInterval
+            }
+            ParetoChoice::Valid(primary, bestalt, stats) => {
+                perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
+                    bytes: bin.bytes_acked_sum,
+                    diff_ci: stats.minrtt50_improv.0,
+                    diff_ci_lb: stats.minrtt50_improv.0 - stats.minrtt50_improv.1,
+                    diff_ci_ub: stats.minrtt50_improv.0 + stats.minrtt50_improv.1,
+                    primary_peer_type: primary.peer_type,
+                    alternate_peer_type: bestalt.peer_type,
+                    selection: perfstats::RouteSelection::alternate(bin, primary, bestalt),
+                    bitmask: compute_bitmask(primary, bestalt),
+                    is_shifted: stats.outcome >= perfstats::ParetoOutcome::OneBetter,
+                    pareto: Some(stats),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "pareto--opp--minrtt-thresh-{:0.2}--hdratio-thresh-{:0.2}--diff-ci-{:0.2}--hdratio-diff-ci-{:0.2}",
+            self.minrtt50_min_improv,
+            self.hdratio50_min_improv,
+            self.max_minrtt50_diff_ci_halfwidth,
+            self.max_hdratio50_diff_ci_halfwidth,
+        )
+    }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let primary = bin.get_primary_route(pareto_valid).as_deref();
+        let bestalt = match self.choose(bin) {
+            ParetoChoice::Valid(_, bestalt, _) => Some(bestalt),
+            _ => None,
+        };
+        let mut lines: Vec<String> = Selection {
+            rule: "alternate: best Pareto outcome among non-dominated valid alternates",
+            metric: Metric::Both,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: Some(ANY_PEER_TYPE),
+            primary,
+            alternate: bestalt,
+        }
+        .describe(bin);
+        let primary: &db::RouteInfo = match primary {
+            None => return lines,
+            Some(primary) => primary,
+        };
+        let alternates = MinRttHdRatioParetoSummarizer::alternates(bin);
+        let frontier = self.frontier(primary, &alternates);
+        for &(i, alternate) in alternates.iter() {
+            if self.compare(primary, alternate).is_none() {
+                lines.push(format!("r{} CI too wide: WideConfidenceInterval", i));
+                continue;
+            }
+            match frontier.iter().find(|(j, _, _)| *j == i) {
+                None => lines.push(format!("r{} dominated by another alternate", i)),
+                Some((_, _, stats)) => lines.push(format!(
+                    "r{} MinRTT P50 improvement {:0.3}+-{:0.3} HD-ratio P50 improvement {:0.3}+-{:0.3}: {:?}",
+                    i,
+                    stats.minrtt50_improv.0,
+                    stats.minrtt50_improv.1,
+                    stats.hdratio50_improv.0,
+                    stats.hdratio50_improv.1,
+                    stats.outcome
+                )),
+            }
+        }
+        lines
+    }
+}
+
+/// Upper tail probability of the standard normal distribution, from the
+/// complementary error function approximation in Numerical Recipes
+/// (fractional error below 1.2e-7).
+fn normal_sf(x: f64) -> f64 {
+    let z: f64 = (x / std::f64::consts::SQRT_2).abs();
+    let t: f64 = 1.0 / (1.0 + 0.5 * z);
+    let poly: f64 = -1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let erfc: f64 = t * (-z * z + poly).exp();
+    if x >= 0.0 {
+        0.5 * erfc
+    } else {
+        1.0 - 0.5 * erfc
+    }
+}
+
+/// Inverse of `normal_sf`, by bisection.
+fn normal_isf(p: f64) -> f64 {
+    let (mut lo, mut hi): (f64, f64) = (-40.0, 40.0);
+    for _ in 0..100 {
+        let mid: f64 = (lo + hi) / 2.0;
+        if normal_sf(mid) > p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+impl MinRtt50AnyAlternateSummarizer {
+    /// Tests every valid alternate, in `bin.num2route` order.  Returns
+    /// `None` if there is no valid primary or alternate route.
+    fn test_alternates<'d>(
+        &self,
+        bin: &'d db::TimeBin,
+    ) -> Option<(&'d db::RouteInfo, Vec<AlternateTest<'d>>)> {
+        let primary: &db::RouteInfo = bin.get_primary_route_minrtt().as_deref()?;
+        let mut tests: Vec<AlternateTest> = bin
+            .num2route
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(i, rtopt)| rtopt.as_deref().map(|rtinfo| (i, rtinfo)))
+            .filter(|(_, rtinfo)| rtinfo.apm_route_num != 1 && db::RouteInfo::minrtt_valid(rtinfo))
+            .map(|(index, alternate)| {
+                let (diff, halfwidth) = db::RouteInfo::minrtt_median_diff_ci(primary, alternate);
+                let stderr: f64 = f64::from(halfwidth / db::CONFIDENCE_Z);
+                let excess: f64 = f64::from(diff - self.minrtt50_min_improv);
+                let pvalue: f64 = if stderr > 0.0 {
+                    normal_sf(excess / stderr)
+                } else if excess >= 0.0 {
+                    0.0
+                } else {
+                    1.0
+                };
+                AlternateTest {
+                    index,
+                    alternate,
+                    diff,
+                    halfwidth,
+                    adjusted_halfwidth: halfwidth,
+                    pvalue,
+                    level: 0.0,
+                    better: false,
+                }
+            })
+            .collect();
+        if tests.is_empty() {
+            return None;
+        }
+
+        // the one-sided level at which the unadjusted CI lower bound
+        // reaches the threshold:
+        let alpha: f64 = normal_sf(f64::from(db::CONFIDENCE_Z));
+        let compared: usize = tests.len();
+        let mut order: Vec<usize> = (0..compared).collect();
+        order.sort_by(|&i, &j| tests[i].pvalue.total_cmp(&tests[j].pvalue));
+        let mut rejecting = true;
+        for (rank, &i) in order.iter().enumerate() {
+            let divisor: usize = match self.correction {
+                MultipleComparison::Bonferroni => compared,
+                MultipleComparison::Holm => compared - rank,
+            };
+            let test: &mut AlternateTest = &mut tests[i];
+            test.level = alpha / divisor as f64;
+            test.adjusted_halfwidth =
+                test.halfwidth / db::CONFIDENCE_Z * normal_isf(test.level) as f32;
+            let wide: bool = test.halfwidth > self.max_minrtt50_diff_ci_halfwidth;
+            test.better = rejecting && !wide && test.pvalue <= test.level;
+            // Holm stops rejecting at the first non-significant alternate
+            if self.correction == MultipleComparison::Holm && !test.better {
+                rejecting = false;
+            }
+        }
+        Some((primary, tests))
+    }
+
+    /// The alternate with the highest adjusted lower bound among those
+    /// with narrow enough CIs.
+    fn best<'a, 'd>(&self, tests: &'a [AlternateTest<'d>]) -> Option<&'a AlternateTest<'d>> {
+        tests.iter().filter(|test| test.halfwidth <= self.max_minrtt50_diff_ci_halfwidth).max_by(
+            |t1, t2| {
+                (t1.diff - t1.adjusted_halfwidth).total_cmp(&(t2.diff - t2.adjusted_halfwidth))
+            },
+        )
+    }
+}
+
+impl TimeBinSummarizer for MinRtt50AnyAlternateSummarizer {
+    fn summarize(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> perfstats::TimeBinSummary {
+        let (primary, tests) = match self.test_alternates(bin) {
+            None => return perfstats::TimeBinSummary::NoRoute,
+            Some(result) => result,
+        };
+        let best: &AlternateTest = match self.best(&tests) {
+            None => return perfstats::TimeBinSummary::WideConfidenceInterval,
+            Some(best) => best,
+        };
+        let better: usize = tests.iter().filter(|test| test.better).count();
+        perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
+            bytes: bin.bytes_acked_sum,
+            diff_ci: best.diff,
+            diff_ci_lb: best.diff - best.adjusted_halfwidth,
+            diff_ci_ub: best.diff + best.adjusted_halfwidth,
+            primary_peer_type: primary.peer_type,
+            alternate_peer_type: best.alternate.peer_type,
+            selection: perfstats::RouteSelection::alternate(bin, primary, best.alternate),
+            bitmask: compute_bitmask(primary, best.alternate),
+            is_shifted: better > 0,
+            alternates: Some(perfstats::AlternateCounts {
+                compared: tests.len() as u8,
+                better: better as u8,
+            }),
+            ..Default::default()
+        })
+    }
+    fn prefix(&self) -> String {
+        format!(
+            "minrtt50--any-opp--holm-{}--diff-thresh-{:0.2}--diff-ci-{:0.2}",
+            self.correction == MultipleComparison::Holm,
+            self.minrtt50_min_improv,
+            self.max_minrtt50_diff_ci_halfwidth,
+        )
+    }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let result = self.test_alternates(bin);
+        let best = result.as_ref().and_then(|(_, tests)| self.best(tests));
+        let mut lines: Vec<String> = Selection {
+            rule: match self.correction {
+                MultipleComparison::Bonferroni => {
+                    "alternate: highest Bonferroni-adjusted MinRTT P50 diff lower bound"
+                }
+                MultipleComparison::Holm => {
+                    "alternate: highest Holm-adjusted MinRTT P50 diff lower bound"
+                }
+            },
+            metric: Metric::MinRtt,
+            primary_bitmask: ANY_PEER_TYPE,
+            alternate_bitmask: Some(ANY_PEER_TYPE),
+            primary: bin.get_primary_route_minrtt().as_deref(),
+            alternate: best.map(|test| test.alternate),
+        }
+        .describe(bin);
+        for test in result.iter().flat_map(|(_, tests)| tests.iter()) {
+            let status: &str = if test.halfwidth > self.max_minrtt50_diff_ci_halfwidth {
+                "WideConfidenceInterval"
+            } else if test.better {
+                "better"
+            } else {
+                "not better"
+            };
+            lines.push(format!(
+                "r{} MinRTT P50 diff {:0.3}+-{:0.3} (unadjusted +-{:0.3}) p-value {:0.4} level {:0.4}: {}",
+                test.index,
+                test.diff,
+                test.adjusted_halfwidth,
+                test.halfwidth,
+                test.pvalue,
+                test.level,
+                status
+            ));
+        }
+        lines
+    }
 }
 
 fn compute_bitmask(primary: &db::RouteInfo, bestalt: &db::RouteInfo) -> u8 {
@@ -222,9 +940,9 @@ fn compute_bitmask(primary: &db::RouteInfo, bestalt: &db::RouteInfo) -> u8 {
 // Return triple is total_shifted, total_shifted_longer, total_shifted_alt_is_prepended_more
 pub(crate) fn compute_opportunity_vs_relationship(
     dbsum: &perfstats::DBSummary,
-) -> HashMap<(db::PeerType, db::PeerType), (u128, u128, u128)> {
-    let mut peering2counters: HashMap<(db::PeerType, db::PeerType), (u128, u128, u128)> =
-        HashMap::new();
+) -> BTreeMap<(db::PeerType, db::PeerType), (u128, u128, u128)> {
+    let mut peering2counters: BTreeMap<(db::PeerType, db::PeerType), (u128, u128, u128)> =
+        BTreeMap::new();
     for psum in dbsum.pathid2summary.values() {
         for binstats in psum.time2binstats.values() {
             if !binstats.is_shifted {
@@ -258,11 +976,11 @@ pub(crate) fn compute_opportunity_vs_relationship(
 
 pub fn dump_opportunity_vs_relationship(
     dbsum: &perfstats::DBSummary,
-    path: &PathBuf,
+    path: &Path,
 ) -> Result<(), io::Error> {
     let peering2counters = compute_opportunity_vs_relationship(dbsum);
 
-    let mut filepath = path.clone();
+    let mut filepath = path.to_path_buf();
     filepath.push("opp-vs-relationship.txt");
     let file =
         fs::OpenOptions::new().read(true).write(true).truncate(true).create(true).open(filepath)?;
@@ -275,7 +993,7 @@ pub fn dump_opportunity_vs_relationship(
         )?;
     }
 
-    let mut peering2bigint: HashMap<(u8, u8), (String, String, String)> = HashMap::new();
+    let mut peering2bigint: BTreeMap<(u8, u8), (String, String, String)> = BTreeMap::new();
     for ((pritype, alttype), (valid, longer, prepended_more)) in peering2counters {
         peering2bigint.insert(
             (pritype as u8, alttype as u8),
@@ -283,7 +1001,7 @@ pub fn dump_opportunity_vs_relationship(
         );
     }
 
-    let mut filepath = path.clone();
+    let mut filepath = path.to_path_buf();
     filepath.push("opp-vs-relationship.pickle");
     let file =
         fs::OpenOptions::new().read(true).write(true).truncate(true).create(true).open(filepath)?;
@@ -371,6 +1089,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_minrtt_percentile_lower_bound() {
+        let pathid: db::PathId = db::tests::make_path_id();
+
+        let sum = MinRttPercentileImprovementSummarizer {
+            percentile: db::MinRttPercentile::P25,
+            minrtt_min_improv: 0.0,
+            max_minrtt_diff_ci_halfwidth: 6.0,
+            compare_lower_bound: true,
+        };
+        assert!(sum.prefix() == "minrtt-p25--opp--bound-true--diff-thresh-0.00--diff-ci-6.00");
+
+        // P50s are equal, but the alternate's P25 is 10 ms lower.
+        let timebin = db::TimeBin::mock_minrtt_p25(0, 30, 20, 4);
+        if let perfstats::TimeBinSummary::Valid(binstats) = sum.summarize(&pathid, &timebin) {
+            assert!(binstats.is_shifted);
+            assert!((binstats.diff_ci - 10.0).abs() < 1e-6);
+        } else {
+            unreachable!();
+        }
+
+        let timebin = db::TimeBin::mock_minrtt_p25(0, 30, 20, 8);
+        let binsum = sum.summarize(&pathid, &timebin);
+        assert!(binsum == perfstats::TimeBinSummary::WideConfidenceInterval);
+
+        // Exports without the percentile have no valid routes.
+        let timebin = db::TimeBin::mock_minrtt_p50(0, 30, 20, 4);
+        assert!(sum.summarize(&pathid, &timebin) == perfstats::TimeBinSummary::NoRoute);
+    }
+
     #[test]
     fn test_hdratio50_lower_bound() {
         let _pathid: db::PathId = db::tests::make_path_id();
@@ -388,7 +1136,7 @@ mod tests {
         if let perfstats::TimeBinSummary::Valid(binstats) = binsum {
             assert!(!binstats.is_shifted);
             assert!((binstats.diff_ci - 0.1).abs() < 1e-6);
-            assert!((binstats.diff_ci_halfwidth - 0.14).abs() < 0.01);
+            assert!((binstats.diff_ci_halfwidth() - 0.14).abs() < 0.01);
         } else {
             unreachable!();
         }
@@ -424,7 +1172,7 @@ mod tests {
         if let perfstats::TimeBinSummary::Valid(binstats) = binsum {
             assert!(binstats.is_shifted);
             assert!((binstats.diff_ci - 0.25).abs() < 1e-6);
-            assert!((binstats.diff_ci_halfwidth - 0.14).abs() < 0.01);
+            assert!((binstats.diff_ci_halfwidth() - 0.14).abs() < 0.01);
         } else {
             unreachable!();
         }
@@ -435,10 +1183,42 @@ mod tests {
         if let perfstats::TimeBinSummary::Valid(binstats) = binsum {
             assert!(!binstats.is_shifted);
             assert!((binstats.diff_ci - 0.1).abs() < 1e-6);
-            assert!((binstats.diff_ci_halfwidth - 0.14).abs() < 0.01);
+            assert!((binstats.diff_ci_halfwidth() - 0.14).abs() < 0.01);
+        } else {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn test_hdratio_mean_lower_bound() {
+        let pathid: db::PathId = db::tests::make_path_id();
+
+        let sum = HdRatioMeanImprovementSummarizer {
+            hdratio_min_improv: 0.05,
+            max_hdratio_diff_ci_halfwidth: 0.1,
+            compare_lower_bound: true,
+        };
+
+        // halfwidth = 2 * (2 * 0.1 / 100).sqrt() = 0.089
+        let timebin = db::TimeBin::mock_hdratio_mean(0, 0.7, 0.9, 0.1);
+        if let perfstats::TimeBinSummary::Valid(binstats) = sum.summarize(&pathid, &timebin) {
+            assert!(binstats.is_shifted);
+            assert!((binstats.diff_ci - 0.2).abs() < 1e-6);
+            assert!((binstats.diff_ci_halfwidth() - 0.0894).abs() < 1e-3);
+        } else {
+            unreachable!();
+        }
+
+        let timebin = db::TimeBin::mock_hdratio_mean(0, 0.8, 0.9, 0.1);
+        if let perfstats::TimeBinSummary::Valid(binstats) = sum.summarize(&pathid, &timebin) {
+            assert!(!binstats.is_shifted);
         } else {
             unreachable!();
         }
+
+        let timebin = db::TimeBin::mock_hdratio_mean(0, 0.7, 0.9, 0.2);
+        let binsum = sum.summarize(&pathid, &timebin);
+        assert!(binsum == perfstats::TimeBinSummary::WideConfidenceInterval);
     }
 
     #[test]
@@ -465,7 +1245,20 @@ mod tests {
         if let perfstats::TimeBinSummary::Valid(binstats) = binsum {
             assert!(binstats.is_shifted);
             assert!((binstats.diff_ci - 0.25).abs() < 1e-6);
-            assert!((binstats.diff_ci_halfwidth - 0.05).abs() < 1e-6);
+            assert!((binstats.diff_ci_halfwidth() - 0.05).abs() < 1e-6);
+        } else {
+            unreachable!();
+        }
+
+        // Bootstrapped CIs need not be symmetric around the difference.
+        let timebin = db::TimeBin::mock_hdratio_boot(0, 0.70, 0.95, 0.22, 0.35);
+
+        let binsum = sum2.summarize(&_pathid, &timebin);
+        if let perfstats::TimeBinSummary::Valid(binstats) = binsum {
+            assert!(binstats.is_shifted);
+            assert!((binstats.diff_ci - 0.25).abs() < 1e-6);
+            assert!((binstats.diff_ci_lb - 0.22).abs() < 1e-6);
+            assert!((binstats.diff_ci_ub - 0.35).abs() < 1e-6);
         } else {
             unreachable!();
         }
@@ -476,7 +1269,7 @@ mod tests {
         if let perfstats::TimeBinSummary::Valid(binstats) = binsum {
             assert!(!binstats.is_shifted);
             assert!((binstats.diff_ci - 0.15).abs() < 1e-6);
-            assert!((binstats.diff_ci_halfwidth - 0.05).abs() < 1e-6);
+            assert!((binstats.diff_ci_halfwidth() - 0.05).abs() < 1e-6);
         } else {
             unreachable!();
         }
@@ -490,12 +1283,150 @@ mod tests {
         if let perfstats::TimeBinSummary::Valid(binstats) = binsum {
             assert!(!binstats.is_shifted);
             assert!((binstats.diff_ci - (-0.25)).abs() < 1e-6);
-            assert!((binstats.diff_ci_halfwidth - 0.05).abs() < 1e-6);
+            assert!((binstats.diff_ci_halfwidth() - 0.05).abs() < 1e-6);
         } else {
             unreachable!();
         }
     }
 
+    #[test]
+    fn test_pareto_outcomes() {
+        let pathid: db::PathId = db::tests::make_path_id();
+        let sum = MinRttHdRatioParetoSummarizer {
+            minrtt50_min_improv: 5.0,
+            hdratio50_min_improv: 0.05,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.1,
+        };
+        let outcome = |timebin: &db::TimeBin| match sum.summarize(&pathid, timebin) {
+            perfstats::TimeBinSummary::Valid(binstats) => {
+                let pareto = binstats.pareto.unwrap();
+                assert!(
+                    binstats.is_shifted == (pareto.outcome >= perfstats::ParetoOutcome::OneBetter)
+                );
+                (pareto.outcome, binstats.diff_ci)
+            }
+            _ => unreachable!(),
+        };
+
+        // ci_halfwidth = 2 * (1 + 1).sqrt() = 2.83, HD-ratio P50 is 1.0
+        let mut timebin = db::TimeBin::mock_minrtt_p50(0, 50, 30, 1);
+        assert!(outcome(&timebin) == (perfstats::ParetoOutcome::OneBetter, 20.0));
+
+        timebin.num2route[1].as_mut().unwrap().hdratio_p50 = 0.8;
+        assert!(outcome(&timebin) == (perfstats::ParetoOutcome::TradeOff, 20.0));
+
+        // r3 is dominated by r2, r1 and r2 are on the frontier
+        timebin.num2route[2] = Some(Box::new(db::RouteInfo::mock_minrtt_p50(3, 40, 1)));
+        let mut dominated = db::RouteInfo::mock_minrtt_p50(4, 45, 1);
+        dominated.hdratio_p50 = 0.5;
+        timebin.num2route[3] = Some(Box::new(dominated));
+        let primary: &db::RouteInfo = timebin.num2route[0].as_deref().unwrap();
+        let alternates = MinRttHdRatioParetoSummarizer::alternates(&timebin);
+        assert!(sum.frontier(primary, &alternates).len() == 2);
+        assert!(outcome(&timebin) == (perfstats::ParetoOutcome::OneBetter, 10.0));
+
+        timebin.num2route[0].as_mut().unwrap().hdratio_p50 = 0.9;
+        assert!(outcome(&timebin) == (perfstats::ParetoOutcome::BothBetter, 10.0));
+        let lines: Vec<String> = sum.explain(&pathid, &timebin);
+        assert!(lines.iter().any(|l| l == "r3 dominated by another alternate"));
+        assert!(lines.iter().any(|l| l.starts_with("r2 ") && l.ends_with(": BothBetter")));
+
+        let narrow = MinRttHdRatioParetoSummarizer {
+            max_minrtt50_diff_ci_halfwidth: 1.0,
+            ..sum
+        };
+        let binsum = narrow.summarize(&pathid, &timebin);
+        assert!(binsum == perfstats::TimeBinSummary::WideConfidenceInterval);
+
+        timebin.num2route[0].as_mut().unwrap().hdratio_num_samples = 10;
+        let binsum = sum.summarize(&pathid, &timebin);
+        assert!(binsum == perfstats::TimeBinSummary::NoRoute);
+
+        // r2 dominates r1 but its CI is too wide, so r1 is still used.
+        let mut timebin = db::TimeBin::mock_minrtt_p50(0, 50, 30, 1);
+        let mut wide = db::RouteInfo::mock_minrtt_p50(3, 20, 1);
+        wide.minrtt_ms_p50_ci_halfwidth = 10;
+        timebin.num2route[2] = Some(Box::new(wide));
+        assert!(outcome(&timebin) == (perfstats::ParetoOutcome::OneBetter, 20.0));
+        let lines: Vec<String> = sum.explain(&pathid, &timebin);
+        assert!(lines.iter().any(|l| l == "r2 CI too wide: WideConfidenceInterval"));
+        assert!(lines.iter().any(|l| l.starts_with("r1 ") && l.ends_with(": OneBetter")));
+    }
+
+    #[test]
+    fn test_normal_tails() {
+        assert!((normal_sf(0.0) - 0.5).abs() < 1e-6);
+        assert!((normal_sf(1.96) - 0.025).abs() < 1e-4);
+        assert!((normal_sf(-1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_isf(0.025) - 1.96).abs() < 1e-3);
+        assert!((normal_isf(normal_sf(2.0) / 3.0) - 2.428).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_minrtt_any_alternate() {
+        let pathid: db::PathId = db::tests::make_path_id();
+        let bonferroni = MinRtt50AnyAlternateSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 10.0,
+            correction: MultipleComparison::Bonferroni,
+        };
+        let holm = MinRtt50AnyAlternateSummarizer {
+            correction: MultipleComparison::Holm,
+            ..bonferroni
+        };
+        let counts = |sum: &MinRtt50AnyAlternateSummarizer, timebin: &db::TimeBin| match sum
+            .summarize(&pathid, timebin)
+        {
+            perfstats::TimeBinSummary::Valid(binstats) => {
+                let counts = binstats.alternates.unwrap();
+                assert!(binstats.is_shifted == (counts.better > 0));
+                (counts.compared, counts.better)
+            }
+            _ => unreachable!(),
+        };
+
+        // ci_halfwidth = 2 * (1.5**2 + 1.5**2).sqrt() = 4.24, so diff 10
+        // is 2.36 stderrs above the threshold; a single alternate is
+        // tested at the unadjusted level (z 2)
+        let mut timebin = db::TimeBin::mock_minrtt_p50(0, 60, 50, 3);
+        assert!(counts(&bonferroni, &timebin) == (1, 1));
+        assert!(counts(&holm, &timebin) == (1, 1));
+
+        // with three alternates, Bonferroni needs z 2.43; so does the
+        // first Holm step, which stops Holm from rejecting anything
+        timebin.num2route[2] = Some(Box::new(db::RouteInfo::mock_minrtt_p50(3, 50, 3)));
+        timebin.num2route[3] = Some(Box::new(db::RouteInfo::mock_minrtt_p50(4, 80, 3)));
+        assert!(counts(&bonferroni, &timebin) == (3, 0));
+        assert!(counts(&holm, &timebin) == (3, 0));
+
+        // once diff 15 passes the first step, Holm tests diff 10 at z 2.28
+        timebin.num2route[1].as_mut().unwrap().minrtt_ms_p50 = 45;
+        assert!(counts(&bonferroni, &timebin) == (3, 1));
+        assert!(counts(&holm, &timebin) == (3, 2));
+        let lines: Vec<String> = holm.explain(&pathid, &timebin);
+        assert!(lines.iter().filter(|l| l.ends_with(": better")).count() == 2);
+
+        // NaN p-values sort last instead of panicking and are never
+        // significant.
+        let nan = MinRtt50AnyAlternateSummarizer {
+            minrtt50_min_improv: f32::NAN,
+            ..holm
+        };
+        assert!(counts(&nan, &timebin) == (3, 0));
+
+        let narrow = MinRtt50AnyAlternateSummarizer {
+            max_minrtt50_diff_ci_halfwidth: 1.0,
+            ..holm
+        };
+        let binsum = narrow.summarize(&pathid, &timebin);
+        assert!(binsum == perfstats::TimeBinSummary::WideConfidenceInterval);
+
+        timebin.num2route[0].as_mut().unwrap().minrtt_num_samples = 10;
+        let binsum = holm.summarize(&pathid, &timebin);
+        assert!(binsum == perfstats::TimeBinSummary::NoRoute);
+    }
+
     #[test]
     fn test_opportunity_vs_peering_relationship() {}
 }
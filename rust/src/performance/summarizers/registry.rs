@@ -0,0 +1,1307 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::performance::db;
+use crate::performance::perfstats::TimeBinSummarizer;
+use crate::performance::summarizers::{combinators, degradation, opportunity, relationships};
+use crate::performance::sweep::ConfigError;
+
+pub type Params = BTreeMap<String, toml::Value>;
+type BuildFn = fn(&Params, &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamKind {
+    Float,
+    Int,
+    Bool,
+    /// A word of letters and digits, e.g., a country code.
+    Str,
+    /// Another summarizer's spec, written as `[name:key=value,...]` in
+    /// spec strings and as a table with a `type` key in TOML.
+    Summarizer,
+}
+
+pub struct Param {
+    pub name: &'static str,
+    pub kind: ParamKind,
+    pub default: Option<f64>,
+}
+
+/// A named summarizer constructor.
+pub struct SummarizerType {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [Param],
+    /// The `prefix()` of summarizers of this type, with a `{name}`
+    /// placeholder for each parameter; used to recover a
+    /// `SummarizerSpec` from an output directory name.
+    pub prefix: &'static str,
+    build: BuildFn,
+}
+
+pub(crate) const fn param(name: &'static str, kind: ParamKind) -> Param {
+    Param {
+        name,
+        kind,
+        default: None,
+    }
+}
+
+pub(crate) const fn optional(name: &'static str, kind: ParamKind, default: f64) -> Param {
+    Param {
+        name,
+        kind,
+        default: Some(default),
+    }
+}
+
+const MINRTT50_OPP_PARAMS: [Param; 4] = [
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("hdratio_diff_ci", ParamKind::Float),
+    param("bound", ParamKind::Bool),
+];
+const HDRATIO_OPP_PARAMS: [Param; 3] = [
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("bound", ParamKind::Bool),
+];
+const MINRTT_PCT_OPP_PARAMS: [Param; 4] = [
+    param("percentile", ParamKind::Int),
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("bound", ParamKind::Bool),
+];
+const PARETO_OPP_PARAMS: [Param; 4] = [
+    param("minrtt_thresh", ParamKind::Float),
+    param("hdratio_thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("hdratio_diff_ci", ParamKind::Float),
+];
+const ANY_OPP_PARAMS: [Param; 3] = [
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("holm", ParamKind::Bool),
+];
+const DEG_PARAMS: [Param; 4] = [
+    param("baseline", ParamKind::Float),
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("base_ci", ParamKind::Float),
+];
+const MINRTT_PCT_DEG_PARAMS: [Param; 5] = [
+    param("percentile", ParamKind::Int),
+    param("baseline", ParamKind::Float),
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("base_ci", ParamKind::Float),
+];
+const WINDOW_DEG_PARAMS: [Param; 5] = [
+    param("baseline", ParamKind::Float),
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("base_ci", ParamKind::Float),
+    param("days", ParamKind::Int),
+];
+const DISTINCT_DEG_PARAMS: [Param; 5] = [
+    param("baseline", ParamKind::Float),
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("base_ci", ParamKind::Float),
+    param("min_frac", ParamKind::Float),
+];
+const REL_PARAMS: [Param; 5] = [
+    param("primary", ParamKind::Int),
+    param("alternate", ParamKind::Int),
+    param("thresh", ParamKind::Float),
+    param("diff_ci", ParamKind::Float),
+    param("bound", ParamKind::Bool),
+];
+
+const FILTER_PEER_TYPES_PARAMS: [Param; 2] =
+    [param("inner", ParamKind::Summarizer), param("bitmask", ParamKind::Int)];
+const FILTER_CONTINENT_PARAMS: [Param; 2] =
+    [param("inner", ParamKind::Summarizer), param("continent", ParamKind::Str)];
+const FILTER_COUNTRY_PARAMS: [Param; 2] =
+    [param("inner", ParamKind::Summarizer), param("country", ParamKind::Str)];
+const FILTER_PREFIX_LEN_PARAMS: [Param; 3] = [
+    param("inner", ParamKind::Summarizer),
+    param("min", ParamKind::Int),
+    param("max", ParamKind::Int),
+];
+const COMBINATION_PARAMS: [Param; 2] =
+    [param("left", ParamKind::Summarizer), param("right", ParamKind::Summarizer)];
+const INVERTED_PARAMS: [Param; 2] =
+    [param("inner", ParamKind::Summarizer), param("thresh", ParamKind::Float)];
+
+pub const SUMMARIZER_TYPES: [SummarizerType; 32] = [
+    SummarizerType {
+        name: "minrtt50-opp",
+        description: "MinRTT P50 improvement of the best alternate over the primary route",
+        params: &MINRTT50_OPP_PARAMS,
+        prefix: "minrtt50--opp--bound-{bound}--diff-thresh-{thresh}--diff-ci-{diff_ci}--hdratio-diff-ci-{hdratio_diff_ci}",
+        build: build_minrtt50_opp,
+    },
+    SummarizerType {
+        name: "hdratio50-opp",
+        description: "HD-ratio P50 improvement of the best alternate over the primary route",
+        params: &HDRATIO_OPP_PARAMS,
+        prefix: "hdratio50--opp--bound-{bound}--diff-thresh-{thresh}--diff-ci-{diff_ci}",
+        build: build_hdratio50_opp,
+    },
+    SummarizerType {
+        name: "hdratio-mean-opp",
+        description: "Mean HD-ratio improvement of the best alternate over the primary route",
+        params: &HDRATIO_OPP_PARAMS,
+        prefix: "hdratio--opp--bound-{bound}--diff-thresh-{thresh}--diff-ci-{diff_ci}",
+        build: build_hdratio_mean_opp,
+    },
+    SummarizerType {
+        name: "hdratio-boot-opp",
+        description: "HD-ratio improvement of the best alternate with bootstrapped CIs",
+        params: &HDRATIO_OPP_PARAMS,
+        prefix: "hdratioboot--opp--bound-{bound}--diff-thresh-{thresh}--diff-ci-{diff_ci}",
+        build: build_hdratio_boot_opp,
+    },
+    SummarizerType {
+        name: "minrtt-pct-opp",
+        description: "MinRTT P10, P25 or P50 improvement of the best alternate over the primary route",
+        params: &MINRTT_PCT_OPP_PARAMS,
+        prefix: "minrtt-p{percentile}--opp--bound-{bound}--diff-thresh-{thresh}--diff-ci-{diff_ci}",
+        build: build_minrtt_pct_opp,
+    },
+    SummarizerType {
+        name: "pareto-opp",
+        description: "Pareto-best alternate on both MinRTT P50 and HD-ratio P50",
+        params: &PARETO_OPP_PARAMS,
+        prefix: "pareto--opp--minrtt-thresh-{minrtt_thresh}--hdratio-thresh-{hdratio_thresh}--diff-ci-{diff_ci}--hdratio-diff-ci-{hdratio_diff_ci}",
+        build: build_pareto_opp,
+    },
+    SummarizerType {
+        name: "minrtt50-any-opp",
+        description: "MinRTT P50 improvement of any alternate, corrected for multiple comparisons",
+        params: &ANY_OPP_PARAMS,
+        prefix: "minrtt50--any-opp--holm-{holm}--diff-thresh-{thresh}--diff-ci-{diff_ci}",
+        build: build_minrtt50_any_opp,
+    },
+    SummarizerType {
+        name: "minrtt50-deg",
+        description: "MinRTT P50 degradation of the primary route against a baseline bin",
+        params: &DEG_PARAMS,
+        prefix: "minrtt50--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}",
+        build: build_minrtt50_deg,
+    },
+    SummarizerType {
+        name: "hdratio50-deg",
+        description: "HD-ratio P50 degradation of the primary route against a baseline bin",
+        params: &DEG_PARAMS,
+        prefix: "hdratio50--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}",
+        build: build_hdratio50_deg,
+    },
+    SummarizerType {
+        name: "hdratio-mean-deg",
+        description: "Mean HD-ratio degradation of the primary route against a baseline bin",
+        params: &DEG_PARAMS,
+        prefix: "hdratio--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}",
+        build: build_hdratio_mean_deg,
+    },
+    SummarizerType {
+        name: "minrtt-pct-deg",
+        description: "MinRTT P10, P25 or P50 degradation of the primary route against a baseline bin",
+        params: &MINRTT_PCT_DEG_PARAMS,
+        prefix: "minrtt-p{percentile}--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}",
+        build: build_minrtt_pct_deg,
+    },
+    SummarizerType {
+        name: "minrtt50-deg-trailing",
+        description: "MinRTT P50 degradation of the primary route against a trailing-window baseline bin",
+        params: &WINDOW_DEG_PARAMS,
+        prefix: "minrtt50--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}--trailing-days-{days}",
+        build: build_minrtt50_deg_trailing,
+    },
+    SummarizerType {
+        name: "minrtt50-deg-tod",
+        description: "MinRTT P50 degradation of the primary route against a same time-of-day baseline bin",
+        params: &WINDOW_DEG_PARAMS,
+        prefix: "minrtt50--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}--time-of-day-days-{days}",
+        build: build_minrtt50_deg_tod,
+    },
+    SummarizerType {
+        name: "minrtt50-deg-nexthops",
+        description: "MinRTT P50 degradation of the primary route against a per-nexthops baseline bin",
+        params: &DEG_PARAMS,
+        prefix: "minrtt50--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}--per-nexthops",
+        build: build_minrtt50_deg_nexthops,
+    },
+    SummarizerType {
+        name: "hdratio50-deg-trailing",
+        description: "HD-ratio P50 degradation of the primary route against a trailing-window baseline bin",
+        params: &WINDOW_DEG_PARAMS,
+        prefix: "hdratio50--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}--trailing-days-{days}",
+        build: build_hdratio50_deg_trailing,
+    },
+    SummarizerType {
+        name: "hdratio50-deg-tod",
+        description: "HD-ratio P50 degradation of the primary route against a same time-of-day baseline bin",
+        params: &WINDOW_DEG_PARAMS,
+        prefix: "hdratio50--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}--time-of-day-days-{days}",
+        build: build_hdratio50_deg_tod,
+    },
+    SummarizerType {
+        name: "hdratio50-deg-nexthops",
+        description: "HD-ratio P50 degradation of the primary route against a per-nexthops baseline bin",
+        params: &DEG_PARAMS,
+        prefix: "hdratio50--deg--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}--per-nexthops",
+        build: build_hdratio50_deg_nexthops,
+    },
+    SummarizerType {
+        name: "minrtt50-distinct-deg",
+        description: "MinRTT P50 degradation of the primary route against a baseline bin with the same nexthops",
+        params: &DISTINCT_DEG_PARAMS,
+        prefix: "minrtt50--deg-distinct--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}--min-frac-{min_frac}",
+        build: build_minrtt50_distinct_deg,
+    },
+    SummarizerType {
+        name: "hdratio50-distinct-deg",
+        description: "HD-ratio P50 degradation of the primary route against a baseline bin with the same nexthops",
+        params: &DISTINCT_DEG_PARAMS,
+        prefix: "hdratio50--deg-distinct--bound-true--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}--min-frac-{min_frac}",
+        build: build_hdratio50_distinct_deg,
+    },
+    SummarizerType {
+        name: "minrtt50-deg-attribution",
+        description: "MinRTT P50 degradation attributed to the primary route or to all routes",
+        params: &DEG_PARAMS,
+        prefix: "minrtt50--deg-attribution--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}",
+        build: build_minrtt50_deg_attribution,
+    },
+    SummarizerType {
+        name: "hdratio50-deg-attribution",
+        description: "HD-ratio P50 degradation attributed to the primary route or to all routes",
+        params: &DEG_PARAMS,
+        prefix: "hdratio50--deg-attribution--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}",
+        build: build_hdratio50_deg_attribution,
+    },
+    SummarizerType {
+        name: "hdratio-mean-deg-attribution",
+        description: "Mean HD-ratio degradation attributed to the primary route or to all routes",
+        params: &DEG_PARAMS,
+        prefix: "hdratio--deg-attribution--diff-thresh-{thresh}--diff-ci-{diff_ci}--base-ci-{base_ci}--baseline-{baseline}",
+        build: build_hdratio_mean_deg_attribution,
+    },
+    SummarizerType {
+        name: "minrtt50-rel",
+        description: "MinRTT P50 improvement between routes of two peer type bitmasks",
+        params: &REL_PARAMS,
+        prefix: "minrtt50--relationships-{primary}-{alternate}--bound-{bound}--diff-thresh-{thresh}--diff-ci-{diff_ci}",
+        build: build_minrtt50_rel,
+    },
+    SummarizerType {
+        name: "hdratio50-rel",
+        description: "HD-ratio P50 improvement between routes of two peer type bitmasks",
+        params: &REL_PARAMS,
+        prefix: "hdratio50--relationships-{primary}-{alternate}--bound-{bound}--diff-thresh-{thresh}--diff-ci-{diff_ci}",
+        build: build_hdratio50_rel,
+    },
+    SummarizerType {
+        name: "filter-primary",
+        description: "Bins of another summarizer whose primary route's peer type is in a bitmask",
+        params: &FILTER_PEER_TYPES_PARAMS,
+        prefix: "{inner}--filter-primary-{bitmask}",
+        build: build_filter_primary,
+    },
+    SummarizerType {
+        name: "filter-alternate",
+        description: "Bins of another summarizer whose alternate route's peer type is in a bitmask",
+        params: &FILTER_PEER_TYPES_PARAMS,
+        prefix: "{inner}--filter-alternate-{bitmask}",
+        build: build_filter_alternate,
+    },
+    SummarizerType {
+        name: "filter-continent",
+        description: "Bins of another summarizer on paths to clients in a continent",
+        params: &FILTER_CONTINENT_PARAMS,
+        prefix: "{inner}--filter-continent-{continent}",
+        build: build_filter_continent,
+    },
+    SummarizerType {
+        name: "filter-country",
+        description: "Bins of another summarizer on paths to clients in a country",
+        params: &FILTER_COUNTRY_PARAMS,
+        prefix: "{inner}--filter-country-{country}",
+        build: build_filter_country,
+    },
+    SummarizerType {
+        name: "filter-prefix-len",
+        description: "Bins of another summarizer on paths whose BGP prefix length is in a range",
+        params: &FILTER_PREFIX_LEN_PARAMS,
+        prefix: "{inner}--filter-prefix-len-{min}-{max}",
+        build: build_filter_prefix_len,
+    },
+    SummarizerType {
+        name: "and",
+        description: "Bins two summarizers both find shifted",
+        params: &COMBINATION_PARAMS,
+        prefix: "{left}--and--{right}--end",
+        build: build_and,
+    },
+    SummarizerType {
+        name: "or",
+        description: "Bins either of two summarizers finds shifted",
+        params: &COMBINATION_PARAMS,
+        prefix: "{left}--or--{right}--end",
+        build: build_or,
+    },
+    SummarizerType {
+        name: "inverted",
+        description: "Bins where the alternate of another summarizer is worse than the primary route",
+        params: &INVERTED_PARAMS,
+        prefix: "{inner}--inverted--diff-thresh-{thresh}",
+        build: build_inverted,
+    },
+];
+
+/// Returns the registered summarizer type called `name`.
+pub fn find(name: &str) -> Option<&'static SummarizerType> {
+    SUMMARIZER_TYPES.iter().find(|stype| stype.name == name)
+}
+
+impl SummarizerType {
+    /// A spec string template, e.g., `hdratio50-opp:thresh=<Float>,...`.
+    pub fn usage(&self) -> String {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| match p.default {
+                None => format!("{}=<{:?}>", p.name, p.kind),
+                Some(default) => format!("[{}=<{:?}>, default {}]", p.name, p.kind, default),
+            })
+            .collect();
+        format!("{}:{}", self.name, params.join(","))
+    }
+
+    /// Matches `prefix` against this type's prefix template and returns
+    /// the parameter values it encodes.
+    fn parse_prefix(&self, prefix: &str) -> Option<Params> {
+        self.match_template(self.prefix, prefix, Params::new())
+    }
+
+    /// Matches `rest` against `template`, adding the values of its
+    /// placeholders to `params`.  Nested summarizers may contain the
+    /// literal that follows them, so each occurrence is tried.
+    fn match_template(&self, template: &str, rest: &str, params: Params) -> Option<Params> {
+        match template.find('{') {
+            Some(0) => {
+                let end: usize = template.find('}')?;
+                let name: &str = &template[1..end];
+                let template: &str = &template[end + 1..];
+                let literal: &str = &template[..template.find('{').unwrap_or(template.len())];
+                let param: &Param = self.params.iter().find(|p| p.name == name)?;
+                let vlens: Vec<usize> = if literal.is_empty() {
+                    vec![rest.len()]
+                } else if param.kind == ParamKind::Summarizer {
+                    rest.match_indices(literal).map(|(i, _)| i).collect()
+                } else {
+                    vec![rest.find(literal)?]
+                };
+                vlens.into_iter().find_map(|vlen| {
+                    let value: toml::Value = match param.kind {
+                        ParamKind::Summarizer => {
+                            SummarizerSpec::from_prefix(&rest[..vlen])?.to_value()
+                        }
+                        kind => parse_value(&rest[..vlen], kind).ok()?,
+                    };
+                    let mut params: Params = params.clone();
+                    params.insert(name.to_string(), value);
+                    self.match_template(template, &rest[vlen..], params)
+                })
+            }
+            Some(start) => {
+                let rest: &str = rest.strip_prefix(&template[..start])?;
+                self.match_template(&template[start..], rest, params)
+            }
+            None if rest == template => Some(params),
+            None => None,
+        }
+    }
+
+    /// Fills in the defaults of parameters missing from `params`.
+    fn fill_defaults(&self, params: &mut Params, ctx: &str) -> Result<(), ConfigError> {
+        for param in self.params.iter() {
+            if params.contains_key(param.name) {
+                continue;
+            }
+            let default: f64 = param
+                .default
+                .ok_or_else(|| ConfigError::new(&format!("{}.{}", ctx, param.name), "missing"))?;
+            params.insert(param.name.to_string(), default_value(param.kind, default));
+        }
+        Ok(())
+    }
+}
+
+/// A summarizer type and one value for each of its parameters.
+///
+/// Specs are written as `name:key=value,key=value`, e.g.,
+/// `minrtt50-deg:baseline=0.1,thresh=5,diff_ci=10,base_ci=20`.
+/// Summarizer parameters are specs in brackets, e.g.,
+/// `filter-country:inner=[minrtt50-deg:baseline=0.1,...],country=BR`,
+/// and are stored as tables with a `type` key, as in sweep files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SummarizerSpec {
+    pub name: String,
+    pub params: Params,
+}
+
+impl SummarizerSpec {
+    pub fn parse(text: &str) -> Result<SummarizerSpec, ConfigError> {
+        let (name, paramstr) = match text.find(':') {
+            Some(i) => (&text[..i], &text[i + 1..]),
+            None => (text, ""),
+        };
+        let stype: &SummarizerType =
+            find(name).ok_or_else(|| ConfigError::new(name, "unknown summarizer type"))?;
+        let mut params = Params::new();
+        for keyvalue in split_params(paramstr).into_iter().filter(|kv| !kv.is_empty()) {
+            let (key, value) = match keyvalue.find('=') {
+                Some(i) => (&keyvalue[..i], &keyvalue[i + 1..]),
+                None => {
+                    return Err(ConfigError::new(
+                        name,
+                        &format!("expected key=value in {}", keyvalue),
+                    ))
+                }
+            };
+            let fullkey: String = format!("{}.{}", name, key);
+            let param: &Param = stype
+                .params
+                .iter()
+                .find(|p| p.name == key)
+                .ok_or_else(|| ConfigError::new(&fullkey, "unknown parameter"))?;
+            let nested: Option<&str> =
+                value.strip_prefix('[').and_then(|value| value.strip_suffix(']'));
+            let value: toml::Value = match (param.kind, nested) {
+                (ParamKind::Summarizer, Some(text)) => SummarizerSpec::parse(text)
+                    .map_err(|e| ConfigError::new(&format!("{}.{}", fullkey, e.key), &e.message))?
+                    .to_value(),
+                (kind, _) => parse_value(value, kind).map_err(|_| {
+                    ConfigError::new(&fullkey, &format!("expected {:?} but got {}", kind, value))
+                })?,
+            };
+            if params.insert(key.to_string(), value).is_some() {
+                return Err(ConfigError::new(&fullkey, "set more than once"));
+            }
+        }
+        stype.fill_defaults(&mut params, name)?;
+        Ok(SummarizerSpec {
+            name: name.to_string(),
+            params,
+        })
+    }
+
+    /// Reads a spec from a table with a `type` key, e.g., a
+    /// `[[summarizer]]` of a sweep file without lists of values.
+    pub(crate) fn from_table(
+        table: &toml::Table,
+        ctx: &str,
+    ) -> Result<SummarizerSpec, ConfigError> {
+        let typekey: String = format!("{}.type", ctx);
+        let name: &str = table
+            .get("type")
+            .ok_or_else(|| ConfigError::new(&typekey, "missing"))?
+            .as_str()
+            .ok_or_else(|| ConfigError::new(&typekey, "expected a string"))?;
+        let stype: &SummarizerType = find(name).ok_or_else(|| {
+            ConfigError::new(&typekey, &format!("unknown summarizer type {}", name))
+        })?;
+        let mut params = Params::new();
+        for (key, value) in table.iter().filter(|(key, _)| *key != "type") {
+            let fullkey: String = format!("{}.{}", ctx, key);
+            let param: &Param = stype
+                .params
+                .iter()
+                .find(|p| p.name == key)
+                .ok_or_else(|| ConfigError::new(&fullkey, "unknown parameter"))?;
+            params.insert(key.clone(), coerce(value, param.kind, &fullkey)?);
+        }
+        stype.fill_defaults(&mut params, ctx)?;
+        Ok(SummarizerSpec {
+            name: name.to_string(),
+            params,
+        })
+    }
+
+    /// The spec as a table with a `type` key, as read by `from_table`.
+    pub fn to_value(&self) -> toml::Value {
+        let mut table = toml::Table::new();
+        table.insert("type".to_string(), toml::Value::String(self.name.clone()));
+        table.extend(self.params.iter().map(|(k, v)| (k.clone(), v.clone())));
+        toml::Value::Table(table)
+    }
+
+    /// Recovers the spec of a summarizer from its `prefix()`.  Values
+    /// are only as precise as the prefix.
+    pub fn from_prefix(prefix: &str) -> Option<SummarizerSpec> {
+        SUMMARIZER_TYPES.iter().find_map(|stype| {
+            stype.parse_prefix(prefix).map(|params| SummarizerSpec {
+                name: stype.name.to_string(),
+                params,
+            })
+        })
+    }
+
+    /// Builds the summarizer; degradation summarizers compute their
+    /// baselines from `db`.
+    pub fn build(&self, db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+        let stype: &SummarizerType = find(&self.name)
+            .ok_or_else(|| ConfigError::new(&self.name, "unknown summarizer type"))?;
+        (stype.build)(&self.params, db)
+            .map_err(|e| ConfigError::new(&format!("{}.{}", self.name, e.key), &e.message))
+    }
+}
+
+impl fmt::Display for SummarizerSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            let sep: char = if i == 0 {
+                ':'
+            } else {
+                ','
+            };
+            match value {
+                toml::Value::String(text) => write!(f, "{}{}={}", sep, key, text)?,
+                toml::Value::Table(table) => write!(f, "{}{}=[{}]", sep, key, nested_spec(table))?,
+                value => write!(f, "{}{}={}", sep, key, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The spec `SummarizerSpec::to_value` stored as `table`.
+fn nested_spec(table: &toml::Table) -> SummarizerSpec {
+    let mut params: Params = table.clone().into_iter().collect();
+    let name: String = match params.remove("type") {
+        Some(toml::Value::String(name)) => name,
+        _ => String::new(),
+    };
+    SummarizerSpec {
+        name,
+        params,
+    }
+}
+
+/// Splits `paramstr` at commas outside brackets.
+fn split_params(paramstr: &str) -> Vec<&str> {
+    let mut keyvalues: Vec<&str> = Vec::new();
+    let mut depth: usize = 0;
+    let mut start: usize = 0;
+    for (i, c) in paramstr.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                keyvalues.push(&paramstr[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    keyvalues.push(&paramstr[start..]);
+    keyvalues
+}
+
+fn build_minrtt50_opp(p: &Params, _db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(opportunity::MinRtt50ImprovementSummarizer {
+        minrtt50_min_improv: get_f32(p, "thresh")?,
+        max_minrtt50_diff_ci_halfwidth: get_f32(p, "diff_ci")?,
+        max_hdratio50_diff_ci_halfwidth: get_f32(p, "hdratio_diff_ci")?,
+        compare_lower_bound: get_bool(p, "bound")?,
+    }))
+}
+
+fn build_hdratio50_opp(
+    p: &Params,
+    _db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(opportunity::HdRatio50ImprovementSummarizer {
+        hdratio50_min_improv: get_f32(p, "thresh")?,
+        max_hdratio50_diff_ci_halfwidth: get_f32(p, "diff_ci")?,
+        compare_lower_bound: get_bool(p, "bound")?,
+    }))
+}
+
+fn build_hdratio_mean_opp(
+    p: &Params,
+    _db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(opportunity::HdRatioMeanImprovementSummarizer {
+        hdratio_min_improv: get_f32(p, "thresh")?,
+        max_hdratio_diff_ci_halfwidth: get_f32(p, "diff_ci")?,
+        compare_lower_bound: get_bool(p, "bound")?,
+    }))
+}
+
+fn build_hdratio_boot_opp(
+    p: &Params,
+    _db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(opportunity::HdRatioBootstrapDifferenceImprovementSummarizer {
+        hdratio_boot_min_improv: get_f32(p, "thresh")?,
+        max_hdratio_boot_diff_ci_fullwidth: get_f32(p, "diff_ci")?,
+        compare_lower_bound: get_bool(p, "bound")?,
+    }))
+}
+
+/// `percentile` must be one of the MinRTT percentiles in the exports.
+fn get_minrtt_percentile(p: &Params) -> Result<db::MinRttPercentile, ConfigError> {
+    db::MinRttPercentile::new(get_int(p, "percentile")?)
+        .ok_or_else(|| ConfigError::new("percentile", "must be 10, 25 or 50"))
+}
+
+fn build_minrtt_pct_opp(
+    p: &Params,
+    _db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(opportunity::MinRttPercentileImprovementSummarizer {
+        percentile: get_minrtt_percentile(p)?,
+        minrtt_min_improv: get_f32(p, "thresh")?,
+        max_minrtt_diff_ci_halfwidth: get_f32(p, "diff_ci")?,
+        compare_lower_bound: get_bool(p, "bound")?,
+    }))
+}
+
+fn build_pareto_opp(p: &Params, _db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(opportunity::MinRttHdRatioParetoSummarizer {
+        minrtt50_min_improv: get_f32(p, "minrtt_thresh")?,
+        hdratio50_min_improv: get_f32(p, "hdratio_thresh")?,
+        max_minrtt50_diff_ci_halfwidth: get_f32(p, "diff_ci")?,
+        max_hdratio50_diff_ci_halfwidth: get_f32(p, "hdratio_diff_ci")?,
+    }))
+}
+
+fn build_minrtt50_any_opp(
+    p: &Params,
+    _db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(opportunity::MinRtt50AnyAlternateSummarizer {
+        minrtt50_min_improv: get_f32(p, "thresh")?,
+        max_minrtt50_diff_ci_halfwidth: get_f32(p, "diff_ci")?,
+        correction: if get_bool(p, "holm")? {
+            opportunity::MultipleComparison::Holm
+        } else {
+            opportunity::MultipleComparison::Bonferroni
+        },
+    }))
+}
+
+fn build_minrtt50_deg(p: &Params, db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::MinRtt50LowerBoundDegradationSummarizer::new(
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        db,
+    )))
+}
+
+fn build_hdratio50_deg(p: &Params, db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::HdRatio50LowerBoundDegradationSummarizer::new(
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        db,
+    )))
+}
+
+fn build_hdratio_mean_deg(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::HdRatioMeanLowerBoundDegradationSummarizer::new(
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        db,
+    )))
+}
+
+fn build_minrtt_pct_deg(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::MinRttPercentileLowerBoundDegradationSummarizer::new(
+        get_minrtt_percentile(p)?,
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        db,
+    )))
+}
+
+/// `days` must be at least 1 so baseline windows are not empty.
+fn get_days(p: &Params) -> Result<u32, ConfigError> {
+    let days: u32 = get_int(p, "days")?;
+    if days == 0 {
+        return Err(ConfigError::new("days", "must be at least 1"));
+    }
+    Ok(days)
+}
+
+fn build_minrtt50_deg_with(
+    p: &Params,
+    db: &db::DB,
+    strategy: degradation::BaselineStrategy,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::MinRtt50LowerBoundDegradationSummarizer::with_strategy(
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        strategy,
+        db,
+    )))
+}
+
+fn build_hdratio50_deg_with(
+    p: &Params,
+    db: &db::DB,
+    strategy: degradation::BaselineStrategy,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::HdRatio50LowerBoundDegradationSummarizer::with_strategy(
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        strategy,
+        db,
+    )))
+}
+
+fn build_minrtt50_deg_trailing(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_minrtt50_deg_with(p, db, degradation::BaselineStrategy::Trailing(get_days(p)?))
+}
+
+fn build_minrtt50_deg_tod(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_minrtt50_deg_with(p, db, degradation::BaselineStrategy::TimeOfDay(get_days(p)?))
+}
+
+fn build_minrtt50_deg_nexthops(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_minrtt50_deg_with(p, db, degradation::BaselineStrategy::Nexthops)
+}
+
+fn build_hdratio50_deg_trailing(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_hdratio50_deg_with(p, db, degradation::BaselineStrategy::Trailing(get_days(p)?))
+}
+
+fn build_hdratio50_deg_tod(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_hdratio50_deg_with(p, db, degradation::BaselineStrategy::TimeOfDay(get_days(p)?))
+}
+
+fn build_hdratio50_deg_nexthops(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_hdratio50_deg_with(p, db, degradation::BaselineStrategy::Nexthops)
+}
+
+fn build_deg_attribution(
+    p: &Params,
+    db: &db::DB,
+    metric: degradation::DegradationMetric,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::DegradationAttributionSummarizer::new(
+        metric,
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        db,
+    )))
+}
+
+fn build_minrtt50_deg_attribution(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_deg_attribution(p, db, degradation::DegradationMetric::MinRtt50)
+}
+
+fn build_hdratio50_deg_attribution(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_deg_attribution(p, db, degradation::DegradationMetric::HdRatio50)
+}
+
+fn build_hdratio_mean_deg_attribution(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_deg_attribution(p, db, degradation::DegradationMetric::HdRatioMean)
+}
+
+/// `min_frac` must be at least 0.5 so a single `px_nexthops` can win.
+fn get_min_frac(p: &Params) -> Result<f32, ConfigError> {
+    let min_frac: f32 = get_f32(p, "min_frac")?;
+    if !(0.5..=1.0).contains(&min_frac) {
+        return Err(ConfigError::new("min_frac", "must be between 0.5 and 1"));
+    }
+    Ok(min_frac)
+}
+
+fn build_minrtt50_distinct_deg(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::MinRtt50LowerBoundDistinctPathsDegradationSummarizer::new(
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        get_min_frac(p)?,
+        db,
+    )))
+}
+
+fn build_hdratio50_distinct_deg(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(degradation::HdRatio50LowerBoundDistinctPathsDegradationSummarizer::new(
+        get_f32(p, "baseline")?,
+        get_f32(p, "thresh")?,
+        get_f32(p, "diff_ci")?,
+        get_f32(p, "base_ci")?,
+        get_min_frac(p)?,
+        db,
+    )))
+}
+
+fn build_minrtt50_rel(p: &Params, _db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(relationships::MinRtt50RelationshipSummarizer {
+        primary_bitmask: get_int(p, "primary")?,
+        alternate_bitmask: get_int(p, "alternate")?,
+        minrtt50_min_improv: get_f32(p, "thresh")?,
+        max_minrtt50_diff_ci_halfwidth: get_f32(p, "diff_ci")?,
+        compare_lower_bound: get_bool(p, "bound")?,
+    }))
+}
+
+fn build_hdratio50_rel(
+    p: &Params,
+    _db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(relationships::HdRatio50RelationshipSummarizer {
+        primary_bitmask: get_int(p, "primary")?,
+        alternate_bitmask: get_int(p, "alternate")?,
+        hdratio50_min_improv: get_f32(p, "thresh")?,
+        max_hdratio50_diff_ci_halfwidth: get_f32(p, "diff_ci")?,
+        compare_lower_bound: get_bool(p, "bound")?,
+    }))
+}
+
+/// `continent` must name a `db::ClientContinent`.
+fn get_continent(p: &Params) -> Result<db::ClientContinent, ConfigError> {
+    let text: &str = get_str(p, "continent")?;
+    match text.parse::<db::ClientContinent>() {
+        Ok(continent) if continent != db::ClientContinent::Unknown || text == "Unknown" => {
+            Ok(continent)
+        }
+        _ => Err(ConfigError::new("continent", "must be AF, AS, EU, NA, OC, SA or Unknown")),
+    }
+}
+
+fn build_filter(
+    p: &Params,
+    db: &db::DB,
+    filter: combinators::Filter,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(combinators::FilteredSummarizer::new(
+        get_summarizer(p, "inner", db)?,
+        vec![filter],
+    )))
+}
+
+fn build_filter_primary(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_filter(p, db, combinators::Filter::PrimaryPeerTypes(get_int(p, "bitmask")?))
+}
+
+fn build_filter_alternate(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_filter(p, db, combinators::Filter::AlternatePeerTypes(get_int(p, "bitmask")?))
+}
+
+fn build_filter_continent(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_filter(p, db, combinators::Filter::Continent(get_continent(p)?))
+}
+
+fn build_filter_country(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    let chars: Vec<char> = get_str(p, "country")?.chars().collect();
+    match chars[..] {
+        [c1, c2] => build_filter(p, db, combinators::Filter::Country([c1, c2])),
+        _ => Err(ConfigError::new("country", "must be a two-letter country code")),
+    }
+}
+
+fn build_filter_prefix_len(
+    p: &Params,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    let min: u8 = get_int(p, "min")?;
+    let max: u8 = get_int(p, "max")?;
+    if min > max || max > 128 {
+        return Err(ConfigError::new("max", "must be between min and 128"));
+    }
+    build_filter(
+        p,
+        db,
+        combinators::Filter::PrefixLength {
+            min,
+            max,
+        },
+    )
+}
+
+fn build_combination(
+    p: &Params,
+    db: &db::DB,
+    combination: combinators::Combination,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(combinators::CombinedSummarizer::new(
+        get_summarizer(p, "left", db)?,
+        get_summarizer(p, "right", db)?,
+        combination,
+    )))
+}
+
+fn build_and(p: &Params, db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_combination(p, db, combinators::Combination::And)
+}
+
+fn build_or(p: &Params, db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    build_combination(p, db, combinators::Combination::Or)
+}
+
+fn build_inverted(p: &Params, db: &db::DB) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    Ok(Arc::new(combinators::InvertedSummarizer::new(
+        get_summarizer(p, "inner", db)?,
+        get_f32(p, "thresh")?,
+    )))
+}
+
+pub(crate) fn parse_value(text: &str, kind: ParamKind) -> Result<toml::Value, ()> {
+    match kind {
+        ParamKind::Float => text.parse::<f64>().map(toml::Value::Float).map_err(|_| ()),
+        ParamKind::Int => match text.parse::<i64>() {
+            Ok(i) if i >= 0 => Ok(toml::Value::Integer(i)),
+            _ => Err(()),
+        },
+        ParamKind::Bool => text.parse::<bool>().map(toml::Value::Boolean).map_err(|_| ()),
+        ParamKind::Str if is_word(text) => Ok(toml::Value::String(text.to_string())),
+        ParamKind::Str => Err(()),
+        ParamKind::Summarizer => text
+            .strip_prefix('[')
+            .and_then(|text| text.strip_suffix(']'))
+            .and_then(|text| SummarizerSpec::parse(text).ok())
+            .map(|spec| spec.to_value())
+            .ok_or(()),
+    }
+}
+
+fn is_word(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(char::is_alphanumeric)
+}
+
+pub(crate) fn default_value(kind: ParamKind, default: f64) -> toml::Value {
+    match kind {
+        ParamKind::Float => toml::Value::Float(default),
+        ParamKind::Int => toml::Value::Integer(default as i64),
+        ParamKind::Bool => toml::Value::Boolean(default != 0.0),
+        ParamKind::Str | ParamKind::Summarizer => {
+            unreachable!("{:?} parameters have no default", kind)
+        }
+    }
+}
+
+pub(crate) fn coerce(
+    value: &toml::Value,
+    kind: ParamKind,
+    key: &str,
+) -> Result<toml::Value, ConfigError> {
+    match (kind, value) {
+        (ParamKind::Float, toml::Value::Float(_)) => Ok(value.clone()),
+        (ParamKind::Float, toml::Value::Integer(i)) => Ok(toml::Value::Float(*i as f64)),
+        (ParamKind::Int, toml::Value::Integer(i)) if *i >= 0 => Ok(value.clone()),
+        (ParamKind::Bool, toml::Value::Boolean(_)) => Ok(value.clone()),
+        (ParamKind::Str, toml::Value::String(text)) if is_word(text) => Ok(value.clone()),
+        (ParamKind::Summarizer, toml::Value::Table(table)) => {
+            Ok(SummarizerSpec::from_table(table, key)?.to_value())
+        }
+        _ => Err(ConfigError::new(key, &format!("expected {:?} but got {}", kind, value))),
+    }
+}
+
+pub(crate) fn get_f32(params: &Params, key: &str) -> Result<f32, ConfigError> {
+    match params.get(key) {
+        Some(toml::Value::Float(f)) => Ok(*f as f32),
+        Some(toml::Value::Integer(i)) => Ok(*i as f32),
+        Some(value) => Err(ConfigError::new(key, &format!("expected Float but got {}", value))),
+        None => Err(ConfigError::new(key, "missing")),
+    }
+}
+
+pub(crate) fn get_bool(params: &Params, key: &str) -> Result<bool, ConfigError> {
+    match params.get(key) {
+        Some(toml::Value::Boolean(b)) => Ok(*b),
+        Some(value) => Err(ConfigError::new(key, &format!("expected Bool but got {}", value))),
+        None => Err(ConfigError::new(key, "missing")),
+    }
+}
+
+pub(crate) fn get_int<T: TryFrom<i64>>(params: &Params, key: &str) -> Result<T, ConfigError> {
+    match params.get(key) {
+        Some(toml::Value::Integer(i)) => {
+            T::try_from(*i).map_err(|_| ConfigError::new(key, &format!("{} is out of range", i)))
+        }
+        Some(value) => Err(ConfigError::new(key, &format!("expected Int but got {}", value))),
+        None => Err(ConfigError::new(key, "missing")),
+    }
+}
+
+pub(crate) fn get_str<'p>(params: &'p Params, key: &str) -> Result<&'p str, ConfigError> {
+    match params.get(key) {
+        Some(toml::Value::String(text)) => Ok(text),
+        Some(value) => Err(ConfigError::new(key, &format!("expected Str but got {}", value))),
+        None => Err(ConfigError::new(key, "missing")),
+    }
+}
+
+/// Builds the summarizer whose spec is the table at `key`.
+fn get_summarizer(
+    params: &Params,
+    key: &str,
+    db: &db::DB,
+) -> Result<Arc<dyn TimeBinSummarizer>, ConfigError> {
+    match params.get(key) {
+        Some(toml::Value::Table(table)) => SummarizerSpec::from_table(table, key)?
+            .build(db)
+            .map_err(|e| ConfigError::new(&format!("{}.{}", key, e.key), &e.message)),
+        Some(value) => {
+            Err(ConfigError::new(key, &format!("expected Summarizer but got {}", value)))
+        }
+        None => Err(ConfigError::new(key, "missing")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_parse_spec() {
+        let spec =
+            SummarizerSpec::parse("minrtt50-deg:baseline=0.1,thresh=5,diff_ci=10,base_ci=20")
+                .unwrap();
+        assert!(spec.name == "minrtt50-deg");
+        assert!(spec.params["thresh"] == toml::Value::Float(5.0));
+        assert!(SummarizerSpec::parse(&spec.to_string()).unwrap() == spec);
+
+        let database = db::DB::default();
+        let summarizer = spec.build(&database).unwrap();
+        assert!(
+            summarizer.prefix()
+                == "minrtt50--deg--bound-true--diff-thresh-5.00--diff-ci-10.00--base-ci-20.00--baseline-0.10"
+        );
+
+        let spec = SummarizerSpec::parse(
+            "minrtt50-distinct-deg:baseline=0.1,thresh=5,diff_ci=10,base_ci=20,min_frac=0.4",
+        )
+        .unwrap();
+        assert!(spec.build(&database).err().unwrap().key == "minrtt50-distinct-deg.min_frac");
+        for name in ["minrtt50-deg-trailing", "hdratio50-deg-tod"].iter() {
+            let text = format!("{}:baseline=0.1,thresh=5,diff_ci=10,base_ci=20,days=0", name);
+            let err: ConfigError =
+                SummarizerSpec::parse(&text).unwrap().build(&database).err().unwrap();
+            assert!(err.key == format!("{}.days", name));
+            assert!(err.message == "must be at least 1");
+        }
+
+        let spec =
+            SummarizerSpec::parse("minrtt-pct-opp:percentile=75,thresh=5,diff_ci=10,bound=true")
+                .unwrap();
+        assert!(spec.build(&database).err().unwrap().key == "minrtt-pct-opp.percentile");
+
+        let check = |text: &str, key: &str| {
+            let err = SummarizerSpec::parse(text).unwrap_err();
+            assert!(err.key == key, "{} != {}", err.key, key);
+        };
+        check("minrtt50-foo:thresh=5", "minrtt50-foo");
+        check("hdratio50-opp:thresh=0.1,bound=true", "hdratio50-opp.diff_ci");
+        check("hdratio50-opp:thresh=0.1,diff_ci=0.1,bound=1", "hdratio50-opp.bound");
+        check("hdratio50-opp:thresh=0.1,diff_ci=0.1,bound=true,color=1", "hdratio50-opp.color");
+        check("hdratio50-opp:thresh=0.1,thresh=0.2,diff_ci=0.1,bound=true", "hdratio50-opp.thresh");
+        check(
+            "minrtt50-rel:primary=-1,alternate=2,thresh=5,diff_ci=10,bound=true",
+            "minrtt50-rel.primary",
+        );
+    }
+
+    #[test]
+    fn test_parse_composite_spec() {
+        let inner = "minrtt50-opp:bound=true,diff_ci=10.0,hdratio_diff_ci=0.1,thresh=5.0";
+        let text =
+            format!("or:left=[{}],right=[filter-country:country=BR,inner=[{}]]", inner, inner);
+        let spec = SummarizerSpec::parse(&text).unwrap();
+        assert!(spec.to_string() == text);
+        let right: &toml::Value = &spec.params["right"];
+        assert!(right["type"].as_str() == Some("filter-country"));
+        assert!(right["inner"]["thresh"] == toml::Value::Float(5.0));
+
+        let database = db::DB::default();
+        let inner_prefix: String =
+            SummarizerSpec::parse(inner).unwrap().build(&database).unwrap().prefix();
+        assert!(
+            spec.build(&database).unwrap().prefix()
+                == format!("{}--or--{}--filter-country-BR--end", inner_prefix, inner_prefix)
+        );
+        let text =
+            format!("filter-country:country=BR,inner=[or:left=[{}],right=[{}]]", inner, inner);
+        assert!(
+            SummarizerSpec::parse(&text).unwrap().build(&database).unwrap().prefix()
+                == format!("{}--or--{}--end--filter-country-BR", inner_prefix, inner_prefix)
+        );
+
+        let check = |text: &str, key: &str| {
+            let err = SummarizerSpec::parse(text).unwrap_err();
+            assert!(err.key == key, "{} != {}", err.key, key);
+        };
+        check(
+            "inverted:inner=[minrtt50-opp:thresh=5],thresh=5",
+            "inverted.inner.minrtt50-opp.diff_ci",
+        );
+        check(&format!("inverted:inner={},thresh=5", inner), "inverted.inner");
+        check(&format!("filter-country:inner=[{}],country=B-R", inner), "filter-country.country");
+        let check_build = |text: &str, key: &str| {
+            let err = SummarizerSpec::parse(text).unwrap().build(&database).err().unwrap();
+            assert!(err.key == key, "{} != {}", err.key, key);
+        };
+        check_build(
+            &format!("filter-continent:inner=[{}],continent=XX", inner),
+            "filter-continent.continent",
+        );
+        check_build(
+            &format!("filter-country:inner=[{}],country=BRA", inner),
+            "filter-country.country",
+        );
+        check_build(
+            &format!("filter-prefix-len:inner=[{}],min=24,max=16", inner),
+            "filter-prefix-len.max",
+        );
+        check_build(
+            "inverted:inner=[minrtt50-deg-trailing:baseline=0.1,thresh=5,diff_ci=10,base_ci=20,days=0],thresh=5",
+            "inverted.inner.minrtt50-deg-trailing.days",
+        );
+    }
+
+    /// One spec per registered type.  The percentile types use the same
+    /// parameters as their P50 counterparts so that their prefixes would
+    /// collide if they shared a token.
+    const SPECS: [&str; 32] = [
+        "minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true",
+        "hdratio50-opp:thresh=0.05,diff_ci=0.1,bound=false",
+        "hdratio-mean-opp:thresh=0.05,diff_ci=0.1,bound=true",
+        "hdratio-boot-opp:thresh=0.05,diff_ci=0.2,bound=true",
+        "minrtt-pct-opp:percentile=50,thresh=5,diff_ci=10,bound=true",
+        "pareto-opp:minrtt_thresh=5,hdratio_thresh=0.05,diff_ci=10,hdratio_diff_ci=0.1",
+        "minrtt50-any-opp:thresh=5,diff_ci=10,holm=true",
+        "minrtt50-deg:baseline=0.1,thresh=5,diff_ci=10,base_ci=20",
+        "hdratio50-deg:baseline=0.9,thresh=0.75,diff_ci=0.1,base_ci=0.2",
+        "hdratio-mean-deg:baseline=0.9,thresh=0.05,diff_ci=0.1,base_ci=0.05",
+        "minrtt-pct-deg:percentile=50,baseline=0.1,thresh=5,diff_ci=10,base_ci=20",
+        "minrtt50-deg-trailing:baseline=0.1,thresh=5,diff_ci=10,base_ci=20,days=3",
+        "minrtt50-deg-tod:baseline=0.1,thresh=5,diff_ci=10,base_ci=20,days=7",
+        "minrtt50-deg-nexthops:baseline=0.1,thresh=5,diff_ci=10,base_ci=20",
+        "hdratio50-deg-trailing:baseline=0.9,thresh=0.05,diff_ci=0.1,base_ci=0.2,days=3",
+        "hdratio50-deg-tod:baseline=0.9,thresh=0.05,diff_ci=0.1,base_ci=0.2,days=7",
+        "hdratio50-deg-nexthops:baseline=0.9,thresh=0.05,diff_ci=0.1,base_ci=0.2",
+        "minrtt50-distinct-deg:baseline=0.1,thresh=5,diff_ci=10,base_ci=20,min_frac=0.8",
+        "hdratio50-distinct-deg:baseline=0.9,thresh=0.05,diff_ci=0.1,base_ci=0.2,min_frac=0.5",
+        "minrtt50-deg-attribution:baseline=0.1,thresh=5,diff_ci=10,base_ci=20",
+        "hdratio50-deg-attribution:baseline=0.9,thresh=0.05,diff_ci=0.1,base_ci=0.2",
+        "hdratio-mean-deg-attribution:baseline=0.9,thresh=0.05,diff_ci=0.1,base_ci=0.05",
+        "minrtt50-rel:primary=28,alternate=2,thresh=5,diff_ci=10,bound=true",
+        "hdratio50-rel:primary=2,alternate=2,thresh=0.05,diff_ci=0.2,bound=true",
+        "filter-primary:inner=[minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true],bitmask=7",
+        "filter-alternate:inner=[hdratio50-opp:thresh=0.05,diff_ci=0.1,bound=true],bitmask=2",
+        "filter-continent:inner=[minrtt50-deg:baseline=0.1,thresh=5,diff_ci=10,base_ci=20],continent=SA",
+        "filter-country:inner=[minrtt50-rel:primary=28,alternate=2,thresh=5,diff_ci=10,bound=true],country=BR",
+        "filter-prefix-len:inner=[minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true],min=16,max=24",
+        "and:left=[minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true],right=[hdratio50-opp:thresh=0.05,diff_ci=0.1,bound=true]",
+        "or:left=[minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true],right=[filter-country:inner=[minrtt50-opp:thresh=10,diff_ci=10,hdratio_diff_ci=0.1,bound=true],country=BR]",
+        "inverted:inner=[minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true],thresh=5",
+    ];
+
+    #[test]
+    fn test_prefix_round_trip() {
+        let database = db::DB::default();
+        for text in SPECS.iter() {
+            let spec = SummarizerSpec::parse(text).unwrap();
+            let prefix: String = spec.build(&database).unwrap().prefix();
+            let parsed = SummarizerSpec::from_prefix(&prefix).unwrap();
+            assert!(parsed == spec, "{} != {}", parsed, spec);
+        }
+        assert!(SummarizerSpec::from_prefix("minrtt50--opp--bound-true").is_none());
+        assert!(SummarizerSpec::from_prefix(
+            "hdratio50--opp--bound-true--diff-thresh-0.05--diff-ci-0.10--extra"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_prefixes_are_distinct() {
+        let database = db::DB::default();
+        let mut names: HashSet<String> = HashSet::new();
+        let mut prefixes: HashSet<String> = HashSet::new();
+        for text in SPECS.iter() {
+            let spec = SummarizerSpec::parse(text).unwrap();
+            let prefix: String = spec.build(&database).unwrap().prefix();
+            assert!(names.insert(spec.name.clone()), "{}", text);
+            assert!(prefixes.insert(prefix.clone()), "{}", prefix);
+        }
+        assert!(SUMMARIZER_TYPES.iter().all(|t| names.contains(t.name)));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::performance::db;
+use crate::performance::explain::{check_shift, check_width, Metric, Selection};
 use crate::performance::perfstats;
 use crate::performance::perfstats::TimeBinSummarizer;
 
@@ -52,36 +53,19 @@ impl TimeBinSummarizer for MinRtt50RelationshipSummarizer {
                     perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
                         bytes: bin.bytes_acked_sum,
                         diff_ci: diff,
-                        diff_ci_halfwidth: halfwidth,
+                        diff_ci_lb: diff - halfwidth,
+                        diff_ci_ub: diff + halfwidth,
                         primary_peer_type: primary.peer_type,
                         alternate_peer_type: bestalt.peer_type,
+                        selection: perfstats::RouteSelection::alternate(bin, primary, bestalt),
                         bitmask: 0,
                         is_shifted: limit >= self.minrtt50_min_improv,
+                        ..Default::default()
                     })
                 }
             }
         }
     }
-    fn get_routes<'s: 'd, 'd>(
-        &'s self,
-        pathid: &db::PathId,
-        time: u64,
-        db: &'d db::DB,
-    ) -> (&'d db::RouteInfo, &'d db::RouteInfo) {
-        let bin = &db.pathid2info[pathid].time2bin[&time];
-        (
-            bin.get_primary_route(|r| {
-                check_valid(r, self.primary_bitmask, db::RouteInfo::minrtt_valid)
-            })
-            .as_ref()
-            .unwrap(),
-            bin.get_first_alternate(|r| {
-                check_valid(r, self.alternate_bitmask, db::RouteInfo::minrtt_valid)
-            })
-            .as_ref()
-            .unwrap(),
-        )
-    }
     fn prefix(&self) -> String {
         format!(
             "minrtt50--relationships-{}-{}--bound-{}--diff-thresh-{:0.2}--diff-ci-{:0.2}",
@@ -92,6 +76,48 @@ impl TimeBinSummarizer for MinRtt50RelationshipSummarizer {
             self.max_minrtt50_diff_ci_halfwidth
         )
     }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let primary = bin
+            .get_primary_route(|r| {
+                check_valid(r, self.primary_bitmask, db::RouteInfo::minrtt_valid)
+            })
+            .as_deref();
+        let bestalt = bin
+            .get_first_alternate(|r| {
+                check_valid(r, self.alternate_bitmask, db::RouteInfo::minrtt_valid)
+            })
+            .as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "alternate: first valid alternate",
+            metric: Metric::MinRtt,
+            primary_bitmask: self.primary_bitmask,
+            alternate_bitmask: Some(self.alternate_bitmask),
+            primary,
+            alternate: bestalt,
+        }
+        .describe(bin);
+        if let (Some(primary), Some(bestalt)) = (primary, bestalt) {
+            let (diff, halfwidth) = db::RouteInfo::minrtt_median_diff_ci(primary, bestalt);
+            lines.push(check_width(
+                "MinRTT P50 diff CI halfwidth",
+                halfwidth,
+                self.max_minrtt50_diff_ci_halfwidth,
+            ));
+            let (name, limit) = if self.compare_lower_bound {
+                ("MinRTT P50 diff lower bound", diff - halfwidth)
+            } else {
+                ("MinRTT P50 diff", diff)
+            };
+            lines.push(check_shift(
+                name,
+                limit,
+                ">=",
+                self.minrtt50_min_improv,
+                limit >= self.minrtt50_min_improv,
+            ));
+        }
+        lines
+    }
 }
 
 impl TimeBinSummarizer for HdRatio50RelationshipSummarizer {
@@ -119,36 +145,19 @@ impl TimeBinSummarizer for HdRatio50RelationshipSummarizer {
                     perfstats::TimeBinSummary::Valid(perfstats::TimeBinStats {
                         bytes: bin.bytes_acked_sum,
                         diff_ci: diff,
-                        diff_ci_halfwidth: halfwidth,
+                        diff_ci_lb: diff - halfwidth,
+                        diff_ci_ub: diff + halfwidth,
                         primary_peer_type: primary.peer_type,
                         alternate_peer_type: bestalt.peer_type,
+                        selection: perfstats::RouteSelection::alternate(bin, primary, bestalt),
                         bitmask: 0,
                         is_shifted: limit >= self.hdratio50_min_improv,
+                        ..Default::default()
                     })
                 }
             }
         }
     }
-    fn get_routes<'s: 'd, 'd>(
-        &'s self,
-        pathid: &db::PathId,
-        time: u64,
-        db: &'d db::DB,
-    ) -> (&'d db::RouteInfo, &'d db::RouteInfo) {
-        let bin = &db.pathid2info[pathid].time2bin[&time];
-        (
-            bin.get_primary_route(|r| {
-                check_valid(r, self.primary_bitmask, db::RouteInfo::hdratio_valid)
-            })
-            .as_ref()
-            .unwrap(),
-            bin.get_first_alternate(|r| {
-                check_valid(r, self.alternate_bitmask, db::RouteInfo::hdratio_valid)
-            })
-            .as_ref()
-            .unwrap(),
-        )
-    }
     fn prefix(&self) -> String {
         format!(
             "hdratio50--relationships-{}-{}--bound-{}--diff-thresh-{}--diff-ci-{:0.2}",
@@ -159,6 +168,48 @@ impl TimeBinSummarizer for HdRatio50RelationshipSummarizer {
             self.max_hdratio50_diff_ci_halfwidth
         )
     }
+    fn explain(&self, _pathid: &db::PathId, bin: &db::TimeBin) -> Vec<String> {
+        let primary = bin
+            .get_primary_route(|r| {
+                check_valid(r, self.primary_bitmask, db::RouteInfo::hdratio_valid)
+            })
+            .as_deref();
+        let bestalt = bin
+            .get_first_alternate(|r| {
+                check_valid(r, self.alternate_bitmask, db::RouteInfo::hdratio_valid)
+            })
+            .as_deref();
+        let mut lines: Vec<String> = Selection {
+            rule: "alternate: first valid alternate",
+            metric: Metric::HdRatio,
+            primary_bitmask: self.primary_bitmask,
+            alternate_bitmask: Some(self.alternate_bitmask),
+            primary,
+            alternate: bestalt,
+        }
+        .describe(bin);
+        if let (Some(primary), Some(bestalt)) = (primary, bestalt) {
+            let (diff, halfwidth) = db::RouteInfo::hdratio_median_diff_ci(bestalt, primary);
+            lines.push(check_width(
+                "HD-ratio P50 diff CI halfwidth",
+                halfwidth,
+                self.max_hdratio50_diff_ci_halfwidth,
+            ));
+            let (name, limit) = if self.compare_lower_bound {
+                ("HD-ratio P50 diff lower bound", diff - halfwidth)
+            } else {
+                ("HD-ratio P50 diff", diff)
+            };
+            lines.push(check_shift(
+                name,
+                limit,
+                ">=",
+                self.hdratio50_min_improv,
+                limit >= self.hdratio50_min_improv,
+            ));
+        }
+        lines
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,678 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::performance::db;
+use crate::performance::perfstats::{TemporalConfig, TimeBinSummarizer};
+use crate::performance::sensitivity::SensitivityRange;
+use crate::performance::summarizers::registry::{
+    self, coerce, default_value, get_f32, get_int, optional, param, parse_value, Param, ParamKind,
+    SummarizerSpec, SummarizerType,
+};
+
+mod error;
+pub use error::ConfigError;
+
+const TEMPCONFIG_PARAMS: [Param; 15] = [
+    param("bin_duration_secs", ParamKind::Int),
+    param("min_days", ParamKind::Int),
+    param("min_frac_existing_bins", ParamKind::Float),
+    param("min_frac_bins_with_alternate", ParamKind::Float),
+    param("min_frac_valid_bins", ParamKind::Float),
+    param("continuous_min_frac_shifted_bins", ParamKind::Float),
+    param("diurnal_min_bad_bins", ParamKind::Int),
+    param("diurnal_bad_bin_min_prob_shift", ParamKind::Float),
+    optional("diurnal_permutations", ParamKind::Int, 0.0),
+    optional("diurnal_max_pvalue", ParamKind::Float, 0.05),
+    param("uneventful_max_frac_shifted_bins", ParamKind::Float),
+    optional("weekly_min_weeks", ParamKind::Int, 0.0),
+    optional("weekly_min_autocorr", ParamKind::Float, 0.5),
+    optional("shift_enter_min_bins", ParamKind::Int, 1.0),
+    optional("shift_exit_min_bins", ParamKind::Int, 1.0),
+];
+
+/// Summarizers and temporal configs to run, after expanding every
+/// parameter list in the configuration file as a cartesian grid.
+///
+/// Configuration files have `[[summarizer]]` tables with a `type` (see
+/// `registry::SUMMARIZER_TYPES`) and one value or a list of values per
+/// parameter, `[[tempconfig]]` tables with one value or a list of values per
+/// `TemporalConfig` field, and optional `[[relationship]]` tables with
+/// `primary` and `alternate` lists of `db::PeerType` names.
+/// Relationship summarizers that do not set `primary` and `alternate`
+/// are expanded over all `[[relationship]]` pairs.
+#[derive(Clone, Debug)]
+pub struct SweepConfig {
+    pub summarizers: Vec<SummarizerSpec>,
+    pub tempconfigs: Vec<TemporalConfig>,
+    /// Ranges for the sensitivity grid around the first temporal
+    /// config, from an optional `[sensitivity]` table with a list of
+    /// values per `TemporalConfig` field.
+    pub sensitivity: Vec<SensitivityRange>,
+}
+
+impl SweepConfig {
+    pub fn from_file(path: &Path) -> Result<SweepConfig, Box<dyn Error>> {
+        let text: String = fs::read_to_string(path)?;
+        Ok(SweepConfig::parse(&text)?)
+    }
+
+    pub fn parse(text: &str) -> Result<SweepConfig, ConfigError> {
+        let table: toml::Table =
+            text.parse().map_err(|e: toml::de::Error| ConfigError::new("toml", &e.to_string()))?;
+        for key in table.keys() {
+            if !["summarizer", "tempconfig", "relationship", "sensitivity"].contains(&key.as_str())
+            {
+                return Err(ConfigError::new(key, "unknown section"));
+            }
+        }
+        let pairs: Vec<(u32, u32)> = match table.get("relationship") {
+            Some(value) => parse_relationships(value)?,
+            None => Vec::new(),
+        };
+
+        let mut summarizers: Vec<SummarizerSpec> = Vec::new();
+        for (i, value) in get_tables(&table, "summarizer")?.iter().enumerate() {
+            let ctx: String = format!("summarizer[{}]", i);
+            summarizers.extend(parse_summarizer(value, &ctx, &pairs)?);
+        }
+
+        let mut tempconfigs: Vec<TemporalConfig> = Vec::new();
+        for (i, value) in get_tables(&table, "tempconfig")?.iter().enumerate() {
+            let ctx: String = format!("tempconfig[{}]", i);
+            let table: &toml::Table = value
+                .as_table()
+                .ok_or_else(|| ConfigError::new(&ctx, "expected a [[tempconfig]] table"))?;
+            for params in expand_table(table, &ctx, &TEMPCONFIG_PARAMS, &[])? {
+                tempconfigs.push(build_tempconfig(&params, &ctx)?);
+            }
+        }
+
+        let sensitivity: Vec<SensitivityRange> = match table.get("sensitivity") {
+            Some(value) => parse_sensitivity(value)?,
+            None => Vec::new(),
+        };
+
+        Ok(SweepConfig {
+            summarizers,
+            tempconfigs,
+            sensitivity,
+        })
+    }
+
+    /// Builds all summarizers; degradation summarizers compute their
+    /// baselines from `db`.
+    pub fn build_summarizers(
+        &self,
+        db: &db::DB,
+    ) -> Result<Vec<Arc<dyn TimeBinSummarizer>>, ConfigError> {
+        self.summarizers.iter().map(|spec| spec.build(db)).collect()
+    }
+
+    /// The resolved configuration, with one table per summarizer and
+    /// temporal config.  It can be loaded back with `parse`.
+    pub fn to_toml(&self) -> String {
+        let mut table = toml::Table::new();
+        let summarizers: Vec<toml::Value> =
+            self.summarizers.iter().map(SummarizerSpec::to_value).collect();
+        let tempconfigs: Vec<toml::Value> = self
+            .tempconfigs
+            .iter()
+            .map(|cfg| toml::Value::Table(tempconfig_params(cfg).into_iter().collect()))
+            .collect();
+        table.insert("summarizer".to_string(), toml::Value::Array(summarizers));
+        table.insert("tempconfig".to_string(), toml::Value::Array(tempconfigs));
+        if !self.sensitivity.is_empty() {
+            let ranges: toml::Table = self
+                .sensitivity
+                .iter()
+                .map(|range| (range.name.clone(), toml::Value::Array(range.values.clone())))
+                .collect();
+            table.insert("sensitivity".to_string(), toml::Value::Table(ranges));
+        }
+        toml::to_string(&table).unwrap()
+    }
+
+    /// Copies the configuration file at `source` to `dir` and writes
+    /// the resolved configuration next to it.
+    pub fn dump(&self, dir: &Path, source: &Path) -> Result<(), io::Error> {
+        fs::create_dir_all(dir)?;
+        let mut filepath = dir.to_path_buf();
+        filepath.push("sweep-config.toml");
+        fs::copy(source, filepath)?;
+        let mut filepath = dir.to_path_buf();
+        filepath.push("sweep-resolved.toml");
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(filepath)?;
+        let mut bw = io::BufWriter::new(file);
+        write!(bw, "{}", self.to_toml())
+    }
+}
+
+fn get_tables<'a>(table: &'a toml::Table, key: &str) -> Result<&'a Vec<toml::Value>, ConfigError> {
+    match table.get(key) {
+        Some(toml::Value::Array(values)) if !values.is_empty() => Ok(values),
+        Some(_) => Err(ConfigError::new(key, &format!("expected one or more [[{}]] tables", key))),
+        None => Err(ConfigError::new(key, &format!("missing [[{}]] tables", key))),
+    }
+}
+
+fn parse_summarizer(
+    value: &toml::Value,
+    ctx: &str,
+    pairs: &[(u32, u32)],
+) -> Result<Vec<SummarizerSpec>, ConfigError> {
+    let table: &toml::Table =
+        value.as_table().ok_or_else(|| ConfigError::new(ctx, "expected a [[summarizer]] table"))?;
+    let typekey: String = format!("{}.type", ctx);
+    let name: &str = table
+        .get("type")
+        .ok_or_else(|| ConfigError::new(&typekey, "missing"))?
+        .as_str()
+        .ok_or_else(|| ConfigError::new(&typekey, "expected a string"))?;
+    let stype: &SummarizerType = registry::find(name)
+        .ok_or_else(|| ConfigError::new(&typekey, &format!("unknown summarizer type {}", name)))?;
+
+    let is_relationship: bool = stype.params.iter().any(|p| p.name == "primary");
+    let mut tables: Vec<toml::Table> = Vec::new();
+    if is_relationship && !table.contains_key("primary") && !table.contains_key("alternate") {
+        if pairs.is_empty() {
+            return Err(ConfigError::new(
+                &format!("{}.primary", ctx),
+                "missing and no [[relationship]] pairs declared",
+            ));
+        }
+        for &(primary, alternate) in pairs {
+            let mut t: toml::Table = table.clone();
+            t.insert("primary".to_string(), toml::Value::Integer(i64::from(primary)));
+            t.insert("alternate".to_string(), toml::Value::Integer(i64::from(alternate)));
+            tables.push(t);
+        }
+    } else {
+        tables.push(table.clone());
+    }
+
+    let mut specs: Vec<SummarizerSpec> = Vec::new();
+    for t in tables.iter() {
+        for params in expand_table(t, ctx, stype.params, &["type"])? {
+            specs.push(SummarizerSpec {
+                name: name.to_string(),
+                params,
+            });
+        }
+    }
+    Ok(specs)
+}
+
+fn parse_relationships(value: &toml::Value) -> Result<Vec<(u32, u32)>, ConfigError> {
+    let values: &Vec<toml::Value> = value
+        .as_array()
+        .ok_or_else(|| ConfigError::new("relationship", "expected [[relationship]] tables"))?;
+    let mut pairs: Vec<(u32, u32)> = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        let ctx: String = format!("relationship[{}]", i);
+        let table: &toml::Table = value
+            .as_table()
+            .ok_or_else(|| ConfigError::new(&ctx, "expected a [[relationship]] table"))?;
+        for key in table.keys() {
+            if key != "primary" && key != "alternate" {
+                return Err(ConfigError::new(&format!("{}.{}", ctx, key), "unknown parameter"));
+            }
+        }
+        pairs.push((
+            parse_peer_types(table.get("primary"), &format!("{}.primary", ctx))?,
+            parse_peer_types(table.get("alternate"), &format!("{}.alternate", ctx))?,
+        ));
+    }
+    Ok(pairs)
+}
+
+/// Converts a list of `db::PeerType` names into a bitmask.
+fn parse_peer_types(value: Option<&toml::Value>, key: &str) -> Result<u32, ConfigError> {
+    let names: &Vec<toml::Value> = value
+        .ok_or_else(|| ConfigError::new(key, "missing"))?
+        .as_array()
+        .ok_or_else(|| ConfigError::new(key, "expected a list of peer types"))?;
+    let mut bitmask: u32 = 0;
+    for name in names {
+        let name: &str =
+            name.as_str().ok_or_else(|| ConfigError::new(key, "expected a list of peer types"))?;
+        let peer_type: db::PeerType = (0..db::PeerType::Uninitialized as u8)
+            .map(|i| db::PeerType::try_from(i).unwrap())
+            .find(|pt| format!("{:?}", pt) == name)
+            .ok_or_else(|| ConfigError::new(key, &format!("unknown peer type {}", name)))?;
+        bitmask |= 1 << peer_type as u8;
+    }
+    Ok(bitmask)
+}
+
+/// Expands a table where each parameter has one value or a list of
+/// values into the cartesian product of all values.  Keys in `skip`
+/// are ignored.
+fn expand_table(
+    table: &toml::Table,
+    ctx: &str,
+    params: &[Param],
+    skip: &[&str],
+) -> Result<Vec<BTreeMap<String, toml::Value>>, ConfigError> {
+    let mut key2values: BTreeMap<String, Vec<toml::Value>> = BTreeMap::new();
+    for (key, value) in table.iter() {
+        if skip.contains(&key.as_str()) {
+            continue;
+        }
+        let fullkey: String = format!("{}.{}", ctx, key);
+        let param: &Param = params
+            .iter()
+            .find(|p| p.name == key)
+            .ok_or_else(|| ConfigError::new(&fullkey, "unknown parameter"))?;
+        let values: Vec<toml::Value> = match value {
+            toml::Value::Array(values) => values.clone(),
+            value => vec![value.clone()],
+        };
+        if values.is_empty() {
+            return Err(ConfigError::new(&fullkey, "empty list of values"));
+        }
+        let values: Vec<toml::Value> = values
+            .iter()
+            .map(|v| coerce(v, param.kind, &fullkey))
+            .collect::<Result<Vec<toml::Value>, ConfigError>>()?;
+        key2values.insert(key.clone(), values);
+    }
+    for param in params.iter() {
+        if key2values.contains_key(param.name) {
+            continue;
+        }
+        let default: f64 = param
+            .default
+            .ok_or_else(|| ConfigError::new(&format!("{}.{}", ctx, param.name), "missing"))?;
+        key2values.insert(param.name.to_string(), vec![default_value(param.kind, default)]);
+    }
+
+    let mut combinations: Vec<BTreeMap<String, toml::Value>> = vec![BTreeMap::new()];
+    for (key, values) in key2values.iter() {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.insert(key.clone(), value.clone());
+                    combination
+                })
+            })
+            .collect();
+    }
+    Ok(combinations)
+}
+
+fn parse_sensitivity(value: &toml::Value) -> Result<Vec<SensitivityRange>, ConfigError> {
+    let table: &toml::Table = value
+        .as_table()
+        .ok_or_else(|| ConfigError::new("sensitivity", "expected a [sensitivity] table"))?;
+    let mut ranges: Vec<SensitivityRange> = Vec::new();
+    for (key, value) in table.iter() {
+        let fullkey: String = format!("sensitivity.{}", key);
+        let param: &Param = find_tempconfig_param(key).map_err(|e| ConfigError {
+            key: fullkey.clone(),
+            ..e
+        })?;
+        let values: Vec<toml::Value> = match value {
+            toml::Value::Array(values) => values.clone(),
+            value => vec![value.clone()],
+        };
+        let values: Vec<toml::Value> = values
+            .iter()
+            .map(|v| coerce(v, param.kind, &fullkey))
+            .collect::<Result<Vec<toml::Value>, ConfigError>>()?;
+        ranges.push(SensitivityRange::new(key, values).map_err(|e| ConfigError {
+            key: fullkey.clone(),
+            ..e
+        })?);
+    }
+    Ok(ranges)
+}
+
+fn find_tempconfig_param(name: &str) -> Result<&'static Param, ConfigError> {
+    TEMPCONFIG_PARAMS
+        .iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| ConfigError::new(name, "unknown TemporalConfig parameter"))
+}
+
+/// `tempconfig` as a map from the names in `TEMPCONFIG_PARAMS` to
+/// values, as accepted by `build_tempconfig`.
+fn tempconfig_params(tempconfig: &TemporalConfig) -> BTreeMap<String, toml::Value> {
+    let mut params: BTreeMap<String, toml::Value> = BTreeMap::new();
+    for ((name, value), param) in tempconfig.params().into_iter().zip(TEMPCONFIG_PARAMS.iter()) {
+        assert!(name == param.name);
+        let value: toml::Value = match param.kind {
+            ParamKind::Float => toml::Value::Float(value.parse().unwrap()),
+            ParamKind::Int => toml::Value::Integer(value.parse().unwrap()),
+            ParamKind::Bool => toml::Value::Boolean(value.parse().unwrap()),
+            ParamKind::Str | ParamKind::Summarizer => unreachable!(),
+        };
+        params.insert(name.to_string(), value);
+    }
+    params
+}
+
+/// Parses `text` as a value of the `TemporalConfig` field `name`.
+pub(crate) fn parse_tempconfig_value(name: &str, text: &str) -> Result<toml::Value, ConfigError> {
+    let param: &Param = find_tempconfig_param(name)?;
+    parse_value(text, param.kind)
+        .map_err(|_| ConfigError::new(name, &format!("expected {:?} but got {}", param.kind, text)))
+}
+
+/// Copy of `tempconfig` with field `name` set to `value`.
+pub fn set_tempconfig_param(
+    tempconfig: &TemporalConfig,
+    name: &str,
+    value: &toml::Value,
+) -> Result<TemporalConfig, ConfigError> {
+    let param: &Param = find_tempconfig_param(name)?;
+    let mut params: BTreeMap<String, toml::Value> = tempconfig_params(tempconfig);
+    params.insert(name.to_string(), coerce(value, param.kind, name)?);
+    build_tempconfig(&params, "sensitivity")
+}
+
+fn build_tempconfig(
+    params: &BTreeMap<String, toml::Value>,
+    ctx: &str,
+) -> Result<TemporalConfig, ConfigError> {
+    let prefix_key = |e: ConfigError| ConfigError::new(&format!("{}.{}", ctx, e.key), &e.message);
+    let tempconfig = TemporalConfig {
+        bin_duration_secs: get_int(params, "bin_duration_secs").map_err(prefix_key)?,
+        min_days: get_int(params, "min_days").map_err(prefix_key)?,
+        min_frac_existing_bins: get_f32(params, "min_frac_existing_bins").map_err(prefix_key)?,
+        min_frac_bins_with_alternate: get_f32(params, "min_frac_bins_with_alternate")
+            .map_err(prefix_key)?,
+        min_frac_valid_bins: get_f32(params, "min_frac_valid_bins").map_err(prefix_key)?,
+        continuous_min_frac_shifted_bins: get_f32(params, "continuous_min_frac_shifted_bins")
+            .map_err(prefix_key)?,
+        diurnal_min_bad_bins: get_int(params, "diurnal_min_bad_bins").map_err(prefix_key)?,
+        diurnal_bad_bin_min_prob_shift: get_f32(params, "diurnal_bad_bin_min_prob_shift")
+            .map_err(prefix_key)?,
+        diurnal_permutations: get_int(params, "diurnal_permutations").map_err(prefix_key)?,
+        diurnal_max_pvalue: get_f32(params, "diurnal_max_pvalue").map_err(prefix_key)?,
+        uneventful_max_frac_shifted_bins: get_f32(params, "uneventful_max_frac_shifted_bins")
+            .map_err(prefix_key)?,
+        weekly_min_weeks: get_int(params, "weekly_min_weeks").map_err(prefix_key)?,
+        weekly_min_autocorr: get_f32(params, "weekly_min_autocorr").map_err(prefix_key)?,
+        shift_enter_min_bins: get_int(params, "shift_enter_min_bins").map_err(prefix_key)?,
+        shift_exit_min_bins: get_int(params, "shift_exit_min_bins").map_err(prefix_key)?,
+    };
+    if tempconfig.bin_duration_secs == 0 || 86400 % tempconfig.bin_duration_secs != 0 {
+        return Err(ConfigError::new(
+            &format!("{}.bin_duration_secs", ctx),
+            "must divide a day into a whole number of bins",
+        ));
+    }
+    Ok(tempconfig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::sensitivity;
+
+    const EXAMPLE: &str = include_str!("../../helpers/sweep-example.toml");
+
+    #[test]
+    fn test_parse_example() {
+        let sweep = SweepConfig::parse(EXAMPLE).unwrap();
+        assert!(sweep.summarizers.len() == 29);
+        assert!(sweep.summarizers.iter().filter(|s| s.name == "minrtt50-rel").count() == 4);
+        assert!(sweep.tempconfigs.len() == 3);
+        assert!(sweep.tempconfigs[0].diurnal_min_bad_bins == 4);
+        assert!(sweep.tempconfigs[0].shift_enter_min_bins == 1);
+        let mut defaults: Vec<SensitivityRange> = SensitivityRange::defaults();
+        defaults.sort_by(|r1, r2| r1.name.cmp(&r2.name));
+        assert!(sweep.sensitivity == defaults);
+
+        let database = db::DB::default();
+        let summarizers = sweep.build_summarizers(&database).unwrap();
+        assert!(summarizers.len() == 29);
+        assert!(
+            summarizers[0].prefix()
+                == "minrtt50--opp--bound-true--diff-thresh-5.00--diff-ci-10.00--hdratio-diff-ci-0.10"
+        );
+
+        let resolved = SweepConfig::parse(&sweep.to_toml()).unwrap();
+        assert!(resolved.summarizers == sweep.summarizers);
+        assert!(resolved.tempconfigs.len() == sweep.tempconfigs.len());
+        for (cfg1, cfg2) in resolved.tempconfigs.iter().zip(sweep.tempconfigs.iter()) {
+            assert!(cfg1.params() == cfg2.params());
+        }
+    }
+
+    #[test]
+    fn test_sensitivity_table() {
+        let tempconfig = r#"
+            [[summarizer]]
+            type = "hdratio50-opp"
+            thresh = 0.05
+            diff_ci = 0.1
+            bound = true
+
+            [[tempconfig]]
+            bin_duration_secs = 900
+            min_days = 2
+            min_frac_existing_bins = 0.6
+            min_frac_bins_with_alternate = 0.6
+            min_frac_valid_bins = 0.6
+            continuous_min_frac_shifted_bins = 0.75
+            diurnal_min_bad_bins = 4
+            diurnal_bad_bin_min_prob_shift = 0.5
+            uneventful_max_frac_shifted_bins = 0
+        "#;
+        let sweep = SweepConfig::parse(tempconfig).unwrap();
+        assert!(sweep.sensitivity.is_empty());
+
+        let text = format!(
+            "{}\n[sensitivity]\ndiurnal_min_bad_bins = [1, 8]\nmin_frac_valid_bins = 1\n",
+            tempconfig
+        );
+        let sweep = SweepConfig::parse(&text).unwrap();
+        assert!(sweep.sensitivity.len() == 2);
+        assert!(sweep.sensitivity[1].values == vec![toml::Value::Float(1.0)]);
+        let resolved = SweepConfig::parse(&sweep.to_toml()).unwrap();
+        assert!(resolved.sensitivity == sweep.sensitivity);
+
+        let grid = sensitivity::build_one_at_a_time_grid(&sweep.tempconfigs[0], &sweep.sensitivity)
+            .unwrap();
+        assert!(grid.len() == 3);
+        assert!(grid[1].diurnal_min_bad_bins == 8 && grid[1].min_frac_valid_bins == 0.6);
+        assert!(grid[2].min_frac_valid_bins == 1.0);
+
+        let err = |extra: &str| {
+            SweepConfig::parse(&format!("{}\n[sensitivity]\n{}\n", tempconfig, extra))
+                .unwrap_err()
+                .key
+        };
+        assert!(err("unknown = [1]") == "sensitivity.unknown");
+        assert!(err("diurnal_min_bad_bins = [0.5]") == "sensitivity.diurnal_min_bad_bins");
+        assert!(err("bin_duration_secs = [300]") == "sensitivity.bin_duration_secs");
+        assert!(err("min_days = []") == "sensitivity.min_days");
+    }
+
+    #[test]
+    fn test_cartesian_expansion() {
+        let text = r#"
+            [[summarizer]]
+            type = "hdratio50-opp"
+            thresh = [0.05, 0.1, 0.2]
+            diff_ci = [0.1, 0.2]
+            bound = [true, false]
+
+            [[tempconfig]]
+            bin_duration_secs = 900
+            min_days = 2
+            min_frac_existing_bins = 0.6
+            min_frac_bins_with_alternate = 0.6
+            min_frac_valid_bins = [0.6, 0.8]
+            continuous_min_frac_shifted_bins = 0.75
+            diurnal_min_bad_bins = [1, 4, 8]
+            diurnal_bad_bin_min_prob_shift = 0.5
+            uneventful_max_frac_shifted_bins = 0
+        "#;
+        let sweep = SweepConfig::parse(text).unwrap();
+        assert!(sweep.summarizers.len() == 12);
+        assert!(sweep.tempconfigs.len() == 6);
+        assert!(sweep.tempconfigs[5].min_frac_valid_bins == 0.8);
+        assert!(sweep.tempconfigs[5].diurnal_min_bad_bins == 8);
+    }
+
+    #[test]
+    fn test_composite_summarizers() {
+        let tempconfig = r#"
+            [[tempconfig]]
+            bin_duration_secs = 900
+            min_days = 2
+            min_frac_existing_bins = 0.6
+            min_frac_bins_with_alternate = 0.6
+            min_frac_valid_bins = 0.6
+            continuous_min_frac_shifted_bins = 0.75
+            diurnal_min_bad_bins = 4
+            diurnal_bad_bin_min_prob_shift = 0.5
+            uneventful_max_frac_shifted_bins = 0
+        "#;
+        let summarizers = r#"
+            [[summarizer]]
+            type = "filter-country"
+            country = ["BR", "US"]
+            inner = { type = "minrtt50-opp", thresh = 5, diff_ci = 10, hdratio_diff_ci = 0.1, bound = true }
+
+            [[summarizer]]
+            type = "and"
+            left = { type = "minrtt50-opp", thresh = 5, diff_ci = 10, hdratio_diff_ci = 0.1, bound = true }
+            right = [
+                { type = "hdratio50-opp", thresh = 0.05, diff_ci = 0.1, bound = true },
+                { type = "hdratio50-opp", thresh = 0.1, diff_ci = 0.1, bound = true },
+            ]
+        "#;
+        let sweep = SweepConfig::parse(&format!("{}\n{}", summarizers, tempconfig)).unwrap();
+        assert!(sweep.summarizers.len() == 4);
+        assert!(sweep.summarizers[1].params["country"].as_str() == Some("US"));
+        assert!(sweep.summarizers[1].params["inner"]["thresh"] == toml::Value::Float(5.0));
+        assert!(sweep.summarizers[3].params["right"]["thresh"] == toml::Value::Float(0.1));
+        let resolved = SweepConfig::parse(&sweep.to_toml()).unwrap();
+        assert!(resolved.summarizers == sweep.summarizers);
+
+        let database = db::DB::default();
+        let prefix: String = sweep.build_summarizers(&database).unwrap()[0].prefix();
+        assert!(prefix.starts_with("minrtt50--opp--") && prefix.ends_with("--filter-country-BR"));
+
+        let text = summarizers.replace("bound = true }\n\n", "bound = true, color = 1 }\n\n");
+        let err = SweepConfig::parse(&format!("{}\n{}", text, tempconfig)).unwrap_err();
+        assert!(err.key == "summarizer[0].inner.color", "{}", err.key);
+    }
+
+    #[test]
+    fn test_errors_name_key() {
+        let tempconfig = r#"
+            [[tempconfig]]
+            bin_duration_secs = 900
+            min_days = 2
+            min_frac_existing_bins = 0.6
+            min_frac_bins_with_alternate = 0.6
+            min_frac_valid_bins = 0.6
+            continuous_min_frac_shifted_bins = 0.75
+            diurnal_min_bad_bins = 4
+            diurnal_bad_bin_min_prob_shift = 0.5
+            uneventful_max_frac_shifted_bins = 0
+        "#;
+        let check = |summarizers: &str, key: &str| {
+            let err = SweepConfig::parse(&format!("{}\n{}", summarizers, tempconfig)).unwrap_err();
+            assert!(err.key == key, "{} != {}", err.key, key);
+        };
+        check("[[summarizer]]\nthresh = 5", "summarizer[0].type");
+        check("[[summarizer]]\ntype = \"minrtt50-foo\"", "summarizer[0].type");
+        check(
+            "[[summarizer]]\ntype = \"hdratio50-opp\"\nthresh = 0.1\ndiff_ci = 0.1\nbound = true\ncolor = 1",
+            "summarizer[0].color",
+        );
+        check(
+            "[[summarizer]]\ntype = \"hdratio50-opp\"\nthresh = 0.1\ndiff_ci = 0.1\nbound = 1",
+            "summarizer[0].bound",
+        );
+        check(
+            "[[summarizer]]\ntype = \"hdratio50-opp\"\nthresh = 0.1\nbound = true",
+            "summarizer[0].diff_ci",
+        );
+        check(
+            "[[summarizer]]\ntype = \"hdratio50-rel\"\nthresh = 0.1\ndiff_ci = 0.1\nbound = true",
+            "summarizer[0].primary",
+        );
+        check(
+            "[[relationship]]\nprimary = [\"Transit\"]\nalternate = [\"Peering\"]",
+            "relationship[0].alternate",
+        );
+
+        let summarizer =
+            "[[summarizer]]\ntype = \"hdratio50-opp\"\nthresh = 0.1\ndiff_ci = 0.1\nbound = true";
+        let err = SweepConfig::parse(summarizer).unwrap_err();
+        assert!(err.key == "tempconfig");
+        let text = format!("{}\n{}", summarizer, tempconfig.replace("= 900", "= 7000"));
+        let err = SweepConfig::parse(&text).unwrap_err();
+        assert!(err.key == "tempconfig[0].bin_duration_secs");
+        let text =
+            format!("{}\n{}", summarizer, tempconfig.replace("min_days = 2", "min_days = -2"));
+        let err = SweepConfig::parse(&text).unwrap_err();
+        assert!(err.key == "tempconfig[0].min_days");
+    }
+
+    #[test]
+    fn test_relationship_pairs() {
+        let text = r#"
+            [[relationship]]
+            primary = ["PeeringPrivate", "PeeringPaid"]
+            alternate = ["Transit"]
+
+            [[relationship]]
+            primary = ["Transit"]
+            alternate = ["Transit"]
+
+            [[summarizer]]
+            type = "minrtt50-rel"
+            thresh = [5, 10]
+            diff_ci = 10
+            bound = true
+
+            [[summarizer]]
+            type = "hdratio50-rel"
+            primary = 2
+            alternate = 5
+            thresh = 0.05
+            diff_ci = 0.2
+            bound = true
+
+            [[tempconfig]]
+            bin_duration_secs = 900
+            min_days = 2
+            min_frac_existing_bins = 0.6
+            min_frac_bins_with_alternate = 0.6
+            min_frac_valid_bins = 0.6
+            continuous_min_frac_shifted_bins = 0.75
+            diurnal_min_bad_bins = 4
+            diurnal_bad_bin_min_prob_shift = 0.5
+            uneventful_max_frac_shifted_bins = 0
+        "#;
+        let sweep = SweepConfig::parse(text).unwrap();
+        assert!(sweep.summarizers.len() == 5);
+        assert!(sweep.summarizers[0].params["primary"] == toml::Value::Integer(5));
+        assert!(sweep.summarizers[0].params["alternate"] == toml::Value::Integer(8));
+        assert!(sweep.summarizers[2].params["primary"] == toml::Value::Integer(8));
+        assert!(sweep.summarizers[4].params["primary"] == toml::Value::Integer(2));
+        let database = db::DB::default();
+        let summarizers = sweep.build_summarizers(&database).unwrap();
+        assert!(summarizers[4].prefix().starts_with("hdratio50--relationships-2-5--"));
+    }
+}
@@ -0,0 +1,27 @@
+use std::error::Error as StdError;
+use std::fmt;
+
+/// Error in a sweep configuration; `key` names the offending entry,
+/// e.g., `summarizer[2].thresh`.
+#[derive(Debug, PartialEq)]
+pub struct ConfigError {
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub(crate) fn new(key: &str, message: &str) -> ConfigError {
+        ConfigError {
+            key: key.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ConfigError {}: {}", self.key, self.message)
+    }
+}
+
+impl StdError for ConfigError {}
@@ -0,0 +1,351 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ipnet::IpNet;
+
+use crate::performance::db;
+use crate::performance::perfstats::{DBSummary, PathSummary, TemporalBehavior};
+
+/// Selects the `PathId`s whose timeseries are dumped.  A path is
+/// selected if it matches every non-empty criterion; values within a
+/// criterion are alternatives.
+#[derive(Clone, Debug, Default)]
+pub struct PathSelector {
+    /// Globs matched against the VIP metro; `*` matches any sequence of
+    /// characters and `?` matches one character.
+    pub metros: Vec<String>,
+    /// Prefixes that must cover the path's BGP prefix.
+    pub prefixes: Vec<IpNet>,
+    /// Two-letter client countries.
+    pub countries: Vec<String>,
+    pub classes: Vec<TemporalBehavior>,
+    /// Keeps only the matching paths with the most shifted bytes.
+    pub top_shifted_bytes: Option<usize>,
+    /// Explicit `PathId`s.
+    pub pathids: HashSet<db::PathId>,
+}
+
+/// Per-route columns, after the route's `r{i}_` prefix.  MinRTT P10
+/// and P25 are empty if not exported.
+const ROUTE_COLUMNS: [&str; 16] = [
+    "apm_route_num",
+    "peer_type",
+    "px_nexthops",
+    "minrtt_num_samples",
+    "minrtt_ms_p10",
+    "minrtt_ms_p10_ci_halfwidth",
+    "minrtt_ms_p25",
+    "minrtt_ms_p25_ci_halfwidth",
+    "minrtt_ms_p50",
+    "minrtt_ms_p50_ci_halfwidth",
+    "hdratio_num_samples",
+    "hdratio",
+    "hdratio_var",
+    "hdratio_p50",
+    "hdratio_p50_ci_halfwidth",
+    "hdratio_boot",
+];
+
+impl PathSelector {
+    /// Whether any criterion is set; nothing is dumped otherwise.
+    pub fn is_enabled(&self) -> bool {
+        !self.metros.is_empty()
+            || !self.prefixes.is_empty()
+            || !self.countries.is_empty()
+            || !self.classes.is_empty()
+            || self.top_shifted_bytes.is_some()
+            || !self.pathids.is_empty()
+    }
+
+    /// Reads explicit `PathId`s from a file with one `PathId::text` per
+    /// line.
+    pub fn load_pathids(&mut self, input: &Path) -> Result<(), io::Error> {
+        for line in fs::read_to_string(input)?.lines() {
+            match db::PathId::from_text(line) {
+                Some(pathid) => {
+                    self.pathids.insert(pathid);
+                }
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("could not parse PathId from line [{}]", line),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, pathid: &db::PathId, psum: &PathSummary) -> bool {
+        let country: String = pathid.client_country.iter().collect();
+        (self.metros.is_empty() || self.metros.iter().any(|g| glob_matches(g, &pathid.vip_metro)))
+            && (self.prefixes.is_empty()
+                || self.prefixes.iter().any(|p| p.contains(&pathid.bgp_ip_prefix)))
+            && (self.countries.is_empty() || self.countries.contains(&country))
+            && (self.classes.is_empty() || self.classes.contains(&psum.temporal_behavior))
+            && (self.pathids.is_empty() || self.pathids.contains(pathid))
+    }
+
+    /// Matching paths, sorted by `PathId`.
+    pub fn select<'a>(&self, dbsum: &'a DBSummary) -> Vec<(&'a db::PathId, &'a PathSummary)> {
+        let mut selected: Vec<(&db::PathId, &PathSummary)> = dbsum
+            .sorted_paths()
+            .into_iter()
+            .filter(|(pid, psum)| self.matches(pid, psum))
+            .collect();
+        if let Some(n) = self.top_shifted_bytes {
+            selected.sort_by_key(|(_pid, psum)| std::cmp::Reverse(psum.shifted_bytes));
+            selected.truncate(n);
+            selected.sort_unstable_by_key(|(pid, _psum)| *pid);
+        }
+        selected
+    }
+
+    /// Writes `timeseries/<path>.csv` for each selected path, with one
+    /// row per bin, and `timeseries/paths.csv` listing the files.
+    pub fn dump(&self, db: &db::DB, dbsum: &DBSummary, path: &Path) -> Result<(), io::Error> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let dir = path.join("timeseries");
+        fs::create_dir_all(&dir)?;
+        let mut index = csv::Writer::from_path(dir.join("paths.csv"))?;
+        index.write_record(["pathid", "file", "class", "shifted_bytes", "existing_bytes"])?;
+        for (pid, psum) in self.select(dbsum) {
+            let pinfo: &db::PathInfo = match db.pathid2info.get(pid) {
+                Some(pinfo) => pinfo,
+                None => continue,
+            };
+            let filename: String = format!("{}.csv", file_stem(pid));
+            dump_path(&dir.join(&filename), pinfo, psum)?;
+            index.write_record([
+                pid.text(),
+                filename,
+                format!("{:?}", psum.temporal_behavior),
+                psum.shifted_bytes.to_string(),
+                psum.existing_bytes.to_string(),
+            ])?;
+        }
+        index.flush()?;
+        Ok(())
+    }
+}
+
+fn dump_path(filepath: &Path, pinfo: &db::PathInfo, psum: &PathSummary) -> Result<(), io::Error> {
+    let mut wtr = csv::Writer::from_path(filepath)?;
+    let mut header: Vec<String> =
+        ["time", "bytes", "valid", "is_shifted", "diff_ci", "diff_ci_lb", "diff_ci_ub"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+    for i in 0..db::TimeBin::MAX_ROUTES {
+        header.extend(ROUTE_COLUMNS.iter().map(|c| format!("r{}_{}", i, c)));
+    }
+    wtr.write_record(&header)?;
+    for (time, bin) in pinfo.time2bin.iter() {
+        let mut row: Vec<String> = vec![time.to_string(), bin.bytes_acked_sum.to_string()];
+        match psum.time2binstats.get(time) {
+            Some(binstats) => row.extend(vec![
+                "true".to_string(),
+                binstats.is_shifted.to_string(),
+                binstats.diff_ci.to_string(),
+                binstats.diff_ci_lb.to_string(),
+                binstats.diff_ci_ub.to_string(),
+            ]),
+            None => row.extend(vec![
+                "false".to_string(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ]),
+        }
+        let percentile = |value: Option<(u16, u16)>| match value {
+            Some((value, halfwidth)) => vec![value.to_string(), halfwidth.to_string()],
+            None => vec![String::new(), String::new()],
+        };
+        for rtopt in bin.num2route.iter() {
+            match rtopt {
+                Some(rtinfo) => {
+                    row.extend(vec![
+                        rtinfo.apm_route_num.to_string(),
+                        format!("{:?}", rtinfo.peer_type),
+                        rtinfo.px_nexthops.to_string(),
+                        rtinfo.minrtt_num_samples.to_string(),
+                    ]);
+                    row.extend(percentile(rtinfo.minrtt_ms_p10));
+                    row.extend(percentile(rtinfo.minrtt_ms_p25));
+                    row.extend(vec![
+                        rtinfo.minrtt_ms_p50.to_string(),
+                        rtinfo.minrtt_ms_p50_ci_halfwidth.to_string(),
+                        rtinfo.hdratio_num_samples.to_string(),
+                        rtinfo.hdratio.to_string(),
+                        rtinfo.hdratio_var.to_string(),
+                        rtinfo.hdratio_p50.to_string(),
+                        rtinfo.hdratio_p50_ci_halfwidth.to_string(),
+                        rtinfo.hdratio_boot.to_string(),
+                    ]);
+                }
+                None => row.extend(ROUTE_COLUMNS.iter().map(|_| String::new())),
+            }
+        }
+        wtr.write_record(&row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// File name for `pathid`, e.g., `gru--1.0.0.0_24--SA--BR`.
+fn file_stem(pathid: &db::PathId) -> String {
+    pathid.text().replace('/', "_").replace(' ', "--")
+}
+
+/// Matches `text` against a glob where `*` matches any sequence of
+/// characters and `?` matches one character.
+fn glob_matches(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut g, mut t) = (0, 0);
+    // Position after the last `*` and the text position it matched up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if g < glob.len() && (glob[g] == '?' || glob[g] == text[t]) {
+            g += 1;
+            t += 1;
+        } else if g < glob.len() && glob[g] == '*' {
+            g += 1;
+            backtrack = Some((g, t));
+        } else if let Some((bg, bt)) = backtrack {
+            g = bg;
+            t = bt + 1;
+            backtrack = Some((bg, bt + 1));
+        } else {
+            return false;
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::performance::perfstats::TemporalConfig;
+    use crate::performance::summarizers::opportunity::MinRtt50ImprovementSummarizer;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("gru", "gru"));
+        assert!(glob_matches("g*", "gru"));
+        assert!(glob_matches("*u", "gru"));
+        assert!(glob_matches("g?u", "gru"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("g*r*u", "gxruru"));
+        assert!(!glob_matches("g?", "gru"));
+        assert!(!glob_matches("mia*", "gru"));
+    }
+
+    #[test]
+    fn test_select_and_dump() -> Result<(), Box<dyn std::error::Error>> {
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        let tempconfig = TemporalConfig {
+            bin_duration_secs: 900,
+            min_days: 1,
+            min_frac_existing_bins: 0.0,
+            min_frac_bins_with_alternate: 0.0,
+            min_frac_valid_bins: 0.0,
+            continuous_min_frac_shifted_bins: 0.8,
+            diurnal_min_bad_bins: 4,
+            diurnal_bad_bin_min_prob_shift: 0.5,
+            diurnal_permutations: 0,
+            diurnal_max_pvalue: 1.0,
+            uneventful_max_frac_shifted_bins: 0.2,
+            weekly_min_weeks: 0,
+            weekly_min_autocorr: 1.0,
+            shift_enter_min_bins: 1,
+            shift_exit_min_bins: 1,
+        };
+        let mut database = db::DB::default();
+        for (metro, prefix, pri_minrtt_p50) in
+            [("gru", "1.0.0.0/24", 60), ("gig", "1.0.1.0/24", 70), ("mia", "2.0.0.0/24", 51)].iter()
+        {
+            let mut pid: db::PathId = db::tests::make_path_id();
+            pid.vip_metro = metro.to_string();
+            pid.bgp_ip_prefix = prefix.parse()?;
+            let mut time2bin: BTreeMap<u64, db::TimeBin> = BTreeMap::new();
+            for time in (0..8 * 900).step_by(900) {
+                time2bin.insert(time, db::TimeBin::mock_minrtt_p50(time, *pri_minrtt_p50, 50, 1));
+            }
+            database.insert(pid, time2bin);
+        }
+        let dbsum = DBSummary::build(&database, &summarizer, &tempconfig);
+        let metros = |selector: &PathSelector| -> Vec<String> {
+            selector.select(&dbsum).iter().map(|(pid, _)| pid.vip_metro.clone()).collect()
+        };
+
+        let mut selector = PathSelector {
+            metros: vec!["g*".to_string()],
+            ..Default::default()
+        };
+        assert!(metros(&selector) == vec!["gig", "gru"]);
+        selector.prefixes = vec!["1.0.0.0/23".parse()?];
+        assert!(metros(&selector) == vec!["gig", "gru"]);
+        selector.prefixes = vec!["1.0.1.0/24".parse()?];
+        assert!(metros(&selector) == vec!["gig"]);
+        selector.countries = vec!["AR".to_string()];
+        assert!(metros(&selector).is_empty());
+
+        let selector = PathSelector {
+            classes: vec![TemporalBehavior::Continuous],
+            ..Default::default()
+        };
+        assert!(metros(&selector) == vec!["gig", "gru"]);
+        let selector = PathSelector {
+            countries: vec!["BR".to_string()],
+            top_shifted_bytes: Some(1),
+            ..Default::default()
+        };
+        assert!(metros(&selector).len() == 1);
+        assert!(!PathSelector::default().is_enabled());
+
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("fbperf-test-timeseries-{}", std::process::id()));
+        let selector = PathSelector {
+            metros: vec!["gru".to_string()],
+            ..Default::default()
+        };
+        selector.dump(&database, &dbsum, &dir)?;
+        let index = fs::read_to_string(dir.join("timeseries").join("paths.csv"))?;
+        assert!(index.lines().count() == 2);
+        let text =
+            fs::read_to_string(dir.join("timeseries").join("gru--1.0.0.0_24--Unknown--BR.csv"))?;
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines.len() == 9);
+        assert!(
+            lines[0].starts_with("time,bytes,valid,is_shifted,diff_ci,diff_ci_lb,diff_ci_ub,r0_")
+        );
+        assert!(lines[1].starts_with("0,1000,true,true,10,"));
+        let fields: Vec<&str> = lines[1].split(',').collect();
+        assert!(fields.len() == 7 + ROUTE_COLUMNS.len() * db::TimeBin::MAX_ROUTES);
+        let header: Vec<&str> = lines[0].split(',').collect();
+        for column in
+            ["r0_minrtt_ms_p10", "r0_minrtt_ms_p25", "r0_hdratio", "r0_hdratio_var"].iter()
+        {
+            assert!(header.contains(column), "{}", column);
+        }
+        let field = |column: &str| fields[header.iter().position(|c| *c == column).unwrap()];
+        assert!(field("r0_minrtt_ms_p50") == "60");
+        assert!(field("r0_minrtt_ms_p25").is_empty());
+        assert!(fields[fields.len() - 1].is_empty());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
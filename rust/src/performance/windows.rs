@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::performance::db;
+use crate::performance::perfstats::{
+    PathSummary, TemporalBehavior, TemporalConfig, TimeBinSummarizer, TransitionMatrix,
+};
+
+/// Windows of `window_secs` seconds starting every `step_secs` seconds.
+/// Windows are tumbling when `step_secs == window_secs` and sliding
+/// when `step_secs < window_secs`.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct WindowConfig {
+    pub window_secs: u64,
+    pub step_secs: u64,
+}
+
+/// Per-path `TemporalBehavior` over consecutive time windows.
+#[derive(Default)]
+pub struct WindowedSummary {
+    /// (start, end) of each window, end exclusive.
+    pub windows: Vec<(u64, u64)>,
+    pub pathid2behaviors: HashMap<Arc<db::PathId>, Vec<TemporalBehavior>>,
+    /// Transitions between consecutive windows; bytes are the path's
+    /// traffic in the later window.
+    pub transitions: TransitionMatrix,
+}
+
+impl WindowConfig {
+    pub fn prefix(&self) -> String {
+        format!("windows--length-{}--step-{}", self.window_secs, self.step_secs)
+    }
+
+    /// Computes the full windows that fit in [first, last].
+    fn compute_windows(&self, first: u64, last: u64, bin_duration_secs: u32) -> Vec<(u64, u64)> {
+        assert!(self.step_secs > 0);
+        let mut windows: Vec<(u64, u64)> = Vec::new();
+        let mut start: u64 = first;
+        while start + self.window_secs <= last + u64::from(bin_duration_secs) {
+            windows.push((start, start + self.window_secs));
+            start += self.step_secs;
+        }
+        windows
+    }
+}
+
+impl WindowedSummary {
+    pub fn build(
+        db: &db::DB,
+        summarizer: &dyn TimeBinSummarizer,
+        tempconfig: &TemporalConfig,
+        wincfg: &WindowConfig,
+    ) -> WindowedSummary {
+        let mut winsum = WindowedSummary::default();
+        let first: Option<u64> =
+            db.pathid2info.values().filter_map(|pinfo| pinfo.time2bin.keys().next()).min().copied();
+        let last: Option<u64> = db
+            .pathid2info
+            .values()
+            .filter_map(|pinfo| pinfo.time2bin.keys().next_back())
+            .max()
+            .copied();
+        if let (Some(first), Some(last)) = (first, last) {
+            winsum.windows = wincfg.compute_windows(first, last, tempconfig.bin_duration_secs);
+        }
+        let window_bins: u32 =
+            (wincfg.window_secs / u64::from(tempconfig.bin_duration_secs)) as u32;
+
+        for (pid, pinfo) in &db.pathid2info {
+            let mut behaviors: Vec<TemporalBehavior> = Vec::with_capacity(winsum.windows.len());
+            let mut prev: Option<TemporalBehavior> = None;
+            for &(start, end) in winsum.windows.iter() {
+                let psum = PathSummary::build_from_bins(
+                    pid,
+                    pinfo.time2bin.range(start..end),
+                    window_bins,
+                    summarizer,
+                    tempconfig,
+                );
+                if let Some(prev) = prev {
+                    let bytes: u128 = pinfo
+                        .time2bin
+                        .range(start..end)
+                        .map(|(_, bin)| u128::from(bin.bytes_acked_sum))
+                        .sum();
+                    winsum.transitions.add(prev, psum.temporal_behavior, bytes);
+                }
+                prev = Some(psum.temporal_behavior);
+                behaviors.push(psum.temporal_behavior);
+            }
+            winsum.pathid2behaviors.insert(Arc::clone(pid), behaviors);
+        }
+        winsum
+    }
+
+    pub fn dump(&self, path: &Path) -> Result<(), io::Error> {
+        std::fs::create_dir_all(path)?;
+        self.dump_windows(path)?;
+        self.dump_timelines(path)?;
+        self.transitions.dump(path, "window-transitions")?;
+        Ok(())
+    }
+
+    fn dump_windows(&self, path: &Path) -> Result<(), io::Error> {
+        let mut filepath = path.to_path_buf();
+        filepath.push("windows.txt");
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(filepath)?;
+        let mut bw = io::BufWriter::new(file);
+        for (i, (start, end)) in self.windows.iter().enumerate() {
+            writeln!(bw, "{} {} {}", i, start, end)?;
+        }
+        Ok(())
+    }
+
+    /// One line per `PathId` with its behavior in each window.
+    fn dump_timelines(&self, path: &Path) -> Result<(), io::Error> {
+        let mut filepath = path.to_path_buf();
+        filepath.push("window-timelines.txt");
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(filepath)?;
+        let mut bw = io::BufWriter::new(file);
+        let mut pids: Vec<&Arc<db::PathId>> = self.pathid2behaviors.keys().collect();
+        pids.sort_unstable();
+        for pid in pids {
+            let behaviors: &Vec<TemporalBehavior> = &self.pathid2behaviors[pid];
+            write!(bw, "{}", pid.text())?;
+            for behavior in behaviors {
+                write!(bw, " {:?}", behavior)?;
+            }
+            writeln!(bw)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::performance::summarizers::opportunity::MinRtt50ImprovementSummarizer;
+
+    const BIN_DURATION_SECS: u64 = 900;
+    const TEMPCONFIG: TemporalConfig = TemporalConfig {
+        bin_duration_secs: 900,
+        min_days: 7,
+        min_frac_existing_bins: 1.0,
+        min_frac_bins_with_alternate: 1.0,
+        min_frac_valid_bins: 0.8,
+        continuous_min_frac_shifted_bins: 0.8,
+        diurnal_min_bad_bins: 24,
+        diurnal_bad_bin_min_prob_shift: 0.8,
+        diurnal_permutations: 0,
+        diurnal_max_pvalue: 1.0,
+        uneventful_max_frac_shifted_bins: 0.20,
+        weekly_min_weeks: 0,
+        weekly_min_autocorr: 1.0,
+        shift_enter_min_bins: 1,
+        shift_exit_min_bins: 1,
+    };
+
+    #[test]
+    fn test_compute_windows() {
+        let tumbling = WindowConfig {
+            window_secs: 7 * 86400,
+            step_secs: 7 * 86400,
+        };
+        let last: u64 = 21 * 86400 - BIN_DURATION_SECS;
+        let windows = tumbling.compute_windows(0, last, 900);
+        assert!(windows == vec![(0, 7 * 86400), (7 * 86400, 14 * 86400), (14 * 86400, 21 * 86400)]);
+        let sliding = WindowConfig {
+            window_secs: 7 * 86400,
+            step_secs: 86400,
+        };
+        let windows = sliding.compute_windows(0, last, 900);
+        assert!(windows.len() == 15);
+        assert!(windows[14] == (14 * 86400, 21 * 86400));
+    }
+
+    #[test]
+    fn test_windowed_summary() {
+        let summarizer = MinRtt50ImprovementSummarizer {
+            minrtt50_min_improv: 5.0,
+            max_minrtt50_diff_ci_halfwidth: 5.0,
+            max_hdratio50_diff_ci_halfwidth: 0.4,
+            compare_lower_bound: false,
+        };
+        // No opportunity in the first week, continuous opportunity in
+        // the second and third weeks.
+        let mut time2bin: BTreeMap<u64, db::TimeBin> = BTreeMap::new();
+        for time in (0..21 * 86400).step_by(BIN_DURATION_SECS as usize) {
+            let pri_minrtt_p50 = if time < 7 * 86400 {
+                51
+            } else {
+                55
+            };
+            time2bin.insert(time, db::TimeBin::mock_minrtt_p50(time, pri_minrtt_p50, 50, 1));
+        }
+        let mut database: db::DB = db::DB::default();
+        let pid: db::PathId = db::tests::make_path_id();
+        assert!(database.insert(pid.clone(), time2bin).is_none());
+
+        let wincfg = WindowConfig {
+            window_secs: 7 * 86400,
+            step_secs: 7 * 86400,
+        };
+        let winsum = WindowedSummary::build(&database, &summarizer, &TEMPCONFIG, &wincfg);
+        assert!(winsum.windows.len() == 3);
+        assert!(
+            winsum.pathid2behaviors[&pid]
+                == vec![
+                    TemporalBehavior::Uneventful,
+                    TemporalBehavior::Continuous,
+                    TemporalBehavior::Continuous
+                ]
+        );
+        let uneventful = TemporalBehavior::Uneventful as usize;
+        let continuous = TemporalBehavior::Continuous as usize;
+        assert!(winsum.transitions.paths[uneventful][continuous] == 1);
+        assert!(winsum.transitions.paths[continuous][continuous] == 1);
+        assert!(winsum.transitions.paths.iter().flatten().sum::<u64>() == 2);
+        let week_bytes = u128::from(db::TimeBin::MOCK_TOTAL_BYTES * 7 * 86400 / BIN_DURATION_SECS);
+        assert!(winsum.transitions.bytes[uneventful][continuous] == week_bytes);
+
+        // Sliding windows see the path go through Episodic while the
+        // window overlaps both periods.
+        let wincfg = WindowConfig {
+            window_secs: 7 * 86400,
+            step_secs: 86400,
+        };
+        let winsum = WindowedSummary::build(&database, &summarizer, &TEMPCONFIG, &wincfg);
+        assert!(winsum.windows.len() == 15);
+        let behaviors = &winsum.pathid2behaviors[&pid];
+        assert!(behaviors[0] == TemporalBehavior::Uneventful);
+        assert!(behaviors[3] == TemporalBehavior::Episodic);
+        assert!(behaviors[7] == TemporalBehavior::Continuous);
+    }
+}
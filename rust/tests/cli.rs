@@ -0,0 +1,219 @@
+//! Runs `perfstats` on a bundled fixture to check how command-line
+//! options are validated and applied.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+fn workdir(name: &str) -> PathBuf {
+    let mut workdir = std::env::temp_dir();
+    workdir.push(format!("fbperf-test-cli-{}-{}", name, std::process::id()));
+    workdir
+}
+
+fn run_perfstats(outdir: &Path, args: &[&str]) -> Result<Output, Box<dyn Error>> {
+    let output = Command::new(env!("CARGO_BIN_EXE_perfstats"))
+        .arg("--outdir")
+        .arg(outdir)
+        .arg("--threads")
+        .arg("1")
+        .args(args)
+        .arg(tests_dir().join("half-opp--no-deg.csv.gz"))
+        .output()?;
+    Ok(output)
+}
+
+#[test]
+fn test_window_step_validation() -> Result<(), Box<dyn Error>> {
+    let outdir: PathBuf = workdir("windows");
+    let output = run_perfstats(&outdir, &["--window-days", "2", "--window-step-days", "0"])?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("--window-step-days must be at least 1"), "{}", stderr);
+
+    let output = run_perfstats(&outdir, &["--window-days", "2", "--window-step-days", "3"])?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("--window-step-days must not exceed --window-days"), "{}", stderr);
+    Ok(())
+}
+
+#[test]
+fn test_sensitivity_ranges() -> Result<(), Box<dyn Error>> {
+    let outdir: PathBuf = workdir("sensitivity");
+    let summarizer = "minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true";
+    let output = run_perfstats(
+        &outdir,
+        &[
+            "--summarizer",
+            summarizer,
+            "--sensitivity",
+            "--sensitivity-range",
+            "diurnal_min_bad_bins=1,4,8",
+            "--sensitivity-range",
+            "min_days=3",
+        ],
+    )?;
+    assert!(output.status.success(), "{}", String::from_utf8(output.stderr)?);
+    let mut dir: PathBuf = outdir.join("sensitivity");
+    dir.push(fs::read_dir(&dir)?.next().unwrap()?.file_name());
+    let summary: String = fs::read_to_string(dir.join("sensitivity-summary.txt"))?;
+    let names: Vec<&str> = summary.lines().map(|l| l.split(' ').next().unwrap()).collect();
+    assert!(names == ["diurnal_min_bad_bins=1", "diurnal_min_bad_bins=8", "min_days=3"]);
+
+    let output = run_perfstats(&outdir, &["--sensitivity-range", "bin_duration_secs=300"])?;
+    assert!(!output.status.success());
+    fs::remove_dir_all(&outdir)?;
+    Ok(())
+}
+
+fn find_manifests(
+    dir: &Path,
+    manifests: &mut Vec<serde_json::Value>,
+) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(dir)? {
+        let path: PathBuf = entry?.path();
+        if path.is_dir() {
+            find_manifests(&path, manifests)?;
+        } else if path.file_name().unwrap() == "manifest.json" {
+            manifests.push(serde_json::from_str(&fs::read_to_string(&path)?)?);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_merge_and_compare_manifests() -> Result<(), Box<dyn Error>> {
+    let outdir: PathBuf = workdir("manifests");
+    let reference = "minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true";
+    let other = "minrtt50-opp:thresh=10.5,diff_ci=10,hdratio_diff_ci=0.1,bound=true";
+    let output =
+        run_perfstats(&outdir.join("run"), &["--save-summaries", "--summarizer", reference])?;
+    assert!(output.status.success(), "{}", String::from_utf8(output.stderr)?);
+    let mut summary: PathBuf = outdir.join("run/summaries");
+    summary.push(fs::read_dir(&summary)?.next().unwrap()?.file_name());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_perfstats"))
+        .arg("--outdir")
+        .arg(outdir.join("merge"))
+        .arg("merge")
+        .arg(&summary)
+        .output()?;
+    assert!(output.status.success(), "{}", String::from_utf8(output.stderr)?);
+    let mut manifests: Vec<serde_json::Value> = Vec::new();
+    find_manifests(&outdir.join("merge"), &mut manifests)?;
+    assert!(manifests.len() == 3);
+    for manifest in manifests.iter() {
+        assert!(manifest["kind"] == "summary");
+        assert!(manifest["summarizer"]["type"] == "minrtt50-opp");
+        assert!(manifest["summarizer"]["params"]["thresh"] == 5.0);
+    }
+    assert!(outdir.join("merge/index.json").exists());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_perfstats"))
+        .arg("--outdir")
+        .arg(outdir.join("compare"))
+        .arg(tests_dir().join("half-opp--no-deg.csv.gz"))
+        .args(["compare", "--reference", reference, "--other", other])
+        .output()?;
+    assert!(output.status.success(), "{}", String::from_utf8(output.stderr)?);
+    let mut manifests: Vec<serde_json::Value> = Vec::new();
+    find_manifests(&outdir.join("compare"), &mut manifests)?;
+    assert!(manifests.len() == 3);
+    for manifest in manifests.iter() {
+        assert!(manifest["kind"] == "comparison");
+        assert!(manifest["summarizer"]["params"]["thresh"] == 10.5);
+        assert!(manifest["reference"]["params"]["thresh"] == 5.0);
+    }
+    assert!(outdir.join("compare/index.json").exists());
+    fs::remove_dir_all(&outdir)?;
+    Ok(())
+}
+
+#[test]
+fn test_merge_mismatched_summaries() -> Result<(), Box<dyn Error>> {
+    let outdir: PathBuf = workdir("mismatch");
+    let mut summaries: Vec<PathBuf> = Vec::new();
+    for (run, thresh) in ["run1", "run2"].iter().zip(["5", "5.001"].iter()) {
+        let spec =
+            format!("minrtt50-opp:thresh={},diff_ci=10,hdratio_diff_ci=0.1,bound=true", thresh);
+        let output =
+            run_perfstats(&outdir.join(run), &["--save-summaries", "--summarizer", &spec])?;
+        assert!(output.status.success(), "{}", String::from_utf8(output.stderr)?);
+        let mut summary: PathBuf = outdir.join(run).join("summaries");
+        summary.push(fs::read_dir(&summary)?.next().unwrap()?.file_name());
+        summaries.push(summary);
+    }
+    // Both thresholds round to the same prefix.
+    assert!(summaries[0].file_name() == summaries[1].file_name());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_perfstats"))
+        .arg("--outdir")
+        .arg(outdir.join("merge"))
+        .arg("merge")
+        .args(&summaries)
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    let prefix: &str = "minrtt50--opp--bound-true--diff-thresh-5.00--diff-ci-10.00";
+    assert!(stderr.matches(prefix).count() >= 3, "{}", stderr);
+    assert!(stderr.contains("thresh=5.001)"), "{}", stderr);
+    assert!(stderr.contains("thresh=5.0)"), "{}", stderr);
+    fs::remove_dir_all(&outdir)?;
+    Ok(())
+}
+
+#[test]
+fn test_composite_summarizers() -> Result<(), Box<dyn Error>> {
+    let outdir: PathBuf = workdir("composite");
+    let opp = "minrtt50-opp:thresh=5,diff_ci=10,hdratio_diff_ci=0.1,bound=true";
+    let inverted =
+        format!("inverted:inner=[filter-continent:inner=[{}],continent=SA],thresh=5", opp);
+    let and =
+        format!("and:left=[{}],right=[hdratio50-opp:thresh=0.05,diff_ci=0.1,bound=true]", opp);
+    let output = run_perfstats(
+        &outdir.join("run"),
+        &["--save-summaries", "--summarizer", &inverted, "--summarizer", &and],
+    )?;
+    assert!(output.status.success(), "{}", String::from_utf8(output.stderr)?);
+
+    let mut manifests: Vec<serde_json::Value> = Vec::new();
+    find_manifests(&outdir.join("run"), &mut manifests)?;
+    assert!(manifests.len() == 6);
+    let inverted_manifest =
+        manifests.iter().find(|m| m["summarizer"]["type"] == "inverted").unwrap();
+    let prefix: &str = inverted_manifest["summarizer"]["prefix"].as_str().unwrap();
+    assert!(prefix.ends_with("--filter-continent-SA--inverted--diff-thresh-5.00"), "{}", prefix);
+    let params = &inverted_manifest["summarizer"]["params"];
+    assert!(params["inner"]["type"] == "filter-continent");
+    assert!(params["inner"]["continent"] == "SA");
+    assert!(params["inner"]["inner"]["thresh"] == 5.0);
+    let dir: &str = inverted_manifest["dir"].as_str().unwrap();
+    assert!(outdir.join("run").join(dir).join("path-summaries.txt").exists());
+
+    let summaries: Vec<PathBuf> = fs::read_dir(outdir.join("run/summaries"))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    assert!(summaries.len() == 2);
+    let output = Command::new(env!("CARGO_BIN_EXE_perfstats"))
+        .arg("--outdir")
+        .arg(outdir.join("merge"))
+        .arg("merge")
+        .args(&summaries)
+        .output()?;
+    assert!(output.status.success(), "{}", String::from_utf8(output.stderr)?);
+    let mut merged: Vec<serde_json::Value> = Vec::new();
+    find_manifests(&outdir.join("merge"), &mut merged)?;
+    assert!(merged.len() == 6);
+    for manifest in merged.iter() {
+        let original = manifests.iter().find(|m| m["dir"] == manifest["dir"]).unwrap();
+        assert!(manifest["summarizer"] == original["summarizer"]);
+    }
+    fs::remove_dir_all(&outdir)?;
+    Ok(())
+}
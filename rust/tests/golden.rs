@@ -0,0 +1,120 @@
+//! Runs `perfstats` on every `tests/*.csv.gz` fixture with the
+//! summarizers and temporal config in `tests/golden.toml` and compares
+//! each output file against `tests/golden/<fixture>/`.
+//!
+//! Fixtures are generated by `helpers/make-test-csv.py` and compressed
+//! with `gzip -n`.  After an intentional change to the outputs, rerun
+//! with `FBPERF_UPDATE_GOLDEN=1 cargo test --test golden` and review the
+//! diff of `tests/golden`.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Files that record when and where a run happened.
+const SKIPPED_FILES: [&str; 2] = ["manifest.json", "index.json"];
+
+fn tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+fn fixtures() -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut fixtures: Vec<PathBuf> = Vec::new();
+    for entry in fs::read_dir(tests_dir())? {
+        let path: PathBuf = entry?.path();
+        if path.to_str().unwrap().ends_with(".csv.gz") {
+            fixtures.push(path);
+        }
+    }
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+/// Paths of all files under `dir`, relative to `dir` and sorted.
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut pending: Vec<PathBuf> = vec![dir.to_path_buf()];
+    while let Some(curr) = pending.pop() {
+        for entry in fs::read_dir(&curr)? {
+            let path: PathBuf = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if !SKIPPED_FILES.contains(&path.file_name().unwrap().to_str().unwrap()) {
+                files.push(path.strip_prefix(dir)?.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn run_perfstats(fixture: &Path, outdir: &Path) -> Result<(), Box<dyn Error>> {
+    let status = Command::new(env!("CARGO_BIN_EXE_perfstats"))
+        .arg("--outdir")
+        .arg(outdir)
+        .arg("--config")
+        .arg(tests_dir().join("golden.toml"))
+        .arg("--formats")
+        .arg("text,csv")
+        .arg("--threads")
+        .arg("1")
+        .arg(fixture)
+        .status()?;
+    assert!(status.success(), "perfstats failed on {:?}", fixture);
+    Ok(())
+}
+
+fn copy_files(src: &Path, dst: &Path, files: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    if dst.exists() {
+        fs::remove_dir_all(dst)?;
+    }
+    for file in files {
+        let target: PathBuf = dst.join(file);
+        fs::create_dir_all(target.parent().unwrap())?;
+        fs::copy(src.join(file), target)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_golden_outputs() -> Result<(), Box<dyn Error>> {
+    let update: bool = std::env::var_os("FBPERF_UPDATE_GOLDEN").is_some();
+    let mut workdir = std::env::temp_dir();
+    workdir.push(format!("fbperf-test-golden-{}", std::process::id()));
+
+    let fixtures: Vec<PathBuf> = fixtures()?;
+    assert!(!fixtures.is_empty());
+    for fixture in fixtures.iter() {
+        let name: &str = fixture.file_name().unwrap().to_str().unwrap();
+        let name: &str = name.trim_end_matches(".csv.gz");
+        let outdir: PathBuf = workdir.join(name);
+        run_perfstats(fixture, &outdir)?;
+        let files: Vec<PathBuf> = list_files(&outdir)?;
+
+        let golden: PathBuf = tests_dir().join("golden").join(name);
+        if update {
+            copy_files(&outdir, &golden, &files)?;
+            continue;
+        }
+        let golden_files: Vec<PathBuf> = list_files(&golden)?;
+        assert!(
+            files == golden_files,
+            "{}: output files differ from golden files, set FBPERF_UPDATE_GOLDEN=1 to update",
+            name
+        );
+        for file in files.iter() {
+            let output: Vec<u8> = fs::read(outdir.join(file))?;
+            let expected: Vec<u8> = fs::read(golden.join(file))?;
+            assert!(
+                output == expected,
+                "{}: {:?} differs from golden file, set FBPERF_UPDATE_GOLDEN=1 to update",
+                name,
+                file
+            );
+        }
+    }
+
+    fs::remove_dir_all(&workdir)?;
+    Ok(())
+}